@@ -75,6 +75,7 @@ pub enum Task {
     Attack,
     Idle,
     Spawn,
+    Graze,
 }
 
 #[derive(Component, Clone, Debug)]
@@ -0,0 +1,386 @@
+use rltk::Point;
+use shipyard::{AllStorages, EntityId, Get, UniqueView, UniqueViewMut, View};
+
+use crate::{
+    components::{Actor, ActorType, Edible, Hunger, Pack, Position, Spawner, Vision},
+    map::{Map, TileType},
+    spatial::SpatialIndex,
+    uniques::{Turn, RNG},
+    utils::normalize,
+};
+
+use super::{
+    decisions::{Action, Consideration, ConsiderationParam, Intent, ResponseCurveType, Target, Task},
+    squads::{nearest_open_neighbor, nearest_walkable},
+};
+
+// hunger removed by a turn of grazing
+pub const GRAZE_NUTRITION: i32 = 5;
+
+// prey bolts once a predator is this close
+const FLEE_RADIUS: f32 = 5.0;
+const FLEE_DISTANCE: i32 = 8;
+
+// wolves hunt above this much hunger and turn on villagers above the second
+const HUNT_HUNGER: f32 = 0.3;
+const DESPERATE_HUNGER: f32 = 0.8;
+
+// packs laze around within this distance of their den
+const DEN_RADIUS: f32 = 6.0;
+
+fn hunger_ratio(hunger: &Hunger) -> f32 {
+    hunger.hunger as f32 / hunger.max as f32
+}
+
+fn is_predator(actor: &Actor) -> bool {
+    actor.atype == ActorType::Wolf || actor.atype == ActorType::Villager || actor.atype == ActorType::Player
+}
+
+pub fn get_graze_actions(store: &AllStorages, id: EntityId) -> Vec<Action> {
+    let turn = store.borrow::<UniqueView<Turn>>().unwrap();
+    let map = store.borrow::<UniqueView<Map>>().unwrap();
+    let index = store.borrow::<UniqueView<SpatialIndex>>().unwrap();
+    let mut rng = store.borrow::<UniqueViewMut<RNG>>().unwrap();
+    let vpos = store.borrow::<View<Position>>().unwrap();
+    let vactor = store.borrow::<View<Actor>>().unwrap();
+    let vvs = store.borrow::<View<Vision>>().unwrap();
+    let vhunger = store.borrow::<View<Hunger>>().unwrap();
+
+    let pos = if let Ok(pos) = vpos.get(id) {
+        pos.ps[0]
+    } else {
+        return vec![];
+    };
+    let vs = if let Ok(vs) = vvs.get(id) {
+        vs
+    } else {
+        return vec![];
+    };
+    let hunger = if let Ok(hunger) = vhunger.get(id) {
+        hunger_ratio(hunger)
+    } else {
+        0.0
+    };
+
+    let mut potential_actions: Vec<Action> = vec![];
+
+    // run from the closest predator
+    let mut threat: Option<(Point, f32)> = None;
+    for (e, p) in index.within_with(&vactor, pos, vs.range as f32) {
+        if !vs.is_visible(p) || !vactor.get(e).map_or(false, is_predator) {
+            continue;
+        }
+
+        let dist = rltk::DistanceAlg::Pythagoras.distance2d(pos, p);
+        if threat.map_or(true, |(_, d)| dist < d) {
+            threat = Some((p, dist));
+        }
+    }
+
+    if let Some((tpos, dist)) = threat {
+        let goal = Point {
+            x: (pos.x + normalize(pos.x - tpos.x) * FLEE_DISTANCE).clamp(1, map.width - 2),
+            y: (pos.y + normalize(pos.y - tpos.y) * FLEE_DISTANCE).clamp(1, map.height - 2),
+        };
+
+        potential_actions.push(Action {
+            intent: Intent {
+                name: "flee predator".to_string(),
+                task: Task::MoveTo,
+                target: vec![Target::from(nearest_walkable(&map, goal))],
+                turn: *turn,
+            },
+            cons: vec![Consideration::new(
+                "Distance to predator".to_string(),
+                dist,
+                ConsiderationParam {
+                    t: ResponseCurveType::LessThan,
+                    m: FLEE_RADIUS,
+                    k: 1.0,
+                    c: 0.0,
+                    b: 0.0,
+                },
+            )],
+            priority: 3.0,
+        });
+    }
+
+    if map.tiles[map.point_idx(pos)] == TileType::Grass {
+        potential_actions.push(Action {
+            intent: Intent {
+                name: "graze".to_string(),
+                task: Task::Graze,
+                target: vec![Target::from(pos)],
+                turn: *turn,
+            },
+            cons: vec![Consideration::new(
+                "hunger".to_string(),
+                hunger,
+                ConsiderationParam {
+                    t: ResponseCurveType::Linear,
+                    m: 1.0,
+                    k: 1.0,
+                    c: 0.0,
+                    b: 0.0,
+                },
+            )],
+            priority: 1.0,
+        });
+    } else {
+        let mut grass: Option<(Point, f32)> = None;
+        for p in vs.visible_tiles.iter() {
+            let idx = map.point_idx(*p);
+            let dist = rltk::DistanceAlg::Pythagoras.distance2d(pos, *p);
+            if map.tiles[idx] == TileType::Grass && !map.blocked[idx] && grass.map_or(true, |(_, d)| dist < d) {
+                grass = Some((*p, dist));
+            }
+        }
+
+        if let Some((gpos, _)) = grass {
+            potential_actions.push(Action {
+                intent: Intent {
+                    name: "find grass".to_string(),
+                    task: Task::MoveTo,
+                    target: vec![Target::from(gpos)],
+                    turn: *turn,
+                },
+                cons: vec![Consideration::new(
+                    "hunger".to_string(),
+                    hunger,
+                    ConsiderationParam {
+                        t: ResponseCurveType::Linear,
+                        m: 0.8,
+                        k: 1.0,
+                        c: 0.0,
+                        b: 0.0,
+                    },
+                )],
+                priority: 1.0,
+            });
+        }
+    }
+
+    // amble about when there's nothing better to do
+    if !vs.visible_tiles.is_empty() {
+        let p = vs.visible_tiles[rng.0.range(0, vs.visible_tiles.len())];
+        let idx = map.point_idx(p);
+        if !map.blocked[idx] {
+            potential_actions.push(Action {
+                intent: Intent {
+                    name: "wander".to_string(),
+                    task: Task::MoveTo,
+                    target: vec![Target::from(p)],
+                    turn: *turn,
+                },
+                cons: vec![Consideration::new(
+                    "baseline".to_string(),
+                    1.0,
+                    ConsiderationParam::new_const(0.15),
+                )],
+                priority: 1.0,
+            });
+        }
+    }
+
+    potential_actions
+}
+
+pub fn get_hunt_actions(store: &AllStorages, id: EntityId) -> Vec<Action> {
+    let turn = store.borrow::<UniqueView<Turn>>().unwrap();
+    let map = store.borrow::<UniqueView<Map>>().unwrap();
+    let vpos = store.borrow::<View<Position>>().unwrap();
+    let vactor = store.borrow::<View<Actor>>().unwrap();
+    let vvs = store.borrow::<View<Vision>>().unwrap();
+    let vhunger = store.borrow::<View<Hunger>>().unwrap();
+    let vedible = store.borrow::<View<Edible>>().unwrap();
+    let vpack = store.borrow::<View<Pack>>().unwrap();
+    let vspawner = store.borrow::<View<Spawner>>().unwrap();
+
+    let pos = if let Ok(pos) = vpos.get(id) {
+        pos.ps[0]
+    } else {
+        return vec![];
+    };
+    let hunger = if let Ok(hunger) = vhunger.get(id) {
+        hunger_ratio(hunger)
+    } else {
+        1.0
+    };
+
+    // packs share what they see, lone wolves rely on themselves
+    let den = vpack.get(id).ok().map(|p| p.den);
+    let pack: Vec<EntityId> = match den.and_then(|d| vspawner.get(d).ok()) {
+        Some(spawner) => spawner
+            .spawned
+            .iter()
+            .filter(|e| vpos.get(**e).is_ok())
+            .copied()
+            .collect(),
+        None => vec![id],
+    };
+    // the eldest wolf still alive leads
+    let leader = pack.first().copied().unwrap_or(id);
+    let leader_pos = vpos.get(leader).map_or(pos, |p| p.ps[0]);
+
+    let mut prey: Vec<(EntityId, Point)> = vec![];
+    let mut intruders: Vec<(EntityId, Point)> = vec![];
+    for m in pack.iter() {
+        if let Ok(vs) = vvs.get(*m) {
+            for p in vs.visible_tiles.iter() {
+                for e in map.tile_content[map.point_idx(*p)].iter() {
+                    if let Ok(actor) = vactor.get(*e) {
+                        if vedible.get(*e).is_ok() && actor.atype != ActorType::Wolf {
+                            if !prey.iter().any(|(pe, _)| pe == e) {
+                                prey.push((*e, *p));
+                            }
+                        } else if actor.atype == ActorType::Villager || actor.atype == ActorType::Player {
+                            if !intruders.iter().any(|(ie, _)| ie == e) {
+                                intruders.push((*e, *p));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let mut potential_actions: Vec<Action> = vec![];
+
+    // the whole pack goes after whatever is closest to the leader
+    let closest_to_leader = |targets: &Vec<(EntityId, Point)>| -> Option<(EntityId, Point)> {
+        let mut best = None;
+        let mut best_dist = f32::MAX;
+        for (e, p) in targets.iter() {
+            let dist = rltk::DistanceAlg::Pythagoras.distance2d(leader_pos, *p);
+            if dist < best_dist {
+                best = Some((*e, *p));
+                best_dist = dist;
+            }
+        }
+        best
+    };
+
+    let mut hunt = |name: &str, target: (EntityId, Point), threshold: f32, priority: f32| {
+        let dist = map.distance(&vpos, Target::from(pos), Target::from(target.1));
+        let hungry = Consideration::new(
+            "hunger".to_string(),
+            hunger,
+            ConsiderationParam {
+                t: ResponseCurveType::GreaterThan,
+                m: threshold,
+                k: 1.0,
+                c: 0.0,
+                b: 0.0,
+            },
+        );
+
+        potential_actions.push(Action {
+            intent: Intent {
+                name: format!("chase {}", name),
+                task: Task::MoveTo,
+                target: vec![Target::from(nearest_open_neighbor(&map, target.1, pos))],
+                turn: *turn,
+            },
+            cons: vec![
+                hungry.clone(),
+                Consideration::new(
+                    "Distance".to_string(),
+                    dist,
+                    ConsiderationParam {
+                        t: ResponseCurveType::Linear,
+                        m: -1.0 / 100.0,
+                        k: 1.0,
+                        c: 0.0,
+                        b: 1.0,
+                    },
+                ),
+            ],
+            priority,
+        });
+
+        potential_actions.push(Action {
+            intent: Intent {
+                name: format!("attack {}", name),
+                task: Task::Attack,
+                target: vec![Target::from(target.0)],
+                turn: *turn,
+            },
+            cons: vec![
+                hungry,
+                Consideration::new(
+                    "Distance".to_string(),
+                    dist,
+                    ConsiderationParam {
+                        t: ResponseCurveType::LessThan,
+                        m: 1.5,
+                        k: 1.0,
+                        c: 0.0,
+                        b: 1.0,
+                    },
+                ),
+            ],
+            priority: priority + 1.0,
+        });
+    };
+
+    if let Some(target) = closest_to_leader(&prey) {
+        hunt("prey", target, HUNT_HUNGER, 1.5);
+    }
+
+    // starving wolves stop being picky
+    if let Some(target) = closest_to_leader(&intruders) {
+        hunt("intruder", target, DESPERATE_HUNGER, 1.2);
+    }
+
+    // stick close to the leader
+    if leader != id {
+        potential_actions.push(Action {
+            intent: Intent {
+                name: "follow pack".to_string(),
+                task: Task::MoveTo,
+                target: vec![Target::from(nearest_open_neighbor(&map, leader_pos, pos))],
+                turn: *turn,
+            },
+            cons: vec![Consideration::new(
+                "Distance to leader".to_string(),
+                map.distance(&vpos, Target::from(pos), Target::from(leader_pos)),
+                ConsiderationParam {
+                    t: ResponseCurveType::GreaterThan,
+                    m: 3.0,
+                    k: 1.0,
+                    c: 0.0,
+                    b: 0.0,
+                },
+            )],
+            priority: 0.4,
+        });
+    }
+
+    // fed packs drift back home
+    if let Some(den_pos) = den.and_then(|d| vpos.get(d).ok()) {
+        let den_pos = den_pos.ps[0];
+
+        potential_actions.push(Action {
+            intent: Intent {
+                name: "return to den".to_string(),
+                task: Task::MoveTo,
+                target: vec![Target::from(nearest_open_neighbor(&map, den_pos, pos))],
+                turn: *turn,
+            },
+            cons: vec![Consideration::new(
+                "Distance to den".to_string(),
+                map.distance(&vpos, Target::from(pos), Target::from(den_pos)),
+                ConsiderationParam {
+                    t: ResponseCurveType::GreaterThan,
+                    m: DEN_RADIUS,
+                    k: 1.0,
+                    c: 0.0,
+                    b: 0.0,
+                },
+            )],
+            priority: 0.3,
+        });
+    }
+
+    potential_actions
+}
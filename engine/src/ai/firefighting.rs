@@ -0,0 +1,368 @@
+use rltk::Point;
+use shipyard::{AllStorages, EntityId, Get, UniqueView, View};
+
+use crate::{
+    components::{
+        Actor, Bucket, ChiefHouse, FishCleaner, Inventory, LumberMill, PlankHouse, Position, SpatialKnowledge, Vision,
+    },
+    map::Map,
+    tiles::tile_info,
+    uniques::Turn,
+    utils::{closest_point, get_neighbors},
+};
+
+use super::decisions::{Action, Consideration, ConsiderationParam, Intent, ResponseCurveType, Target, Task};
+use super::labors::AIBehaviors;
+use super::squads::nearest_open_neighbor;
+
+// anyone on or next to a burning tile gets out of the way first
+const DANGER_RADIUS: f32 = 1.5;
+
+// how far to look for somewhere safe to stand
+const FLEE_RANGE: i32 = 6;
+
+// buckets are emptied onto a fire, filled and handed over from right next to it
+const DOUSE_RANGE: f32 = 1.5;
+
+// a fire next to a building counts as this many tiles closer when picking which one to fight
+const STRUCTURE_BONUS: f32 = 10.0;
+
+fn is_burning_near(map: &Map, p: Point) -> bool {
+    std::iter::once(p)
+        .chain(get_neighbors(p))
+        .any(|n| map.in_bounds(n.x, n.y) && map.fire_turns[map.point_idx(n)] > 0)
+}
+
+// closest tile that isn't burning or next to a fire, searching outward in rings
+fn nearest_safe(map: &Map, p: Point) -> Option<Point> {
+    for r in 1..=FLEE_RANGE {
+        let mut best: Option<(Point, f32)> = None;
+        for dx in -r..=r {
+            for dy in -r..=r {
+                if dx.abs() != r && dy.abs() != r {
+                    continue;
+                }
+
+                let candidate = Point {
+                    x: p.x + dx,
+                    y: p.y + dy,
+                };
+                if !map.in_bounds(candidate.x, candidate.y) {
+                    continue;
+                }
+
+                let idx = map.point_idx(candidate);
+                if map.blocks_movement(idx) || map.blocked[idx] || is_burning_near(map, candidate) {
+                    continue;
+                }
+
+                let dist = rltk::DistanceAlg::Pythagoras.distance2d(p, candidate);
+                if best.map_or(true, |(_, d)| dist < d) {
+                    best = Some((candidate, dist));
+                }
+            }
+        }
+
+        if let Some((safe, _)) = best {
+            return Some(safe);
+        }
+    }
+
+    None
+}
+
+// where along the line from the water to the fire someone without a bucket should stand to pass them on
+fn chain_spot(map: &Map, water: Point, fire: Point, p: Point) -> Point {
+    let (dx, dy) = ((fire.x - water.x) as f32, (fire.y - water.y) as f32);
+    let length = dx * dx + dy * dy;
+    let t = if length > 0. {
+        (((p.x - water.x) as f32 * dx + (p.y - water.y) as f32 * dy) / length).clamp(0., 1.)
+    } else {
+        0.
+    };
+
+    let spot = Point {
+        x: water.x + (t * dx).round() as i32,
+        y: water.y + (t * dy).round() as i32,
+    };
+    if spot == p || !map.blocked[map.point_idx(spot)] {
+        spot
+    } else {
+        nearest_open_neighbor(map, spot, p)
+    }
+}
+
+/// Villagers get clear of fires, then fight the ones they can see with buckets, buildings first.
+/// Buckets are fetched from wherever the village keeps them and refilled at the water closest to the fire.
+/// Anyone without one lines up between the water and the fire, and buckets are handed along that line,
+/// full ones towards the fire and empty ones back to the water.
+pub fn get_firefighting_actions(store: &AllStorages, id: EntityId) -> Vec<Action> {
+    let turn = store.borrow::<UniqueView<Turn>>().unwrap();
+    let map = store.borrow::<UniqueView<Map>>().unwrap();
+    let vpos = store.borrow::<View<Position>>().unwrap();
+    let vinv = store.borrow::<View<Inventory>>().unwrap();
+    let vvs = store.borrow::<View<Vision>>().unwrap();
+    let vspace = store.borrow::<View<SpatialKnowledge>>().unwrap();
+    let vactor = store.borrow::<View<Actor>>().unwrap();
+    let vbucket = store.borrow::<View<Bucket>>().unwrap();
+    let vhouse = store.borrow::<View<PlankHouse>>().unwrap();
+    let vchief = store.borrow::<View<ChiefHouse>>().unwrap();
+    let vlm = store.borrow::<View<LumberMill>>().unwrap();
+    let vfc = store.borrow::<View<FishCleaner>>().unwrap();
+
+    let body = if let Ok(pos) = vpos.get(id) {
+        &pos.ps
+    } else {
+        return vec![];
+    };
+    let pos = body[0];
+    let vs = if let Ok(vs) = vvs.get(id) {
+        vs
+    } else {
+        return vec![];
+    };
+
+    let distance = |p: Point| rltk::DistanceAlg::Pythagoras.distance2d(p, closest_point(body, p));
+
+    let fires: Vec<Point> = vs
+        .visible_tiles
+        .iter()
+        .filter(|p| map.fire_turns[map.point_idx(**p)] > 0)
+        .copied()
+        .collect();
+    if fires.is_empty() {
+        return vec![];
+    }
+
+    let mut potential_actions: Vec<Action> = vec![];
+
+    // get off and away from anything burning
+    let nearest_fire = fires.iter().map(|f| distance(*f)).fold(f32::MAX, f32::min);
+    if nearest_fire < DANGER_RADIUS {
+        if let Some(safe) = nearest_safe(&map, pos) {
+            potential_actions.push(Action {
+                intent: Intent {
+                    name: "flee fire".to_string(),
+                    task: Task::MoveTo,
+                    target: vec![Target::from(safe)],
+                    turn: *turn,
+                },
+                cons: vec![Consideration::new(
+                    "Distance to fire".to_string(),
+                    nearest_fire,
+                    ConsiderationParam {
+                        t: ResponseCurveType::LessThan,
+                        m: DANGER_RADIUS,
+                        k: 1.0,
+                        c: 0.0,
+                        b: 0.0,
+                    },
+                )],
+                priority: 4.0,
+            });
+        }
+    }
+
+    let is_structure =
+        |e: &EntityId| vhouse.get(*e).is_ok() || vchief.get(*e).is_ok() || vlm.get(*e).is_ok() || vfc.get(*e).is_ok();
+    let threatens_structure = |p: Point| {
+        std::iter::once(p)
+            .chain(get_neighbors(p))
+            .any(|n| map.in_bounds(n.x, n.y) && map.tile_content[map.point_idx(n)].iter().any(is_structure))
+    };
+
+    // fight the closest fire, fires about to take a building count as closer
+    let (fire, saving) = fires
+        .iter()
+        .map(|f| (*f, threatens_structure(*f)))
+        .min_by(|(a, sa), (b, sb)| {
+            let score = |p: Point, s: bool| distance(p) - if s { STRUCTURE_BONUS } else { 0. };
+            score(*a, *sa).partial_cmp(&score(*b, *sb)).unwrap()
+        })
+        .unwrap();
+    let fire_dist = distance(fire);
+    let urgency = if saving { 1.0 } else { 0.6 };
+    let saving_building = || {
+        Consideration::new(
+            "saving a building".to_string(),
+            urgency,
+            ConsiderationParam::new_const(1.0),
+        )
+    };
+
+    // buckets are refilled from whatever water lies closest to the fire
+    let water = vspace.get(id).ok().and_then(|space| {
+        space
+            .tiles
+            .iter()
+            .filter(|(_, (tile, _))| tile_info(*tile).swimmable)
+            .map(|(idx, _)| map.idx_point(*idx))
+            .min_by(|a, b| {
+                let da = rltk::DistanceAlg::Pythagoras.distance2d(*a, fire);
+                let db = rltk::DistanceAlg::Pythagoras.distance2d(*b, fire);
+                da.partial_cmp(&db).unwrap()
+            })
+    });
+
+    let carried_bucket = |e: EntityId| {
+        vinv.get(e).ok().and_then(|inv| {
+            inv.items
+                .iter()
+                .find_map(|item| vbucket.get(*item).ok().map(|bucket| (*item, bucket.full)))
+        })
+    };
+
+    let (bucket, full) = if let Some(bucket) = carried_bucket(id) {
+        bucket
+    } else {
+        // grab the nearest bucket lying around, or wait in line for one to be passed along
+        let lying = vs
+            .visible_tiles
+            .iter()
+            .flat_map(|p| map.tile_content[map.point_idx(*p)].iter().map(move |e| (*e, *p)))
+            .filter(|(e, _)| vbucket.get(*e).is_ok())
+            .min_by(|(_, a), (_, b)| distance(*a).partial_cmp(&distance(*b)).unwrap());
+
+        if let Some((bucket, at)) = lying {
+            let (name, task, target) = if distance(at) < DOUSE_RANGE {
+                ("pick up bucket", Task::PickUpItem, Target::from(bucket))
+            } else {
+                ("fetch bucket", Task::MoveTo, Target::from(at))
+            };
+            potential_actions.push(Action {
+                intent: Intent {
+                    name: name.to_string(),
+                    task,
+                    target: vec![target],
+                    turn: *turn,
+                },
+                cons: vec![saving_building()],
+                priority: 3.0,
+            });
+        } else if let Some(water) = water {
+            potential_actions.push(Action {
+                intent: Intent {
+                    name: "join bucket chain".to_string(),
+                    task: Task::MoveTo,
+                    target: vec![Target::from(chain_spot(&map, water, fire, pos))],
+                    turn: *turn,
+                },
+                cons: vec![saving_building()],
+                priority: 2.0,
+            });
+        }
+
+        return potential_actions;
+    };
+
+    // hand the bucket to a firefighter next to us who's further along the chain and has nothing to carry
+    let towards = if full { Some(fire) } else { water };
+    if let Some(towards) = towards {
+        let partner = get_neighbors(pos)
+            .into_iter()
+            .filter(|n| map.in_bounds(n.x, n.y))
+            .flat_map(|n| map.tile_content[map.point_idx(n)].iter().map(move |e| (*e, n)))
+            .filter(|(e, _)| {
+                *e != id
+                    && vactor
+                        .get(*e)
+                        .map_or(false, |actor| actor.behaviors.contains(&AIBehaviors::FightFires))
+                    && vinv.get(*e).is_ok()
+                    && carried_bucket(*e).is_none()
+            })
+            .map(|(e, n)| (e, rltk::DistanceAlg::Pythagoras.distance2d(n, towards)))
+            .filter(|(_, d)| *d + 1. <= distance(towards))
+            .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap());
+
+        if let Some((partner, _)) = partner {
+            potential_actions.push(Action {
+                intent: Intent {
+                    name: "pass bucket along".to_string(),
+                    task: Task::DepositItemToInventory,
+                    target: vec![Target::from(bucket), Target::from(partner)],
+                    turn: *turn,
+                },
+                cons: vec![saving_building()],
+                priority: 3.5,
+            });
+        }
+    }
+
+    if full {
+        if fire_dist < DOUSE_RANGE {
+            potential_actions.push(Action {
+                intent: Intent {
+                    name: "douse fire".to_string(),
+                    task: Task::UseItem,
+                    target: vec![Target::from(bucket), Target::from(fire)],
+                    turn: *turn,
+                },
+                cons: vec![
+                    Consideration::new(
+                        "Distance to fire".to_string(),
+                        fire_dist,
+                        ConsiderationParam {
+                            t: ResponseCurveType::LessThan,
+                            m: DOUSE_RANGE,
+                            k: 1.0,
+                            c: 0.0,
+                            b: 0.0,
+                        },
+                    ),
+                    saving_building(),
+                ],
+                // beats running off, a bucket on the fire is the quickest way to be safe
+                priority: 5.0,
+            });
+        } else if let Some(spot) = get_neighbors(fire)
+            .into_iter()
+            .filter(|n| map.in_bounds(n.x, n.y) && !map.blocks_movement(map.point_idx(*n)))
+            .min_by_key(|n| map.fire_turns[map.point_idx(*n)])
+        {
+            potential_actions.push(Action {
+                intent: Intent {
+                    name: "carry water to fire".to_string(),
+                    task: Task::MoveTo,
+                    target: vec![Target::from(spot)],
+                    turn: *turn,
+                },
+                cons: vec![saving_building()],
+                priority: 3.0,
+            });
+        }
+
+        return potential_actions;
+    }
+
+    let water = if let Some(water) = water {
+        water
+    } else {
+        return potential_actions;
+    };
+
+    if distance(water) < DOUSE_RANGE {
+        potential_actions.push(Action {
+            intent: Intent {
+                name: "fill bucket".to_string(),
+                task: Task::UseItem,
+                target: vec![Target::from(bucket), Target::from(water)],
+                turn: *turn,
+            },
+            cons: vec![saving_building()],
+            priority: 3.0,
+        });
+    } else {
+        potential_actions.push(Action {
+            intent: Intent {
+                name: "fetch water".to_string(),
+                task: Task::MoveTo,
+                target: vec![Target::from(nearest_open_neighbor(&map, water, fire))],
+                turn: *turn,
+            },
+            cons: vec![saving_building()],
+            priority: 3.0,
+        });
+    }
+
+    potential_actions
+}
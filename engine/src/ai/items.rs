@@ -0,0 +1,310 @@
+use rltk::Point;
+use shipyard::{AllStorages, EntityId, Get, IntoIter, IntoWithId, UniqueView, View};
+
+use crate::{
+    components::{
+        Actor, AreaOfEffect, CombatStats, Confusion, DealsDamage, EquipmentSlot, Equippable, Equipped, Inventory, Item,
+        MeleeDefenseBonus, MeleePowerBonus, Position, ProvidesHealing, Ranged, Vision,
+    },
+    map::Map,
+    uniques::Turn,
+};
+
+use super::decisions::{Action, Consideration, ConsiderationParam, Intent, ResponseCurveType, Target, Task};
+
+pub fn get_item_actions(store: &AllStorages, id: EntityId) -> Vec<Action> {
+    let turn = store.borrow::<UniqueView<Turn>>().unwrap();
+    let map = store.borrow::<UniqueView<Map>>().unwrap();
+    let vpos = store.borrow::<View<Position>>().unwrap();
+    let vinv = store.borrow::<View<Inventory>>().unwrap();
+    let vitem = store.borrow::<View<Item>>().unwrap();
+    let vvs = store.borrow::<View<Vision>>().unwrap();
+    let vactor = store.borrow::<View<Actor>>().unwrap();
+    let vstats = store.borrow::<View<CombatStats>>().unwrap();
+    let vequipped = store.borrow::<View<Equipped>>().unwrap();
+    let vequippable = store.borrow::<View<Equippable>>().unwrap();
+    let vpower = store.borrow::<View<MeleePowerBonus>>().unwrap();
+    let vdefense = store.borrow::<View<MeleeDefenseBonus>>().unwrap();
+    let vheal = store.borrow::<View<ProvidesHealing>>().unwrap();
+    let vranged = store.borrow::<View<Ranged>>().unwrap();
+    let vdamage = store.borrow::<View<DealsDamage>>().unwrap();
+    let vconfusion = store.borrow::<View<Confusion>>().unwrap();
+    let vaoe = store.borrow::<View<AreaOfEffect>>().unwrap();
+
+    let pos = if let Ok(pos) = vpos.get(id) {
+        pos.ps[0]
+    } else {
+        return vec![];
+    };
+    let inv = if let Ok(inv) = vinv.get(id) {
+        inv
+    } else {
+        return vec![];
+    };
+    let vs = if let Ok(vs) = vvs.get(id) {
+        vs
+    } else {
+        return vec![];
+    };
+    let actor = if let Ok(actor) = vactor.get(id) {
+        actor
+    } else {
+        return vec![];
+    };
+
+    let has_inventory_space = inv.capacity > inv.items.len() as i32;
+
+    let item_bonus = |item: EntityId| -> i32 {
+        let mut bonus = 0;
+        if let Ok(power) = vpower.get(item) {
+            bonus += power.power;
+        }
+        if let Ok(defense) = vdefense.get(item) {
+            bonus += defense.defense;
+        }
+        bonus
+    };
+
+    // what we're holding right now
+    let mut equipped_items: Vec<EntityId> = vec![];
+    let mut right_hand = 0;
+    let mut left_hand = 0;
+    for (item, equipped) in vequipped.iter().with_id() {
+        if equipped.owner == id {
+            equipped_items.push(item);
+            match equipped.slot {
+                EquipmentSlot::RightHand => right_hand = item_bonus(item),
+                EquipmentSlot::LeftHand => left_hand = item_bonus(item),
+            }
+        }
+    }
+    let slot_bonus = |slot: EquipmentSlot| -> i32 {
+        match slot {
+            EquipmentSlot::RightHand => right_hand,
+            EquipmentSlot::LeftHand => left_hand,
+        }
+    };
+
+    let is_offensive = |item: EntityId| -> bool {
+        vranged.get(item).is_ok() && (vdamage.get(item).is_ok() || vconfusion.get(item).is_ok())
+    };
+
+    let mut enemies: Vec<Point> = vec![];
+    for point in vs.visible_tiles.iter() {
+        let idx = map.point_idx(*point);
+        for entity in map.tile_content[idx].iter() {
+            if let Ok(eactor) = vactor.get(*entity) {
+                if actor.faction != eactor.faction && vstats.get(*entity).is_ok() {
+                    enemies.push(*point);
+                }
+            }
+        }
+    }
+
+    let mut potential_actions: Vec<Action> = vec![];
+
+    // use or equip what we carry
+    for item in inv.items.iter() {
+        if equipped_items.contains(item) {
+            continue;
+        }
+
+        if let Ok(equippable) = vequippable.get(*item) {
+            if item_bonus(*item) > slot_bonus(equippable.slot) {
+                potential_actions.push(Action {
+                    intent: Intent {
+                        name: "equip item".to_string(),
+                        task: Task::EquipItem,
+                        target: vec![Target::from(*item)],
+                        turn: *turn,
+                    },
+                    cons: vec![Consideration::new(
+                        "baseline".to_string(),
+                        1.0,
+                        ConsiderationParam::new_const(0.9),
+                    )],
+                    priority: 1.0,
+                });
+            }
+        }
+
+        if vheal.get(*item).is_ok() {
+            if let Ok(stats) = vstats.get(id) {
+                let health = stats.hp as f32 / stats.max_hp as f32;
+
+                potential_actions.push(Action {
+                    intent: Intent {
+                        name: "drink healing potion".to_string(),
+                        task: Task::UseItem,
+                        target: vec![Target::from(*item)],
+                        turn: *turn,
+                    },
+                    cons: vec![
+                        Consideration::new(
+                            "hurt".to_string(),
+                            health,
+                            ConsiderationParam {
+                                t: ResponseCurveType::LessThan,
+                                m: 0.5,
+                                k: 1.0,
+                                c: 0.0,
+                                b: 0.0,
+                            },
+                        ),
+                        Consideration::new(
+                            "health".to_string(),
+                            health,
+                            ConsiderationParam {
+                                t: ResponseCurveType::Linear,
+                                m: -1.0,
+                                k: 1.0,
+                                c: 0.0,
+                                b: 1.0,
+                            },
+                        ),
+                    ],
+                    priority: 3.0,
+                });
+            }
+        }
+
+        if is_offensive(*item) {
+            let range = vranged.get(*item).map_or(0, |r| r.range) as f32;
+            let radius = vaoe.get(*item).map_or(0, |aoe| aoe.radius) as f32;
+
+            for epoint in enemies.iter() {
+                let distance = map.distance(&vpos, Target::from(pos), Target::from(*epoint));
+
+                potential_actions.push(Action {
+                    intent: Intent {
+                        name: "read scroll at enemy".to_string(),
+                        task: Task::UseItem,
+                        target: vec![Target::from(*item), Target::from(*epoint)],
+                        turn: *turn,
+                    },
+                    cons: vec![
+                        Consideration::new(
+                            "in range".to_string(),
+                            distance,
+                            ConsiderationParam {
+                                t: ResponseCurveType::LessThan,
+                                m: range + 0.5,
+                                k: 1.0,
+                                c: 0.0,
+                                b: 0.0,
+                            },
+                        ),
+                        // don't get caught in our own blast
+                        Consideration::new(
+                            "outside blast".to_string(),
+                            distance,
+                            ConsiderationParam {
+                                t: ResponseCurveType::GreaterThan,
+                                m: radius + 0.5,
+                                k: 1.0,
+                                c: 0.0,
+                                b: 0.0,
+                            },
+                        ),
+                    ],
+                    priority: 2.5,
+                });
+            }
+        }
+    }
+
+    // look for something better on the ground
+    if has_inventory_space {
+        for point in vs.visible_tiles.iter() {
+            let idx = map.point_idx(*point);
+            for e in map.tile_content[idx].iter() {
+                if vitem.get(*e).is_err() {
+                    continue;
+                }
+
+                let wanted = if let Ok(equippable) = vequippable.get(*e) {
+                    item_bonus(*e) > slot_bonus(equippable.slot)
+                } else {
+                    vheal.get(*e).is_ok() || is_offensive(*e)
+                };
+
+                if !wanted {
+                    continue;
+                }
+
+                let distance = map.distance(&vpos, Target::from(pos), Target::from(*point));
+
+                potential_actions.push(Action {
+                    intent: Intent {
+                        name: "go to item".to_string(),
+                        task: Task::MoveTo,
+                        target: vec![Target::from(*point)],
+                        turn: *turn,
+                    },
+                    cons: vec![Consideration::new(
+                        "Distance".to_string(),
+                        distance,
+                        ConsiderationParam {
+                            t: ResponseCurveType::Linear,
+                            m: -1.0 / 100.0,
+                            k: 1.0,
+                            c: 1.0,
+                            b: 1.0,
+                        },
+                    )],
+                    priority: 0.8,
+                });
+
+                potential_actions.push(Action {
+                    intent: Intent {
+                        name: "pick up item".to_string(),
+                        task: Task::PickUpItem,
+                        target: vec![Target::from(*e)],
+                        turn: *turn,
+                    },
+                    cons: vec![Consideration::new(
+                        "Distance".to_string(),
+                        distance,
+                        ConsiderationParam {
+                            t: ResponseCurveType::LessThan,
+                            m: 1.5,
+                            k: 1.0,
+                            c: 0.0,
+                            b: 1.0,
+                        },
+                    )],
+                    priority: 1.5,
+                });
+            }
+        }
+    } else {
+        // make room by dropping gear that's no better than what we hold
+        for item in inv.items.iter() {
+            if equipped_items.contains(item) {
+                continue;
+            }
+
+            if let Ok(equippable) = vequippable.get(*item) {
+                if item_bonus(*item) <= slot_bonus(equippable.slot) {
+                    potential_actions.push(Action {
+                        intent: Intent {
+                            name: "drop junk".to_string(),
+                            task: Task::DropItem,
+                            target: vec![Target::from(*item)],
+                            turn: *turn,
+                        },
+                        cons: vec![Consideration::new(
+                            "baseline".to_string(),
+                            1.0,
+                            ConsiderationParam::new_const(0.6),
+                        )],
+                        priority: 1.0,
+                    });
+                    break;
+                }
+            }
+        }
+    }
+
+    potential_actions
+}
@@ -3,13 +3,18 @@ use shipyard::{AllStorages, EntityId, Get, UniqueView, View};
 
 use crate::{
     components::{
-        Actor, ActorType, FishCleaner, Inventory, Item, ItemType, LumberMill, Position, SpatialKnowledge, Tree, Vision,
+        Actor, ActorType, FishCleaner, Hearing, Inventory, Item, ItemType, LumberMill, Position, SpatialKnowledge,
+        Tree, Vision,
     },
     map::{Map, TileType},
     uniques::Turn,
 };
 
 use super::decisions::{Action, Consideration, ConsiderationParam, Intent, ResponseCurveType, Target, Task, AI};
+use super::{ecology, firefighting, items, squads};
+
+// turns a noise stays interesting after it was heard
+const NOISE_MEMORY: i32 = 20;
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum AIBehaviors {
@@ -18,6 +23,11 @@ pub enum AIBehaviors {
     AttackEnemies,
     Confused,
     Wander,
+    SquadTactics,
+    UseItems,
+    Graze,
+    Hunt,
+    FightFires,
 }
 
 pub fn get_action(store: &AllStorages, id: EntityId) -> Action {
@@ -47,6 +57,13 @@ pub fn get_action(store: &AllStorages, id: EntityId) -> Action {
                 AIBehaviors::GatherWood => potential_actions.append(&mut get_gather_wood_actions(&store, id)),
                 AIBehaviors::GatherFish => potential_actions.append(&mut get_gather_fish_actions(&store, id)),
                 AIBehaviors::AttackEnemies => potential_actions.append(&mut get_attack_actions(&store, id)),
+                AIBehaviors::SquadTactics => potential_actions.append(&mut squads::get_squad_actions(&store, id)),
+                AIBehaviors::UseItems => potential_actions.append(&mut items::get_item_actions(&store, id)),
+                AIBehaviors::Graze => potential_actions.append(&mut ecology::get_graze_actions(&store, id)),
+                AIBehaviors::Hunt => potential_actions.append(&mut ecology::get_hunt_actions(&store, id)),
+                AIBehaviors::FightFires => {
+                    potential_actions.append(&mut firefighting::get_firefighting_actions(&store, id))
+                }
                 _ => {} // AIBehaviors::Wander => ,
             }
         }
@@ -652,6 +669,11 @@ pub fn get_attack_actions(store: &AllStorages, id: EntityId) -> Vec<Action> {
         }
     }
 
+    // nothing in sight, go see what made that noise
+    if enemies.is_empty() {
+        return get_investigate_actions(store, id);
+    }
+
     let mut potential_actions: Vec<Action> = vec![];
 
     for (_, epoint) in enemies.iter() {
@@ -700,3 +722,52 @@ pub fn get_attack_actions(store: &AllStorages, id: EntityId) -> Vec<Action> {
 
     potential_actions
 }
+
+pub fn get_investigate_actions(store: &AllStorages, id: EntityId) -> Vec<Action> {
+    let turn = store.borrow::<UniqueView<Turn>>().unwrap();
+    let map = store.borrow::<UniqueView<Map>>().unwrap();
+    let vpos = store.borrow::<View<Position>>().unwrap();
+    let vhearing = store.borrow::<View<Hearing>>().unwrap();
+
+    let pos = if let Ok(pos) = vpos.get(id) {
+        pos.ps[0]
+    } else {
+        return vec![];
+    };
+
+    let noise = match vhearing.get(id) {
+        Ok(Hearing {
+            heard: Some(noise),
+            heard_turn,
+            ..
+        }) if turn.0 - heard_turn <= NOISE_MEMORY => *noise,
+        _ => return vec![],
+    };
+
+    let goal = if map.blocked[map.point_idx(noise)] {
+        squads::nearest_open_neighbor(&map, noise, pos)
+    } else {
+        noise
+    };
+
+    vec![Action {
+        intent: Intent {
+            name: "investigate noise".to_string(),
+            task: Task::MoveTo,
+            target: vec![Target::from(goal)],
+            turn: *turn,
+        },
+        cons: vec![Consideration::new(
+            "Distance".to_string(),
+            map.distance(&vpos, Target::from(pos), Target::from(noise)),
+            ConsiderationParam {
+                t: ResponseCurveType::GreaterThan,
+                m: 1.5,
+                k: 1.0,
+                c: 0.0,
+                b: 0.0,
+            },
+        )],
+        priority: 0.6,
+    }]
+}
@@ -1,2 +1,6 @@
 pub mod decisions;
+pub mod ecology;
+pub mod firefighting;
+pub mod items;
 pub mod labors;
+pub mod squads;
@@ -0,0 +1,260 @@
+use std::f32::consts::PI;
+
+use rltk::Point;
+use shipyard::{AllStorages, EntityId, Get, UniqueView, View};
+
+use crate::{
+    components::{Position, Squad, SquadMember, SquadState},
+    map::Map,
+    uniques::Turn,
+    utils::{get_neighbors, normalize},
+};
+
+use super::{
+    decisions::{Action, Consideration, ConsiderationParam, Intent, ResponseCurveType, Target, Task},
+    labors::{get_attack_actions, get_investigate_actions},
+};
+
+// members further than this from their leader are not counted as rallied
+pub const RALLY_RADIUS: f32 = 4.0;
+
+// how far from the threat a retreating squad tries to get
+const RETREAT_DISTANCE: i32 = 10;
+
+pub fn get_squad_actions(store: &AllStorages, id: EntityId) -> Vec<Action> {
+    let turn = store.borrow::<UniqueView<Turn>>().unwrap();
+    let map = store.borrow::<UniqueView<Map>>().unwrap();
+    let vpos = store.borrow::<View<Position>>().unwrap();
+    let vmember = store.borrow::<View<SquadMember>>().unwrap();
+    let vsquad = store.borrow::<View<Squad>>().unwrap();
+
+    // actors that lost their squad fight on their own
+    let squad = if let Ok(member) = vmember.get(id) {
+        if let Ok(squad) = vsquad.get(member.squad) {
+            squad
+        } else {
+            return get_attack_actions(store, id);
+        }
+    } else {
+        return get_attack_actions(store, id);
+    };
+
+    let pos = if let Ok(pos) = vpos.get(id) {
+        pos.ps[0]
+    } else {
+        return vec![];
+    };
+
+    let leader_pos = if let Ok(lpos) = vpos.get(squad.leader) {
+        lpos.ps[0]
+    } else {
+        pos
+    };
+
+    let target_pos = if let Some(target) = squad.target {
+        if let Ok(tpos) = vpos.get(target) {
+            Some(tpos.ps[0])
+        } else {
+            None
+        }
+    } else {
+        None
+    };
+
+    let mut potential_actions: Vec<Action> = vec![];
+
+    match (squad.state, squad.target, target_pos) {
+        (SquadState::Engaging, Some(target), Some(target_pos)) => {
+            let slot = flank_position(&map, squad, id, pos, leader_pos, target_pos);
+
+            potential_actions.push(Action {
+                intent: Intent {
+                    name: "flank target".to_string(),
+                    task: Task::MoveTo,
+                    target: vec![Target::from(slot)],
+                    turn: *turn,
+                },
+                cons: vec![Consideration::new(
+                    "Distance to slot".to_string(),
+                    map.distance(&vpos, Target::from(pos), Target::from(slot)),
+                    ConsiderationParam {
+                        t: ResponseCurveType::Linear,
+                        m: -1.0 / 100.0,
+                        k: 1.0,
+                        c: 0.0,
+                        b: 1.0,
+                    },
+                )],
+                priority: 1.0,
+            });
+
+            potential_actions.push(Action {
+                intent: Intent {
+                    name: "attack squad target".to_string(),
+                    task: Task::Attack,
+                    target: vec![Target::from(target)],
+                    turn: *turn,
+                },
+                cons: vec![Consideration::new(
+                    "Distance".to_string(),
+                    map.distance(&vpos, Target::from(pos), Target::from(target)),
+                    ConsiderationParam {
+                        t: ResponseCurveType::LessThan,
+                        m: 1.5,
+                        k: 1.0,
+                        c: 1.0,
+                        b: 1.0,
+                    },
+                )],
+                priority: 2.0,
+            });
+        }
+        (SquadState::Retreating, _, Some(target_pos)) => {
+            let dx = normalize(leader_pos.x - target_pos.x);
+            let dy = normalize(leader_pos.y - target_pos.y);
+            let goal = Point {
+                x: (leader_pos.x + dx * RETREAT_DISTANCE).clamp(1, map.width - 2),
+                y: (leader_pos.y + dy * RETREAT_DISTANCE).clamp(1, map.height - 2),
+            };
+            let goal = nearest_walkable(&map, goal);
+
+            potential_actions.push(Action {
+                intent: Intent {
+                    name: "retreat with squad".to_string(),
+                    task: Task::MoveTo,
+                    target: vec![Target::from(goal)],
+                    turn: *turn,
+                },
+                cons: vec![Consideration::new(
+                    "baseline".to_string(),
+                    1.0,
+                    ConsiderationParam::new_const(0.8),
+                )],
+                priority: 1.0,
+            });
+        }
+        _ => {
+            // gathering, fight back if cornered but otherwise wait for the rest of the squad
+            for action in get_attack_actions(store, id) {
+                if action.intent.task == Task::Attack {
+                    potential_actions.push(action);
+                }
+            }
+
+            if squad.leader == id {
+                // the squad follows its leader, so only the leader chases noises
+                if squad.target.is_none() {
+                    potential_actions.append(&mut get_investigate_actions(store, id));
+                } else {
+                    potential_actions.push(Action {
+                        intent: Intent {
+                            name: "wait for squad".to_string(),
+                            task: Task::Idle,
+                            target: vec![],
+                            turn: *turn,
+                        },
+                        cons: vec![Consideration::new(
+                            "baseline".to_string(),
+                            1.0,
+                            ConsiderationParam::new_const(0.5),
+                        )],
+                        priority: 1.0,
+                    });
+                }
+            } else {
+                let rally_point = nearest_open_neighbor(&map, leader_pos, pos);
+
+                potential_actions.push(Action {
+                    intent: Intent {
+                        name: "regroup".to_string(),
+                        task: Task::MoveTo,
+                        target: vec![Target::from(rally_point)],
+                        turn: *turn,
+                    },
+                    cons: vec![Consideration::new(
+                        "Distance to leader".to_string(),
+                        map.distance(&vpos, Target::from(pos), Target::from(leader_pos)),
+                        ConsiderationParam {
+                            t: ResponseCurveType::GreaterThan,
+                            m: 1.5,
+                            k: 1.0,
+                            c: 0.0,
+                            b: 0.0,
+                        },
+                    )],
+                    priority: 0.5,
+                });
+            }
+        }
+    }
+
+    potential_actions
+}
+
+/// Spreads squad members evenly around the target, starting on the side the leader approaches from
+fn flank_position(map: &Map, squad: &Squad, id: EntityId, pos: Point, leader_pos: Point, target_pos: Point) -> Point {
+    let index = squad.members.iter().position(|m| *m == id).unwrap_or(0);
+    let count = usize::max(squad.members.len(), 1);
+    let radius = if count > 8 { 2.0 } else { 1.0 };
+
+    let base_angle = ((leader_pos.y - target_pos.y) as f32).atan2((leader_pos.x - target_pos.x) as f32);
+    let angle = base_angle + 2.0 * PI * index as f32 / count as f32;
+
+    let slot = Point {
+        x: target_pos.x + (angle.cos() * radius).round() as i32,
+        y: target_pos.y + (angle.sin() * radius).round() as i32,
+    };
+
+    if slot == pos {
+        return slot;
+    }
+
+    if map.in_bounds(slot.x, slot.y) && !map.blocked[map.point_idx(slot)] {
+        slot
+    } else {
+        nearest_open_neighbor(map, target_pos, pos)
+    }
+}
+
+/// Returns the unblocked tile next to `around` closest to `from`, so paths don't end on an occupied tile
+pub fn nearest_open_neighbor(map: &Map, around: Point, from: Point) -> Point {
+    let mut best = around;
+    let mut best_dist = f32::MAX;
+
+    for n in get_neighbors(around) {
+        if n == from {
+            return n;
+        }
+
+        if !map.in_bounds(n.x, n.y) || map.blocked[map.point_idx(n)] {
+            continue;
+        }
+
+        let dist = rltk::DistanceAlg::Pythagoras.distance2d(n, from);
+        if dist < best_dist {
+            best = n;
+            best_dist = dist;
+        }
+    }
+
+    best
+}
+
+/// Searches outward in rings for the closest tile that doesn't block movement
+pub fn nearest_walkable(map: &Map, p: Point) -> Point {
+    for r in 0..6 {
+        for dx in -r..=r {
+            for dy in -r..=r {
+                let candidate = Point {
+                    x: p.x + dx,
+                    y: p.y + dy,
+                };
+                if map.in_bounds(candidate.x, candidate.y) && !map.blocks_movement(map.point_idx(candidate)) {
+                    return candidate;
+                }
+            }
+        }
+    }
+
+    p
+}
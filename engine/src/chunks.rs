@@ -0,0 +1,157 @@
+use std::ops::{Index, IndexMut};
+
+use serde::{Deserialize, Serialize};
+
+/// Width and height of a chunk in tiles
+pub const CHUNK_SIZE: i32 = 32;
+
+/// One per-tile layer of the map, split into square chunks but indexed like a flat row-major vec.
+/// Chunks are only allocated once something is written to them and read as the layer's default until then,
+/// so a large world only pays for the parts that have been generated or touched.
+#[derive(Default, Serialize, Deserialize, Clone)]
+pub struct Chunked<T> {
+    width: i32,
+    height: i32,
+    chunks_wide: i32,
+    default: T,
+    chunks: Vec<Option<Vec<T>>>,
+}
+
+impl<T: Clone> Chunked<T> {
+    pub fn new(size: (i32, i32), default: T) -> Chunked<T> {
+        let chunks_wide = (size.0 + CHUNK_SIZE - 1) / CHUNK_SIZE;
+        let chunks_high = (size.1 + CHUNK_SIZE - 1) / CHUNK_SIZE;
+        Chunked {
+            width: size.0,
+            height: size.1,
+            chunks_wide,
+            default,
+            chunks: vec![None; (chunks_wide * chunks_high) as usize],
+        }
+    }
+
+    pub fn from_vec(size: (i32, i32), values: Vec<T>, default: T) -> Chunked<T>
+    where
+        T: PartialEq,
+    {
+        let mut layer = Chunked::new(size, default);
+        layer.update(|idx, value| *value = values[idx].clone());
+        layer
+    }
+
+    pub fn len(&self) -> usize {
+        (self.width * self.height) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    // which chunk a tile is in, and where in that chunk
+    fn locate(&self, idx: usize) -> (usize, usize) {
+        assert!(
+            idx < self.len(),
+            "tile {} is outside a {}x{} map",
+            idx,
+            self.width,
+            self.height
+        );
+        let (x, y) = (idx as i32 % self.width, idx as i32 / self.width);
+        let chunk = (y / CHUNK_SIZE) * self.chunks_wide + x / CHUNK_SIZE;
+        let offset = (y % CHUNK_SIZE) * CHUNK_SIZE + x % CHUNK_SIZE;
+        (chunk as usize, offset as usize)
+    }
+
+    pub fn chunk_of(&self, idx: usize) -> usize {
+        self.locate(idx).0
+    }
+
+    /// Map indexes of every tile in a chunk
+    pub fn chunk_tiles(&self, chunk: usize) -> impl Iterator<Item = usize> {
+        let (width, height) = (self.width, self.height);
+        let x1 = (chunk as i32 % self.chunks_wide) * CHUNK_SIZE;
+        let y1 = (chunk as i32 / self.chunks_wide) * CHUNK_SIZE;
+        (y1..(y1 + CHUNK_SIZE).min(height))
+            .flat_map(move |y| (x1..(x1 + CHUNK_SIZE).min(width)).map(move |x| (y * width + x) as usize))
+    }
+
+    /// Chunk coordinates, in chunks rather than tiles
+    pub fn chunk_xy(&self, chunk: usize) -> (i32, i32) {
+        (chunk as i32 % self.chunks_wide, chunk as i32 / self.chunks_wide)
+    }
+
+    pub fn is_loaded(&self, chunk: usize) -> bool {
+        self.chunks[chunk].is_some()
+    }
+
+    /// Drops a chunk's storage, it reads as the default again until it's next written to
+    pub fn unload(&mut self, chunk: usize) {
+        self.chunks[chunk] = None;
+    }
+
+    /// Drops a chunk's storage if every tile in it is back to the default, so it costs nothing until it's touched
+    pub fn unload_if_default(&mut self, chunk: usize)
+    where
+        T: PartialEq,
+    {
+        let default = &self.default;
+        if let Some(values) = &self.chunks[chunk] {
+            if values.iter().all(|v| v == default) {
+                self.chunks[chunk] = None;
+            }
+        }
+    }
+
+    fn load(&mut self, chunk: usize) -> &mut Vec<T> {
+        let default = &self.default;
+        self.chunks[chunk].get_or_insert_with(|| vec![default.clone(); (CHUNK_SIZE * CHUNK_SIZE) as usize])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        (0..self.len()).map(move |idx| &self[idx])
+    }
+
+    /// Runs `f` on every tile in turn. Chunks that aren't loaded are only loaded if `f` changes one of their tiles,
+    /// so resetting a layer or touching a few spots of it doesn't allocate the whole map.
+    pub fn update(&mut self, mut f: impl FnMut(usize, &mut T))
+    where
+        T: PartialEq,
+    {
+        for idx in 0..self.len() {
+            let (chunk, offset) = self.locate(idx);
+            match &mut self.chunks[chunk] {
+                Some(values) => f(idx, &mut values[offset]),
+                None => {
+                    let mut value = self.default.clone();
+                    f(idx, &mut value);
+                    if value != self.default {
+                        self.load(chunk)[offset] = value;
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<T: Clone> Index<usize> for Chunked<T> {
+    type Output = T;
+
+    fn index(&self, idx: usize) -> &T {
+        let (chunk, offset) = self.locate(idx);
+        match &self.chunks[chunk] {
+            Some(values) => &values[offset],
+            None => &self.default,
+        }
+    }
+}
+
+impl<T: Clone> IndexMut<usize> for Chunked<T> {
+    fn index_mut(&mut self, idx: usize) -> &mut T {
+        let (chunk, offset) = self.locate(idx);
+        &mut self.load(chunk)[offset]
+    }
+}
@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 
 use rltk::{self, DijkstraMap, Point};
 use serde::{Deserialize, Serialize};
@@ -6,7 +7,9 @@ use shipyard::{Component, EntityId, IntoIter, View};
 
 use crate::{
     ai::labors::AIBehaviors,
+    fluids::Fluid,
     map::{Map, TileType},
+    spatial::TileSet,
     RenderOrder,
 };
 
@@ -67,22 +70,38 @@ impl Default for Renderable {
 #[derive(Component, Clone, Debug, PartialEq)]
 pub struct Vision {
     pub visible_tiles: Vec<rltk::Point>,
+    visible: TileSet, // the same tiles as a bitset, kept in step by `set_visible`
     pub range: i32,
     pub dirty: bool,
 }
 
 impl Vision {
-    pub fn is_visible(&self, idx: Point) -> bool {
-        for p in self.visible_tiles.iter() {
-            if p.x == idx.x && p.y == idx.y {
-                return true;
-            }
+    pub fn new(range: i32) -> Vision {
+        Vision {
+            visible_tiles: Vec::new(),
+            visible: TileSet::default(),
+            range,
+            dirty: true,
         }
+    }
+
+    pub fn is_visible(&self, idx: Point) -> bool {
+        self.visible.contains(idx)
+    }
 
-        false
+    pub fn set_visible(&mut self, tiles: Vec<Point>) {
+        self.visible = TileSet::new(&tiles);
+        self.visible_tiles = tiles;
     }
 }
 
+/// Sheds light of `color` out to `range` tiles, from wherever it stands or from whoever has it equipped
+#[derive(Component, Copy, Clone, Debug, PartialEq)]
+pub struct LightSource {
+    pub color: rltk::RGBA,
+    pub range: i32,
+}
+
 #[derive(Component, Clone, Debug, PartialEq)]
 pub struct Name {
     pub name: String,
@@ -119,6 +138,32 @@ pub enum ActorType {
     Fish,
     Spawner,
     Wolf,
+    Deer,
+    Rabbit,
+}
+
+/// Squads
+
+#[derive(Component, Clone, Debug, PartialEq)]
+pub struct Squad {
+    pub leader: EntityId,
+    pub members: Vec<EntityId>,
+    pub target: Option<EntityId>,
+    pub state: SquadState,
+    pub min_engage_size: usize, // members that need to be rallied around the leader before engaging
+    pub strength: i32,          // combined max hp of the squad at its biggest, losses count against it
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SquadState {
+    Gathering,
+    Engaging,
+    Retreating,
+}
+
+#[derive(Component, Copy, Clone, Debug, PartialEq)]
+pub struct SquadMember {
+    pub squad: EntityId,
 }
 
 /// Structures
@@ -142,22 +187,58 @@ pub struct FishCleaner {}
 pub enum SpawnerType {
     Orc,
     Fish,
+    Wolf,
+    Deer,
+    Rabbit,
 }
 
-#[derive(Component, Copy, Clone, Debug, PartialEq)]
+#[derive(Component, Clone, Debug, PartialEq)]
 pub struct Spawner {
     pub typ: SpawnerType,
     pub rate: i32,
+    pub cap: usize,             // stops spawning while this many of its spawns are alive
+    pub spawned: Vec<EntityId>, // what it has spawned, the dead are pruned before each spawn
 }
 
 #[derive(Component, Copy, Clone, Debug, PartialEq)]
 pub struct Tree {}
 
+/// Ecology
+
+#[derive(Component, Copy, Clone, Debug, PartialEq)]
+pub struct Hunger {
+    pub hunger: i32,
+    pub max: i32, // starving at this point
+}
+
+#[derive(Component, Copy, Clone, Debug, PartialEq)]
+pub struct Edible {
+    pub nutrition: i32,
+}
+
+/// Wolves from the same den share what they see and hunt together
+#[derive(Component, Copy, Clone, Debug, PartialEq)]
+pub struct Pack {
+    pub den: EntityId,
+}
+
+/// Senses
+
+#[derive(Component, Copy, Clone, Debug, PartialEq)]
+pub struct Hearing {
+    pub acuity: i32,          // extra tiles away this actor can still pick up a noise
+    pub heard: Option<Point>, // where the last noise came from
+    pub heard_turn: i32,
+}
+
+#[derive(Component, Copy, Clone, Debug, PartialEq)]
+pub struct Sneaking {}
+
 /// Labors?
 
 /// Entity properties
 
-#[derive(Component, Copy, Clone, Debug, PartialEq)]
+#[derive(Component, Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum LocomotionType {
     Ground,
     Water,
@@ -169,9 +250,34 @@ pub struct Locomotive {
     pub speed: usize,
 }
 
+/// Turns owed for crossing rough ground or being slowed, negative when quick ground or haste has banked some time.
+/// Actors sit out a turn for every whole turn they owe, the player by letting the world take an extra turn,
+/// and take an extra turn for every whole turn banked.
+#[derive(Component, Copy, Clone, Debug, PartialEq)]
+pub struct Energy {
+    pub debt: f32,
+}
+
 #[derive(Component, Copy, Clone, Debug, PartialEq)]
 pub struct BlocksTile {}
 
+#[derive(Component, Copy, Clone, Debug, PartialEq)]
+pub struct BlocksVisibility {}
+
+/// Who besides anyone can get through a locked door
+#[derive(Clone, Debug, PartialEq)]
+pub enum DoorLock {
+    Faction(Faction),
+    Key(String), // name of the item that unlocks it
+}
+
+/// Opens when someone allowed through walks into it and closes once the doorway is clear
+#[derive(Component, Clone, Debug, PartialEq)]
+pub struct Door {
+    pub open: bool,
+    pub lock: Option<DoorLock>,
+}
+
 #[derive(Component, Copy, Clone, Debug, PartialEq)]
 pub struct CombatStats {
     pub max_hp: i32,
@@ -207,7 +313,7 @@ pub struct SpatialKnowledge {
 
 #[derive(Component)]
 pub struct DijkstraMapToMe {
-    pub map: DijkstraMap,
+    pub map: Arc<DijkstraMap>, // shared with the path cache
 }
 
 #[derive(Component)]
@@ -276,6 +382,9 @@ pub enum ItemType {
     Potion,
     Scroll,
     Fish,
+    Light,
+    Bucket,
+    Tool,
 }
 
 #[derive(Component, Copy, Clone, Debug, PartialEq)]
@@ -311,25 +420,160 @@ pub struct DealsDamage {
     pub damage: i32,
 }
 
+/// Confuses whoever it's used on for this many turns
 #[derive(Component, Clone, Copy)]
 pub struct Confusion {
     pub turns: i32,
 }
 
+/// Hastes whoever it's used on for this many turns
+#[derive(Component, Clone, Copy)]
+pub struct Haste {
+    pub turns: i32,
+}
+
 #[derive(Component)]
 pub struct AreaOfEffect {
     pub radius: i32,
 }
 
+/// Lets whoever has it in hand break walls by walking into them
+#[derive(Component, Clone, Copy)]
+pub struct DigsTerrain {
+    pub power: i32, // damage done to a tile per turn of digging
+}
+
+/// Status effects
+
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug, Deserialize)]
+pub enum StatusKind {
+    Poisoned,
+    Burning,
+    Confused,
+    Slowed,
+    Hasted,
+    Regenerating,
+    Stunned,
+}
+
+/// What happens when a status is applied to someone who already has it
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+pub enum Stacking {
+    Refresh,   // the longer of the two durations
+    Extend,    // the durations add up
+    Intensify, // another stack, each one ticks separately
+}
+
+// no status piles up past this many stacks
+pub const MAX_STACKS: i32 = 5;
+
+impl StatusKind {
+    pub fn stacking(&self) -> Stacking {
+        match self {
+            StatusKind::Poisoned | StatusKind::Regenerating => Stacking::Intensify,
+            StatusKind::Confused => Stacking::Extend,
+            StatusKind::Burning | StatusKind::Slowed | StatusKind::Hasted | StatusKind::Stunned => Stacking::Refresh,
+        }
+    }
+
+    /// The status that this one wipes out when applied
+    pub fn cancels(&self) -> Option<StatusKind> {
+        match self {
+            StatusKind::Slowed => Some(StatusKind::Hasted),
+            StatusKind::Hasted => Some(StatusKind::Slowed),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            StatusKind::Poisoned => "Poisoned",
+            StatusKind::Burning => "Burning",
+            StatusKind::Confused => "Confused",
+            StatusKind::Slowed => "Slowed",
+            StatusKind::Hasted => "Hasted",
+            StatusKind::Regenerating => "Regenerating",
+            StatusKind::Stunned => "Stunned",
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Status {
+    pub kind: StatusKind,
+    pub turns: i32,
+    pub stacks: i32,
+}
+
+/// Everything currently affecting an entity, ticked once a turn by the status system
+#[derive(Component, Clone, Debug, Default, PartialEq)]
+pub struct Statuses {
+    pub active: Vec<Status>,
+}
+
+impl Statuses {
+    pub fn get(&self, kind: StatusKind) -> Option<&Status> {
+        self.active.iter().find(|s| s.kind == kind)
+    }
+
+    pub fn has(&self, kind: StatusKind) -> bool {
+        self.get(kind).is_some()
+    }
+
+    /// Applies `turns` of `kind` following its stacking rule
+    pub fn add(&mut self, kind: StatusKind, turns: i32) {
+        if turns <= 0 {
+            return;
+        }
+
+        if let Some(cancelled) = kind.cancels() {
+            self.remove(cancelled);
+        }
+
+        if let Some(status) = self.active.iter_mut().find(|s| s.kind == kind) {
+            match kind.stacking() {
+                Stacking::Refresh => status.turns = i32::max(status.turns, turns),
+                Stacking::Extend => status.turns += turns,
+                Stacking::Intensify => {
+                    status.turns = i32::max(status.turns, turns);
+                    status.stacks = i32::min(status.stacks + 1, MAX_STACKS);
+                }
+            }
+        } else {
+            self.active.push(Status { kind, turns, stacks: 1 });
+        }
+    }
+
+    pub fn remove(&mut self, kind: StatusKind) {
+        self.active.retain(|s| s.kind != kind);
+    }
+}
+
 /// Fire components
 
+/// Sets whatever it's used on alight
 #[derive(Component, Clone, Copy)]
 pub struct Fire {
     pub turns: i32,
 }
 
 #[derive(Component, Clone, Copy)]
-pub struct Flammable {}
+pub struct Flammable {
+    pub fuel: i32, // turns left to burn, used up while on fire
+}
+
+/// Carries water to put out fires, filled from any water next to where it's used
+#[derive(Component, Clone, Copy)]
+pub struct Bucket {
+    pub full: bool,
+}
+
+/// Shatters where it's thrown, spilling its contents over every tile it hits
+#[derive(Component, Clone, Copy)]
+pub struct SpillsFluid {
+    pub fluid: Fluid,
+    pub amount: f32, // per tile
+}
 
 /// Save components
 
@@ -351,3 +595,79 @@ pub struct Particle {
     pub float_x: f32,
     pub float_y: f32,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn statuses_with(kind: StatusKind, turns: i32) -> Statuses {
+        let mut statuses = Statuses::default();
+        statuses.add(kind, turns);
+        statuses
+    }
+
+    #[test]
+    fn refreshing_keeps_the_longer_duration() {
+        let mut statuses = statuses_with(StatusKind::Burning, 5);
+
+        statuses.add(StatusKind::Burning, 3);
+        assert_eq!(statuses.get(StatusKind::Burning).unwrap().turns, 5);
+        statuses.add(StatusKind::Burning, 8);
+        assert_eq!(statuses.get(StatusKind::Burning).unwrap().turns, 8);
+        assert_eq!(statuses.active.len(), 1, "refreshing added a second status");
+    }
+
+    #[test]
+    fn extending_adds_the_durations() {
+        let mut statuses = statuses_with(StatusKind::Confused, 4);
+
+        statuses.add(StatusKind::Confused, 3);
+        assert_eq!(statuses.get(StatusKind::Confused).unwrap().turns, 7);
+        assert_eq!(statuses.get(StatusKind::Confused).unwrap().stacks, 1);
+    }
+
+    #[test]
+    fn intensifying_adds_stacks_up_to_the_cap() {
+        let mut statuses = statuses_with(StatusKind::Poisoned, 3);
+
+        statuses.add(StatusKind::Poisoned, 2);
+        let poison = statuses.get(StatusKind::Poisoned).unwrap();
+        assert_eq!((poison.turns, poison.stacks), (3, 2));
+
+        for _ in 0..MAX_STACKS * 2 {
+            statuses.add(StatusKind::Poisoned, 3);
+        }
+        assert_eq!(statuses.get(StatusKind::Poisoned).unwrap().stacks, MAX_STACKS);
+    }
+
+    #[test]
+    fn slow_and_haste_cancel_each_other() {
+        let mut statuses = statuses_with(StatusKind::Hasted, 5);
+        statuses.add(StatusKind::Poisoned, 5);
+
+        statuses.add(StatusKind::Slowed, 2);
+        assert!(!statuses.has(StatusKind::Hasted), "slowing left the haste");
+        assert!(statuses.has(StatusKind::Slowed));
+        assert!(
+            statuses.has(StatusKind::Poisoned),
+            "slowing cancelled an unrelated status"
+        );
+
+        statuses.add(StatusKind::Hasted, 2);
+        assert!(!statuses.has(StatusKind::Slowed), "hasting left the slow");
+        assert!(statuses.has(StatusKind::Hasted));
+    }
+
+    #[test]
+    fn nothing_is_added_for_no_turns() {
+        let mut statuses = statuses_with(StatusKind::Slowed, 0);
+        assert!(statuses.active.is_empty());
+
+        statuses.add(StatusKind::Hasted, 3);
+        statuses.add(StatusKind::Slowed, 0);
+        assert!(
+            statuses.has(StatusKind::Hasted),
+            "an empty slow still cancelled the haste"
+        );
+    }
+}
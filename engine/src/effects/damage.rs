@@ -1,10 +1,14 @@
-use shipyard::{AddComponent, Get, UniqueViewMut, ViewMut};
+use shipyard::{AddComponent, Get, UniqueViewMut, View, ViewMut};
 
 use super::*;
-use crate::{components::CombatStats, uniques::GameLog};
+use crate::{
+    components::{CombatStats, Edible},
+    uniques::GameLog,
+};
 
 pub fn inflict_damage(store: &mut AllStoragesViewMut, damage: &EffectSpawner) {
     let mut log = store.borrow::<UniqueViewMut<GameLog>>().unwrap();
+    let vedible = store.borrow::<View<Edible>>().unwrap();
 
     if let EffectType::Damage { amount, target } = &damage.effect_type {
         if let Ok(mut vs) = store.borrow::<ViewMut<CombatStats>>() {
@@ -12,6 +16,19 @@ pub fn inflict_damage(store: &mut AllStoragesViewMut, damage: &EffectSpawner) {
                 match (&vs).get(target) {
                     Ok(stats) => {
                         let mut stats = stats.clone();
+
+                        // predators eat what they kill
+                        if stats.hp > 0 && stats.hp <= *amount {
+                            if let (Some(creator), Ok(edible)) = (damage.creator, vedible.get(target)) {
+                                add_effect(
+                                    Some(creator),
+                                    EffectType::Eat {
+                                        amount: edible.nutrition,
+                                    },
+                                );
+                            }
+                        }
+
                         stats.hp -= amount;
                         vs.add_component_unchecked(target, stats);
                     }
@@ -1,9 +1,10 @@
-use std::cmp;
-
-use shipyard::{UniqueViewMut, ViewMut};
+use shipyard::{UniqueViewMut, View, ViewMut};
 
 use super::*;
-use crate::{components::Fire, map::Map};
+use crate::{
+    components::{Flammable, StatusKind, Statuses},
+    map::Map,
+};
 
 // pub fn inflict_fire_tile(gs: &mut State, effect: &EffectSpawner, tile_idx: usize) {
 
@@ -17,30 +18,45 @@ use crate::{components::Fire, map::Map};
 pub fn inflict_fire(store: &mut AllStoragesViewMut, effect: &EffectSpawner) {
     if let EffectType::Fire { turns, target } = &effect.effect_type {
         for target in get_effected_entities(&store, &target) {
-            let mut to_add_fire = vec![];
-            store.run(|vfire: ViewMut<Fire>| {
-                if let Ok(fire) = vfire.get(target) {
-                    to_add_fire.push((
-                        target,
-                        Fire {
-                            turns: cmp::max(fire.turns, *turns),
-                        },
-                    ));
-                } else {
-                    to_add_fire.push((target, Fire { turns: *turns }));
-                }
+            let (burning, fuel) = store.run(|vstatuses: View<Statuses>, vflammable: View<Flammable>| {
+                (
+                    vstatuses.get(target).map_or(false, |s| s.has(StatusKind::Burning)),
+                    vflammable.get(target).map(|f| f.fuel),
+                )
             });
 
-            for (target, fire) in to_add_fire {
-                store.add_component(target, fire);
+            // flammable things burn for as long as their fuel lasts, however they were lit
+            match fuel {
+                Ok(fuel) => {
+                    if !burning && fuel > 0 {
+                        add_status(store, target, StatusKind::Burning, fuel);
+                    }
+                }
+                Err(_) => add_status(store, target, StatusKind::Burning, *turns),
             }
         }
 
+        let tiles = get_effected_tiles(&store, &target);
         let mut map = store.borrow::<UniqueViewMut<Map>>().unwrap();
-        for tile_idx in get_effected_tiles(&store, &target) {
-            if map.is_flammable(tile_idx) {
-                map.fire_turns[tile_idx] += turns;
+        for tile_idx in tiles {
+            map.ignite(tile_idx);
+        }
+    }
+}
+
+pub fn extinguish(store: &mut AllStoragesViewMut, effect: &EffectSpawner) {
+    if let EffectType::Extinguish { target } = &effect.effect_type {
+        let mut vstatuses = store.borrow::<ViewMut<Statuses>>().unwrap();
+        for target in get_effected_entities(&store, &target) {
+            if let Ok(statuses) = (&mut vstatuses).get(target) {
+                statuses.remove(StatusKind::Burning);
             }
         }
+
+        let tiles = get_effected_tiles(&store, &target);
+        let mut map = store.borrow::<UniqueViewMut<Map>>().unwrap();
+        for tile_idx in tiles {
+            map.fire_turns[tile_idx] = 0;
+        }
     }
 }
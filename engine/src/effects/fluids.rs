@@ -0,0 +1,14 @@
+use shipyard::UniqueViewMut;
+
+use super::*;
+use crate::map::Map;
+
+pub fn spill(store: &mut AllStoragesViewMut, effect: &EffectSpawner) {
+    if let EffectType::Spill { fluid, amount, target } = &effect.effect_type {
+        let tiles = get_effected_tiles(&store, &target);
+        let mut map = store.borrow::<UniqueViewMut<Map>>().unwrap();
+        for tile_idx in tiles {
+            map.fluids[tile_idx][*fluid] += *amount;
+        }
+    }
+}
@@ -0,0 +1,14 @@
+use shipyard::{Get, ViewMut};
+
+use super::*;
+use crate::components::Hunger;
+
+pub fn eat(store: &AllStoragesViewMut, effect: &EffectSpawner) {
+    if let (Some(id), EffectType::Eat { amount }) = (effect.creator, &effect.effect_type) {
+        store.run(|mut vhunger: ViewMut<Hunger>| {
+            if let Ok(mut hunger) = (&mut vhunger).get(id) {
+                hunger.hunger = i32::max(hunger.hunger - amount, 0);
+            }
+        });
+    }
+}
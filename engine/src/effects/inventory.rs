@@ -82,8 +82,8 @@ pub fn drop_item(store: &AllStoragesViewMut, effect: &EffectSpawner) {
             }
         }
 
-        vpack.remove(id);
-        vequipped.remove(id);
+        vpack.remove(*target);
+        vequipped.remove(*target);
         vpos.add_component_unchecked(*target, Position { ps: vec![pos] });
     }
 }
@@ -6,22 +6,36 @@ pub use damage::inflict_damage;
 
 mod delete;
 
-mod confusion;
-pub use confusion::inflict_confusion;
-
 mod fire;
-pub use fire::inflict_fire;
+pub use fire::{extinguish, inflict_fire};
+
+mod fluids;
+pub use fluids::spill;
 
 mod heal;
 
+mod hunger;
+
 mod inventory;
 pub use inventory::pick_up;
 
 mod movement;
 
+pub mod noise;
+
+mod status;
+pub use status::add_status;
+
+mod terrain;
+pub use terrain::demolish;
+
 use shipyard::{AllStoragesViewMut, EntityId, Get, UniqueView, View};
 
-use crate::{components::Position, map::Map};
+use crate::{
+    components::{Position, StatusKind},
+    fluids::Fluid,
+    map::Map,
+};
 
 lazy_static! {
     pub static ref EFFECT_QUEUE: Mutex<VecDeque<EffectSpawner>> = Mutex::new(VecDeque::new());
@@ -29,17 +43,62 @@ lazy_static! {
 
 #[derive(Clone)]
 pub enum EffectType {
-    Damage { amount: i32, target: Targets },
-    Confusion { turns: i32, target: Targets },
-    Fire { turns: i32, target: Targets },
-    PickUp { entity: EntityId },
-    Drop { entity: EntityId },
+    Damage {
+        amount: i32,
+        target: Targets,
+    },
+    Status {
+        kind: StatusKind,
+        turns: i32,
+        target: Targets,
+    },
+    TickStatuses {
+        entity: EntityId,
+    },
+    Fire {
+        turns: i32,
+        target: Targets,
+    },
+    Extinguish {
+        target: Targets,
+    },
+    Spill {
+        fluid: Fluid,
+        amount: f32,
+        target: Targets,
+    },
+    Demolish {
+        amount: i32,
+        target: Targets,
+    },
+    PickUp {
+        entity: EntityId,
+    },
+    Drop {
+        entity: EntityId,
+    },
     Explore {},
-    Heal { amount: i32, target: Targets },
-    Move { tile_idx: usize },
-    MoveOrAttack { tile_idx: usize },
+    Heal {
+        amount: i32,
+        target: Targets,
+    },
+    Move {
+        tile_idx: usize,
+    },
+    MoveOrAttack {
+        tile_idx: usize,
+    },
     Wait {},
-    Delete { entity: EntityId },
+    Delete {
+        entity: EntityId,
+    },
+    Eat {
+        amount: i32,
+    },
+    Noise {
+        tile_idx: usize,
+        volume: i32,
+    },
 }
 
 #[derive(Clone)]
@@ -69,8 +128,12 @@ pub fn run_effects_queue(mut store: AllStoragesViewMut) {
         if let Some(effect) = &effect {
             match effect.effect_type {
                 EffectType::Damage { .. } => damage::inflict_damage(&mut store, effect),
-                EffectType::Confusion { .. } => confusion::inflict_confusion(&mut store, effect),
+                EffectType::Status { .. } => status::apply_status(&store, effect),
+                EffectType::TickStatuses { .. } => status::tick_statuses(&store, effect),
                 EffectType::Fire { .. } => fire::inflict_fire(&mut store, effect),
+                EffectType::Extinguish { .. } => fire::extinguish(&mut store, effect),
+                EffectType::Spill { .. } => fluids::spill(&mut store, effect),
+                EffectType::Demolish { .. } => terrain::demolish(&mut store, effect),
                 EffectType::PickUp { .. } => inventory::pick_up(&store, effect),
                 EffectType::Drop { .. } => inventory::drop_item(&store, effect),
                 EffectType::Explore {} => movement::autoexplore(&store, effect),
@@ -79,6 +142,8 @@ pub fn run_effects_queue(mut store: AllStoragesViewMut) {
                 EffectType::Wait {} => movement::skip_turn(&store, effect),
                 EffectType::Delete { .. } => delete::delete(&mut store, effect),
                 EffectType::MoveOrAttack { .. } => movement::try_move_or_attack(&store, effect, true),
+                EffectType::Eat { .. } => hunger::eat(&store, effect),
+                EffectType::Noise { .. } => noise::make_noise(&store, effect),
             }
         } else {
             // this happens when the queue is empty
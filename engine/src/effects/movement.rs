@@ -1,17 +1,22 @@
-use rltk::{DijkstraMap, Point};
-use shipyard::{AddComponent, Get, UniqueViewMut, View, ViewMut};
+use rltk::{BaseMap, DijkstraMap, Point};
+use shipyard::{AddComponent, Get, IntoIter, Remove, UniqueViewMut, View, ViewMut};
 
 use super::*;
 use crate::{
+    chunks::Chunked,
     components::{
-        BlocksTile, CombatStats, Fire, IsCamera, LocomotionType, Locomotive, Player, Position, SpatialKnowledge,
-        Vision, WantsToAttack,
+        Actor, BlocksTile, BlocksVisibility, CombatStats, DigsTerrain, Door, DoorLock, Energy, Equipped, InBackpack,
+        IsCamera, LocomotionType, Locomotive, Name, Player, Position, Renderable, Sneaking, SpatialKnowledge,
+        StatusKind, Statuses, Vision, WantsToAttack,
     },
     map::{Map, TileType},
-    uniques::PPoint,
-    utils::{dijkstra_backtrace, normalize, point_plus},
+    tiles::tile_info,
+    uniques::{GameLog, PPoint, RNG},
+    utils::{closest_point, normalize, point_plus},
 };
 
+pub const MAX_BANKED_TURNS: f32 = 1.;
+
 pub fn try_move_or_attack(store: &AllStoragesViewMut, effect: &EffectSpawner, attack: bool) {
     let mut map = store.borrow::<UniqueViewMut<Map>>().unwrap();
 
@@ -36,13 +41,46 @@ pub fn try_move_or_attack(store: &AllStoragesViewMut, effect: &EffectSpawner, at
         0
     };
 
+    let (stunned, confused) = store.run(|vstatuses: View<Statuses>| {
+        vstatuses.get(entity).map_or((false, false), |s| {
+            (s.has(StatusKind::Stunned), s.has(StatusKind::Confused))
+        })
+    });
+    if stunned && !is_camera {
+        if is_player {
+            let mut log = store.borrow::<UniqueViewMut<GameLog>>().unwrap();
+            log.messages.push("You are stunned".to_string());
+        }
+        return;
+    }
+
     if let Ok(pos) = (&mut vpos).get(entity) {
         let tp = map.idx_point(tile_idx);
-        let dp = Point {
-            x: normalize(tp.x - pos.ps[0].x),
-            y: normalize(tp.y - pos.ps[0].y),
+
+        // moves steer the first tile of the body, attacks swing from whichever part of it is closest to the target
+        let from = if attack { closest_point(&pos.ps, tp) } else { pos.ps[0] };
+        let mut dp = Point {
+            x: normalize(tp.x - from.x),
+            y: normalize(tp.y - from.y),
         };
 
+        // the confused stumble off in any direction half the time
+        if confused && !is_camera {
+            let mut rng = store.borrow::<UniqueViewMut<RNG>>().unwrap();
+            if rng.0.range(0, 2) == 0 {
+                dp = Point {
+                    x: rng.0.range(-1, 2),
+                    y: rng.0.range(-1, 2),
+                };
+            }
+        }
+
+        // doors open for whoever is allowed through, bumping a locked one as the player says so
+        if !is_camera && open_doors(&store, &map, entity, &pos, dp) && is_player {
+            let mut log = store.borrow::<UniqueViewMut<GameLog>>().unwrap();
+            log.messages.push("The door is locked".to_string());
+        }
+
         let canmove = can_move(&store, &map, entity, &pos, dp);
 
         if !is_camera && attack {
@@ -54,6 +92,29 @@ pub fn try_move_or_attack(store: &AllStoragesViewMut, effect: &EffectSpawner, at
             }
         }
 
+        // walking into a wall with a pick in hand digs at it instead
+        if !is_camera && !canmove {
+            let wall = point_plus(from, dp);
+            if map.in_bounds(wall.x, wall.y) && map.is_breakable(map.point_idx(wall)) {
+                if let Some(power) = dig_power(&store, entity) {
+                    let tile_idx = map.point_idx(wall);
+                    add_effect(
+                        Some(entity),
+                        EffectType::Demolish {
+                            amount: power,
+                            target: Targets::Tile { tile_idx },
+                        },
+                    );
+                    if is_player {
+                        let mut log = store.borrow::<UniqueViewMut<GameLog>>().unwrap();
+                        let name = &tile_info(map.tiles[tile_idx]).name;
+                        log.messages.push(format!("You dig at the {}", name.to_lowercase()));
+                    }
+                    return;
+                }
+            }
+        }
+
         // do movement
         if is_camera || canmove {
             // dbg!("can move");
@@ -61,21 +122,69 @@ pub fn try_move_or_attack(store: &AllStoragesViewMut, effect: &EffectSpawner, at
                 vs.dirty = true;
             }
 
-            // for pos in pos.ps.iter_mut() {
-            for i in 0..pos.ps.len() {
-                let oldidx = map.point_idx(pos.ps[i]);
-
-                pos.ps[i] = point_plus(pos.ps[i], dp);
-
-                let idx = map.point_idx(pos.ps[i]);
+            // free the whole old footprint before blocking the new one, the two overlap for anything bigger than a tile
+            for p in pos.ps.iter() {
+                let oldidx = map.point_idx(*p);
                 map.blocked[oldidx] = false;
+            }
+
+            for p in pos.ps.iter_mut() {
+                *p = point_plus(*p, dp);
+                let idx = map.point_idx(*p);
                 map.blocked[idx] = true;
             }
 
+            if !is_camera {
+                let (sneaking, mtype) = store.run(|vsneaking: View<Sneaking>, vloco: View<Locomotive>| {
+                    (
+                        vsneaking.get(entity).is_ok(),
+                        vloco.get(entity).map_or(LocomotionType::Ground, |l| l.mtype),
+                    )
+                });
+
+                // rough ground costs turns, quick ground banks a little time against the next stretch of it
+                let mut venergy = store.borrow::<ViewMut<Energy>>().unwrap();
+                let cost = tile_info(map.tiles[map.point_idx(pos.ps[0])]).move_cost(mtype);
+                let debt = (&venergy).get(entity).map_or(0., |e| e.debt) + cost - 1.;
+                venergy.add_component_unchecked(
+                    entity,
+                    Energy {
+                        debt: debt.max(-MAX_BANKED_TURNS),
+                    },
+                );
+
+                // swimmers glide through the water without a sound, no need to spread a noise for them
+                let silent = mtype == LocomotionType::Water;
+                let volume = if sneaking {
+                    noise::SNEAK_NOISE
+                } else {
+                    noise::MOVE_NOISE
+                };
+                if !silent {
+                    add_effect(
+                        Some(entity),
+                        EffectType::Noise {
+                            tile_idx: map.point_idx(pos.ps[0]),
+                            volume,
+                        },
+                    );
+                }
+            }
+
             // If this is a player, change the position in resources according to first in pos.ps
             if is_player {
                 let mut ppos = store.borrow::<UniqueViewMut<PPoint>>().unwrap();
+                let old_region = map.regions[map.point_idx(ppos.0)];
                 *ppos = PPoint(pos.ps[0]);
+
+                // let the player know when they cross into another named part of the map
+                let idx = map.point_idx(pos.ps[0]);
+                if map.regions[idx] != old_region {
+                    if let Some(name) = map.region_name(idx) {
+                        let mut log = store.borrow::<UniqueViewMut<GameLog>>().unwrap();
+                        log.messages.push(format!("You enter {}", name));
+                    }
+                }
             }
 
             return;
@@ -84,14 +193,24 @@ pub fn try_move_or_attack(store: &AllStoragesViewMut, effect: &EffectSpawner, at
     }
 }
 
+// the best digging tool an entity has in hand
+fn dig_power(store: &AllStoragesViewMut, entity: EntityId) -> Option<i32> {
+    store.run(|vequipped: View<Equipped>, vdigs: View<DigsTerrain>| {
+        (&vequipped, &vdigs)
+            .iter()
+            .filter(|(equipped, _)| equipped.owner == entity)
+            .map(|(_, digs)| digs.power)
+            .max()
+    })
+}
+
 pub fn autoexplore(store: &AllStoragesViewMut, effect: &EffectSpawner) {
     if let (Some(entity), EffectType::Explore {}) = (effect.creator, effect.effect_type.clone()) {
         // TODO Check for adjacent enemies and attack them
 
-        // Use djikstras to find nearest unexplored tile
+        // Use djikstras out from every unexplored tile, then step downhill towards the nearest one
         let mut target = (0 as usize, std::f32::MAX); // tile_idx, distance
         {
-            let dijkstra_map: DijkstraMap;
             let map = &mut store.borrow::<UniqueViewMut<Map>>().unwrap();
 
             let vpos = store.borrow::<View<Position>>().unwrap();
@@ -113,15 +232,14 @@ pub fn autoexplore(store: &AllStoragesViewMut, effect: &EffectSpawner) {
 
             let e_idx = map.point_idx(e_pos.any_point());
 
-            let starts: Vec<usize> = e_pos.idxes(map);
-            dijkstra_map = rltk::DijkstraMap::new(map.width, map.height, &starts, &**map, 800.0);
-            for (i, tile) in map.tiles.iter().enumerate() {
-                if *tile != TileType::Wall && !e_space.tiles.contains_key(&i) {
-                    let distance_to_start = dijkstra_map.map[i];
+            let starts: Vec<usize> = (0..map.tiles.len())
+                .filter(|i| map.tiles[*i] != TileType::Wall && !e_space.tiles.contains_key(i))
+                .collect();
+            let dijkstra_map = DijkstraMap::new(map.width, map.height, &starts, &**map, 800.0);
 
-                    if distance_to_start < target.1 {
-                        target = (i, distance_to_start)
-                    }
+            for (i, _) in map.get_available_exits(e_idx) {
+                if dijkstra_map.map[i] < target.1 {
+                    target = (i, dijkstra_map.map[i]);
                 }
             }
 
@@ -132,11 +250,7 @@ pub fn autoexplore(store: &AllStoragesViewMut, effect: &EffectSpawner) {
 
             // log.messages.push(format!("Closest unexplored tile is {} steps away", target.1));
 
-            map.dijkstra_map = dijkstra_map.map.clone();
-
-            // We have a target tile. Now follow the path up the chain
-            let t = dijkstra_backtrace(dijkstra_map, map, e_idx, target.0);
-            target = (t, 1.0);
+            map.dijkstra_map = Chunked::from_vec((map.width, map.height), dijkstra_map.map.clone(), -1.0);
         }
 
         // Send a move command
@@ -158,17 +272,70 @@ pub fn autoexplore(store: &AllStoragesViewMut, effect: &EffectSpawner) {
     }
 }
 
+// resting a turn lets wounds start to close
 pub fn skip_turn(store: &AllStoragesViewMut, effect: &EffectSpawner) {
-    let mut vstats = store.borrow::<ViewMut<CombatStats>>().unwrap();
-    let vfire = store.borrow::<View<Fire>>().unwrap();
-
     if let Some(id) = effect.creator {
-        if let Ok(stats) = (&mut vstats).get(id) {
-            if let Err(_) = vfire.get(id) {
-                stats.hp = i32::min(stats.hp + stats.regen_rate, stats.max_hp);
+        add_status(store, id, StatusKind::Regenerating, 1);
+    }
+}
+
+// opens the closed doors in the way that `entity` may go through, returns whether a locked one is still in the way
+fn open_doors(store: &AllStoragesViewMut, map: &Map, entity: EntityId, pos: &Position, dp: Point) -> bool {
+    let mut vdoor = store.borrow::<ViewMut<Door>>().unwrap();
+    let mut vblocks = store.borrow::<ViewMut<BlocksTile>>().unwrap();
+    let mut vblocksvis = store.borrow::<ViewMut<BlocksVisibility>>().unwrap();
+    let mut vrend = store.borrow::<ViewMut<Renderable>>().unwrap();
+
+    let mut locked = false;
+    for pos in pos.ps.iter() {
+        let dest_idx = map.xy_idx(pos.x + dp.x, pos.y + dp.y);
+        for door_id in map.tile_content[dest_idx].iter() {
+            if let Ok(door) = (&mut vdoor).get(*door_id) {
+                if door.open {
+                    continue;
+                }
+
+                if !can_unlock(store, entity, &door.lock) {
+                    locked = true;
+                    continue;
+                }
+
+                door.open = true;
+                vblocks.remove(*door_id);
+                vblocksvis.remove(*door_id);
+                if let Ok(rend) = (&mut vrend).get(*door_id) {
+                    rend.glyph = rltk::to_cp437('/');
+                }
+
+                add_effect(
+                    Some(entity),
+                    EffectType::Noise {
+                        tile_idx: dest_idx,
+                        volume: noise::DOOR_NOISE,
+                    },
+                );
             }
         }
     }
+
+    locked
+}
+
+fn can_unlock(store: &AllStoragesViewMut, entity: EntityId, lock: &Option<DoorLock>) -> bool {
+    match lock {
+        None => true,
+        Some(DoorLock::Faction(faction)) => {
+            let vactor = store.borrow::<View<Actor>>().unwrap();
+            vactor.get(entity).map_or(false, |actor| actor.faction == *faction)
+        }
+        Some(DoorLock::Key(key)) => {
+            let vpack = store.borrow::<View<InBackpack>>().unwrap();
+            let vname = store.borrow::<View<Name>>().unwrap();
+            (&vpack, &vname)
+                .iter()
+                .any(|(pack, name)| pack.owner == entity && name.name == *key)
+        }
+    }
 }
 
 pub fn can_move(store: &AllStoragesViewMut, map: &Map, entity: EntityId, pos: &Position, dp: Point) -> bool {
@@ -177,14 +344,15 @@ pub fn can_move(store: &AllStoragesViewMut, map: &Map, entity: EntityId, pos: &P
 
     if let Ok(loco) = vloco.get(entity) {
         for pos in pos.ps.iter() {
-            // check for tiles that block
-            let dest_idx = map.xy_idx(pos.x + dp.x, pos.y + dp.y);
-            // let dest_idx = map.point_idx(tp);
-            if loco.mtype == LocomotionType::Ground && map.blocks_movement(dest_idx) {
+            // the whole body has to fit, any tile off the map or blocked stops it
+            if !map.in_bounds(pos.x + dp.x, pos.y + dp.y) {
                 return false;
             }
 
-            if loco.mtype == LocomotionType::Water && map.tiles[dest_idx] != TileType::Water {
+            // check for tiles that block
+            let dest_idx = map.xy_idx(pos.x + dp.x, pos.y + dp.y);
+            // let dest_idx = map.point_idx(tp);
+            if !map.is_passable(dest_idx, loco.mtype) {
                 return false;
             }
 
@@ -219,6 +387,7 @@ pub fn get_target(
     dp: Point,
 ) -> Option<EntityId> {
     let vstats = store.borrow::<View<CombatStats>>().unwrap();
+    let vdoor = store.borrow::<View<Door>>().unwrap();
 
     // check for combat stats on entity
     if let Err(_) = vstats.get(entity) {
@@ -227,6 +396,10 @@ pub fn get_target(
     }
 
     for pos in pos.ps.iter() {
+        if !map.in_bounds(pos.x + dp.x, pos.y + dp.y) {
+            continue;
+        }
+
         let dest_idx = map.xy_idx(pos.x + dp.x, pos.y + dp.y);
         // let dest_idx = map.point_idx(tp);
         for potential_target in map.tile_content[dest_idx].iter() {
@@ -234,6 +407,11 @@ pub fn get_target(
                 continue;
             }
 
+            // bumping a door only ever tries to open it, breaking one down takes fire or explosions
+            if vdoor.get(*potential_target).is_ok() {
+                continue;
+            }
+
             // dbg!("does enetiy have stats");
             match vstats.get(*potential_target) {
                 Ok(_cs) => return Some(*potential_target),
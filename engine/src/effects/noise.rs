@@ -0,0 +1,81 @@
+use std::collections::{HashMap, VecDeque};
+
+use shipyard::{Get, UniqueView, View, ViewMut};
+
+use super::*;
+use crate::{
+    components::{Actor, Hearing},
+    uniques::Turn,
+    utils::get_neighbors,
+};
+
+pub const MOVE_NOISE: i32 = 3;
+pub const SNEAK_NOISE: i32 = 1;
+pub const MELEE_NOISE: i32 = 6;
+pub const EXPLOSION_NOISE: i32 = 12;
+pub const FIRE_NOISE: i32 = 3;
+pub const DOOR_NOISE: i32 = 2;
+pub const DIG_NOISE: i32 = 5;
+
+// the best hearing in the game, bounds how far a noise has to be propagated
+const MAX_ACUITY: i32 = 4;
+
+// Noise spreads over walkable tiles losing a point of volume per step, so walls and closed doors muffle it
+pub fn make_noise(store: &AllStoragesViewMut, effect: &EffectSpawner) {
+    if let EffectType::Noise { tile_idx, volume } = effect.effect_type {
+        let map = store.borrow::<UniqueView<Map>>().unwrap();
+        let turn = store.borrow::<UniqueView<Turn>>().unwrap();
+        let vactor = store.borrow::<View<Actor>>().unwrap();
+        let mut vhearing = store.borrow::<ViewMut<Hearing>>().unwrap();
+
+        // allies already know what their own side is up to
+        let faction = effect.creator.and_then(|c| vactor.get(c).ok()).map(|a| a.faction);
+
+        let source = map.idx_point(tile_idx);
+        let max_steps = volume + MAX_ACUITY;
+
+        // only the handful of tiles within earshot are ever visited, every footstep makes a noise
+        let mut steps: HashMap<usize, i32> = HashMap::new();
+        let mut queue = VecDeque::new();
+        steps.insert(tile_idx, 0);
+        queue.push_back(tile_idx);
+
+        while let Some(idx) = queue.pop_front() {
+            let step = steps[&idx];
+            for e in map.tile_content[idx].iter() {
+                if Some(*e) == effect.creator {
+                    continue;
+                }
+
+                if let Ok(actor) = vactor.get(*e) {
+                    if Some(actor.faction) == faction {
+                        continue;
+                    }
+                }
+
+                if let Ok(hearing) = (&mut vhearing).get(*e) {
+                    if step <= volume + hearing.acuity {
+                        hearing.heard = Some(source);
+                        hearing.heard_turn = turn.0;
+                    }
+                }
+            }
+
+            if step >= max_steps {
+                continue;
+            }
+
+            for n in get_neighbors(map.idx_point(idx)) {
+                if !map.in_bounds(n.x, n.y) {
+                    continue;
+                }
+
+                let nidx = map.point_idx(n);
+                if !steps.contains_key(&nidx) && !map.blocks_movement(nidx) && !map.view_blocked[nidx] {
+                    steps.insert(nidx, step + 1);
+                    queue.push_back(nidx);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use shipyard::{AddComponent, Get, Remove, ViewMut};
+
+use super::movement::MAX_BANKED_TURNS;
+use super::*;
+use crate::components::{CombatStats, Energy, StatusKind, Statuses};
+
+// turns of energy a tick of slow costs, or of haste banks
+const SPEED_PER_TICK: f32 = 0.5;
+
+/// Puts `turns` of `kind` on `entity`, stacking with whatever it already has
+pub fn add_status(store: &AllStoragesViewMut, entity: EntityId, kind: StatusKind, turns: i32) {
+    let mut vstatuses = store.borrow::<ViewMut<Statuses>>().unwrap();
+    if let Ok(statuses) = (&mut vstatuses).get(entity) {
+        statuses.add(kind, turns);
+    } else {
+        let mut statuses = Statuses::default();
+        statuses.add(kind, turns);
+        vstatuses.add_component_unchecked(entity, statuses);
+    }
+}
+
+pub fn apply_status(store: &AllStoragesViewMut, effect: &EffectSpawner) {
+    if let EffectType::Status { kind, turns, target } = &effect.effect_type {
+        for entity in get_effected_entities(&store, &target) {
+            add_status(store, entity, *kind, *turns);
+        }
+    }
+}
+
+// one turn of every status on the entity, anything that runs out is dropped
+pub fn tick_statuses(store: &AllStoragesViewMut, effect: &EffectSpawner) {
+    if let EffectType::TickStatuses { entity } = effect.effect_type {
+        let mut vstatuses = store.borrow::<ViewMut<Statuses>>().unwrap();
+        let mut vstats = store.borrow::<ViewMut<CombatStats>>().unwrap();
+        let mut venergy = store.borrow::<ViewMut<Energy>>().unwrap();
+
+        let expired = {
+            let statuses = if let Ok(statuses) = (&mut vstatuses).get(entity) {
+                statuses
+            } else {
+                return;
+            };
+
+            let burning = statuses.has(StatusKind::Burning);
+            for status in statuses.active.iter_mut() {
+                match status.kind {
+                    StatusKind::Poisoned => {
+                        add_effect(
+                            None,
+                            EffectType::Damage {
+                                amount: status.stacks,
+                                target: Targets::Single { target: entity },
+                            },
+                        );
+                    }
+                    StatusKind::Burning => {
+                        if vstats.get(entity).is_ok() {
+                            add_effect(
+                                None,
+                                EffectType::Damage {
+                                    amount: 1,
+                                    target: Targets::Single { target: entity },
+                                },
+                            );
+                        }
+                    }
+                    StatusKind::Regenerating => {
+                        // nobody heals while they're on fire
+                        if let Ok(stats) = (&mut vstats).get(entity) {
+                            if !burning {
+                                stats.hp = i32::min(stats.hp + stats.regen_rate * status.stacks, stats.max_hp);
+                            }
+                        }
+                    }
+                    StatusKind::Slowed | StatusKind::Hasted => {
+                        // actors sit out a turn for every whole turn of energy they owe and get one back for each banked
+                        let change = if status.kind == StatusKind::Slowed {
+                            SPEED_PER_TICK
+                        } else {
+                            -SPEED_PER_TICK
+                        };
+                        let debt = (&venergy).get(entity).map_or(0., |e| e.debt) + change;
+                        venergy.add_component_unchecked(
+                            entity,
+                            Energy {
+                                debt: debt.max(-MAX_BANKED_TURNS),
+                            },
+                        );
+                    }
+                    StatusKind::Confused | StatusKind::Stunned => {}
+                }
+
+                status.turns -= 1;
+            }
+
+            statuses.active.retain(|s| s.turns > 0);
+            statuses.active.is_empty()
+        };
+        if expired {
+            vstatuses.remove(entity);
+        }
+    }
+}
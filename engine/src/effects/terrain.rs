@@ -0,0 +1,80 @@
+use std::collections::HashSet;
+
+use shipyard::{IntoIter, UniqueViewMut, ViewMut};
+
+use super::*;
+use crate::{
+    components::{SpatialKnowledge, Vision},
+    effects::noise::DIG_NOISE,
+    entity_factory,
+    map::TileType,
+    path_cache::PathCache,
+    tiles::tile_info,
+};
+
+// Wears down walls, anything that breaks turns into its broken tile and leaves its drops behind
+pub fn demolish(store: &mut AllStoragesViewMut, effect: &EffectSpawner) {
+    if let EffectType::Demolish { amount, target } = &effect.effect_type {
+        let tiles = get_effected_tiles(&store, &target);
+
+        let mut broken: Vec<(usize, TileType)> = vec![];
+        {
+            let mut map = store.borrow::<UniqueViewMut<Map>>().unwrap();
+            for tile_idx in tiles {
+                if !map.is_breakable(tile_idx) {
+                    continue;
+                }
+
+                add_effect(
+                    effect.creator,
+                    EffectType::Noise {
+                        tile_idx,
+                        volume: DIG_NOISE,
+                    },
+                );
+
+                if let Some(old) = map.damage_tile(tile_idx, *amount) {
+                    broken.push((tile_idx, old));
+                }
+            }
+        }
+
+        if broken.is_empty() {
+            return;
+        }
+
+        // paths through the new hole are found right away, not whenever the path cache next refreshes
+        {
+            let map = store.borrow::<UniqueView<Map>>().unwrap();
+            let chunks: HashSet<usize> = broken.iter().map(|(idx, _)| map.tiles.chunk_of(*idx)).collect();
+            store
+                .borrow::<UniqueViewMut<PathCache>>()
+                .unwrap()
+                .invalidate_chunks(&chunks);
+        }
+
+        // anyone watching sees the wall come down, and the hole changes what everyone can see
+        store.run(
+            |map: UniqueView<Map>, mut vvs: ViewMut<Vision>, mut vspace: ViewMut<SpatialKnowledge>| {
+                for (vs, space) in (&mut vvs, &mut vspace).iter() {
+                    for (tile_idx, _) in broken.iter() {
+                        if vs.is_visible(map.idx_point(*tile_idx)) {
+                            if let Some((tile, _)) = space.tiles.get_mut(tile_idx) {
+                                *tile = map.tiles[*tile_idx];
+                            }
+                        }
+                    }
+                }
+                for vs in (&mut vvs).iter() {
+                    vs.dirty = true;
+                }
+            },
+        );
+
+        for (tile_idx, old) in broken {
+            if let Some(drop) = &tile_info(old).drops {
+                entity_factory::spawn_entity(store, &(tile_idx, drop.clone()));
+            }
+        }
+    }
+}
@@ -1,12 +1,16 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 
 use crate::ai::labors::AIBehaviors;
 use crate::components::{
-    Actor, ActorType, AreaOfEffect, BlocksTile, ChiefHouse, CombatStats, Confusion, Consumable, DealsDamage,
-    DijkstraMapToMe, EquipmentSlot, Equippable, Faction, Fire, FishCleaner, Flammable, Inventory, Item, ItemType,
-    LocomotionType, Locomotive, LumberMill, MeleeDefenseBonus, MeleePowerBonus, Name, PlankHouse, Player, Position,
-    ProvidesHealing, Ranged, Renderable, SpatialKnowledge, Spawner, SpawnerType, Tree, Vision,
+    Actor, ActorType, AreaOfEffect, BlocksTile, BlocksVisibility, Bucket, ChiefHouse, CombatStats, Confusion,
+    Consumable, DealsDamage, DigsTerrain, DijkstraMapToMe, Door, DoorLock, Edible, EquipmentSlot, Equippable, Faction,
+    Fire, FishCleaner, Flammable, Haste, Hearing, Hunger, Inventory, Item, ItemType, LightSource, LocomotionType,
+    Locomotive, LumberMill, MeleeDefenseBonus, MeleePowerBonus, Name, Pack, PlankHouse, Player, Position,
+    ProvidesHealing, Ranged, Renderable, SpatialKnowledge, Spawner, SpawnerType, SpillsFluid, Squad, SquadMember,
+    SquadState, Tree, Velocity, Vision,
 };
+use crate::fluids::Fluid;
 use crate::map::{Map, TileType};
 use crate::palette::Palette;
 use crate::rect::Rect;
@@ -14,7 +18,7 @@ use crate::systems::system_fire::NEW_FIRE_TURNS;
 use crate::weighted_table::WeightedTable;
 use crate::RenderOrder;
 use rltk::{DijkstraMap, Point, RandomNumberGenerator};
-use shipyard::{AllStoragesViewMut, EntityId, UniqueView};
+use shipyard::{AllStoragesViewMut, EntityId, Get, UniqueView, ViewMut};
 
 const MAX_MONSTERS: i32 = 4;
 
@@ -24,6 +28,7 @@ pub fn room_table(depth: i32) -> WeightedTable {
         .add("Goblin", 10)
         .add("Orc", 1 + depth)
         .add("Health Potion", 7)
+        .add("Haste Potion", 2)
         .add("Fireball Scroll", 2 + depth)
         .add("Confusion Scroll", 2 + depth)
         .add("Magic Missile Scroll", 4)
@@ -31,6 +36,11 @@ pub fn room_table(depth: i32) -> WeightedTable {
         .add("Shield", 2)
         .add("Longsword", depth - 1)
         .add("Tower Shield", depth - 1)
+        .add("Lantern", 2)
+        .add("Bucket", 1)
+        .add("Oil Flask", 2)
+        .add("Poison Gas Potion", 1 + depth)
+        .add("Pickaxe", 2)
 }
 
 pub fn spawn_room(store: &mut AllStoragesViewMut, map: &Map, room: &Rect, depth: i32) {
@@ -51,7 +61,18 @@ pub fn spawn_room(store: &mut AllStoragesViewMut, map: &Map, room: &Rect, depth:
 }
 
 pub fn spawn_region(store: &mut AllStoragesViewMut, area: &[usize], map_depth: i32) {
-    let spawn_table = room_table(map_depth);
+    for spawn in region_spawns(area, map_depth).iter() {
+        spawn_entity(store, spawn);
+    }
+}
+
+/// Rolls what should spawn in an area without spawning it, for builders that collect a spawn list
+pub fn region_spawns(area: &[usize], map_depth: i32) -> Vec<(usize, String)> {
+    region_spawns_from(area, map_depth, &room_table(map_depth))
+}
+
+/// Same as `region_spawns` but rolling on the given table, for themed areas
+pub fn region_spawns_from(area: &[usize], map_depth: i32, spawn_table: &WeightedTable) -> Vec<(usize, String)> {
     let mut spawn_points: HashMap<usize, String> = HashMap::new();
     let mut areas: Vec<usize> = Vec::from(area);
 
@@ -63,7 +84,7 @@ pub fn spawn_region(store: &mut AllStoragesViewMut, area: &[usize], map_depth: i
             rng.roll_dice(1, MAX_MONSTERS + 3) + (map_depth - 1) - 3,
         );
         if num_spawns == 0 {
-            return;
+            return vec![];
         }
 
         for _i in 0..num_spawns {
@@ -78,21 +99,19 @@ pub fn spawn_region(store: &mut AllStoragesViewMut, area: &[usize], map_depth: i
         }
     }
 
-    // Actually spawn the monsters
-    for spawn in spawn_points.iter() {
-        spawn_entity(store, &spawn);
-    }
+    spawn_points.into_iter().collect()
 }
 
 /// Spawns a named entity (name in tuple.1) at the location in (tuple.0)
-fn spawn_entity(store: &mut AllStoragesViewMut, spawn: &(&usize, &String)) {
-    let (x, y) = store.run(|map: UniqueView<Map>| map.idx_xy(*spawn.0));
+pub fn spawn_entity(store: &mut AllStoragesViewMut, spawn: &(usize, String)) {
+    let (x, y) = store.run(|map: UniqueView<Map>| map.idx_xy(spawn.0));
 
     match spawn.1.as_ref() {
         "Wolf" => wolf(store, x, y),
         "Goblin" => goblin(store, x, y),
         "Orc" => orc(store, x, y),
         "Health Potion" => health_potion(store, x, y),
+        "Haste Potion" => haste_potion(store, x, y),
         "Fireball Scroll" => fireball_scroll(store, x, y),
         "Confusion Scroll" => confusion_scroll(store, x, y),
         "Magic Missile Scroll" => magic_missile_scroll(store, x, y),
@@ -100,6 +119,13 @@ fn spawn_entity(store: &mut AllStoragesViewMut, spawn: &(&usize, &String)) {
         "Shield" => shield(store, x, y),
         "Longsword" => longsword(store, x, y),
         "Tower Shield" => tower_shield(store, x, y),
+        "Lantern" => lantern(store, x, y),
+        "Bucket" => bucket(store, x, y),
+        "Oil Flask" => oil_flask(store, x, y),
+        "Poison Gas Potion" => poison_gas_potion(store, x, y),
+        "Door" => door(store, x, y, None),
+        "Log" => log(store, x, y),
+        "Pickaxe" => pickaxe(store, x, y),
         _ => unreachable!(),
     };
 }
@@ -126,11 +152,7 @@ pub fn player(store: &mut AllStoragesViewMut, pos: (i32, i32)) -> EntityId {
             mtype: LocomotionType::Ground,
             speed: 1,
         },
-        Vision {
-            visible_tiles: Vec::new(),
-            range: 20,
-            dirty: true,
-        },
+        Vision::new(20),
         Name {
             name: "Player".to_string(),
         },
@@ -163,11 +185,7 @@ pub fn villager(store: &mut AllStoragesViewMut, x: i32, y: i32) -> EntityId {
             order: RenderOrder::NPC,
             ..Default::default()
         },
-        Vision {
-            visible_tiles: Vec::new(),
-            range: 20,
-            dirty: true,
-        },
+        Vision::new(20),
         Locomotive {
             mtype: LocomotionType::Ground,
             speed: 1,
@@ -184,7 +202,13 @@ pub fn villager(store: &mut AllStoragesViewMut, x: i32, y: i32) -> EntityId {
         Actor {
             faction: Faction::Villager,
             atype: ActorType::Villager,
-            behaviors: vec![AIBehaviors::GatherWood, AIBehaviors::GatherFish, AIBehaviors::Wander],
+            behaviors: vec![
+                AIBehaviors::FightFires,
+                AIBehaviors::GatherWood,
+                AIBehaviors::GatherFish,
+                AIBehaviors::UseItems,
+                AIBehaviors::Wander,
+            ],
         },
     ))
 }
@@ -201,11 +225,7 @@ pub fn fish(store: &mut AllStoragesViewMut, x: i32, y: i32) -> EntityId {
             order: RenderOrder::NPC,
             ..Default::default()
         },
-        Vision {
-            visible_tiles: Vec::new(),
-            range: 2,
-            dirty: true,
-        },
+        Vision::new(2),
         Locomotive {
             mtype: LocomotionType::Water,
             speed: 1,
@@ -219,6 +239,7 @@ pub fn fish(store: &mut AllStoragesViewMut, x: i32, y: i32) -> EntityId {
             behaviors: Vec::new(),
         },
         Item { typ: ItemType::Fish },
+        Velocity { x: 0.0, y: 0.0 },
     ))
 }
 
@@ -242,15 +263,16 @@ pub fn monster(store: &mut AllStoragesViewMut, x: i32, y: i32, glyph: rltk::Font
             order: RenderOrder::NPC,
             ..Default::default()
         },
-        Vision {
-            visible_tiles: Vec::new(),
-            range: 8,
-            dirty: true,
+        Vision::new(8),
+        Hearing {
+            acuity: 2,
+            heard: None,
+            heard_turn: 0,
         },
         Actor {
             faction: Faction::Orcs,
             atype: ActorType::Orc,
-            behaviors: vec![AIBehaviors::AttackEnemies],
+            behaviors: vec![AIBehaviors::AttackEnemies, AIBehaviors::UseItems],
         },
         Locomotive {
             mtype: LocomotionType::Ground,
@@ -272,6 +294,46 @@ pub fn monster(store: &mut AllStoragesViewMut, x: i32, y: i32, glyph: rltk::Font
     ))
 }
 
+/// Spawns an orc at each point and groups them into a squad led by the first one
+pub fn orc_squad(store: &mut AllStoragesViewMut, points: &[Point]) -> Option<EntityId> {
+    if points.is_empty() {
+        return None;
+    }
+
+    let mut members = vec![];
+    for p in points.iter() {
+        members.push(orc(store, p.x, p.y));
+    }
+
+    let squad = store.add_entity((
+        Name {
+            name: "Orc squad".to_string(),
+        },
+        Squad {
+            leader: members[0],
+            members: members.clone(),
+            target: None,
+            state: SquadState::Gathering,
+            min_engage_size: (members.len() + 1) / 2,
+            strength: 0,
+        },
+    ));
+
+    for m in members.iter() {
+        store.add_component(*m, SquadMember { squad });
+    }
+
+    store.run(|mut vactor: ViewMut<Actor>| {
+        for m in members.iter() {
+            if let Ok(actor) = (&mut vactor).get(*m) {
+                actor.behaviors = vec![AIBehaviors::SquadTactics, AIBehaviors::UseItems];
+            }
+        }
+    });
+
+    Some(squad)
+}
+
 pub fn wolf(store: &mut AllStoragesViewMut, x: i32, y: i32) -> EntityId {
     store.add_entity((
         Position {
@@ -284,10 +346,11 @@ pub fn wolf(store: &mut AllStoragesViewMut, x: i32, y: i32) -> EntityId {
             order: RenderOrder::NPC,
             ..Default::default()
         },
-        Vision {
-            visible_tiles: Vec::new(),
-            range: 5,
-            dirty: true,
+        Vision::new(5),
+        Hearing {
+            acuity: 4,
+            heard: None,
+            heard_turn: 0,
         },
         Actor {
             faction: Faction::Nature,
@@ -312,6 +375,110 @@ pub fn wolf(store: &mut AllStoragesViewMut, x: i32, y: i32) -> EntityId {
     ))
 }
 
+pub fn wolf_den(store: &mut AllStoragesViewMut, x: i32, y: i32, pack: &[Point]) -> EntityId {
+    let den = spawner(store, x, y, Faction::Nature, SpawnerType::Wolf, 300, 6);
+
+    // the den's spawns are its pack, pups born here join it
+    let wolves: Vec<EntityId> = pack.iter().map(|p| pack_wolf(store, p.x, p.y, den)).collect();
+    store.run(|mut vname: ViewMut<Name>, mut vspawner: ViewMut<Spawner>| {
+        if let Ok(name) = (&mut vname).get(den) {
+            name.name = "Wolf den".to_string();
+        }
+        if let Ok(spawner) = (&mut vspawner).get(den) {
+            spawner.spawned = wolves;
+        }
+    });
+
+    den
+}
+
+// wild wolves hunt with their pack instead of attacking everything in sight
+pub fn pack_wolf(store: &mut AllStoragesViewMut, x: i32, y: i32, den: EntityId) -> EntityId {
+    let w = wolf(store, x, y);
+    // they start out fed, hunting only once hunger builds up
+    store.add_component(w, (Pack { den }, Hunger { hunger: 30, max: 300 }));
+
+    store.run(|mut vactor: ViewMut<Actor>| {
+        if let Ok(actor) = (&mut vactor).get(w) {
+            actor.behaviors = vec![AIBehaviors::Hunt];
+        }
+    });
+
+    w
+}
+
+pub fn deer(store: &mut AllStoragesViewMut, x: i32, y: i32) -> EntityId {
+    prey(
+        store,
+        x,
+        y,
+        rltk::to_cp437('d'),
+        ActorType::Deer,
+        "Deer".to_string(),
+        10,
+        60,
+    )
+}
+
+pub fn rabbit(store: &mut AllStoragesViewMut, x: i32, y: i32) -> EntityId {
+    prey(
+        store,
+        x,
+        y,
+        rltk::to_cp437('r'),
+        ActorType::Rabbit,
+        "Rabbit".to_string(),
+        3,
+        25,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn prey(
+    store: &mut AllStoragesViewMut,
+    x: i32,
+    y: i32,
+    glyph: rltk::FontCharType,
+    atype: ActorType,
+    name: String,
+    hp: i32,
+    nutrition: i32,
+) -> EntityId {
+    store.add_entity((
+        Position {
+            ps: vec![Point { x, y }],
+        },
+        Renderable {
+            glyph,
+            fg: Palette::COLOR_WOOD,
+            bg: Palette::MAIN_BG,
+            order: RenderOrder::NPC,
+            ..Default::default()
+        },
+        Vision::new(6),
+        Actor {
+            faction: Faction::Nature,
+            atype,
+            behaviors: vec![AIBehaviors::Graze],
+        },
+        Locomotive {
+            mtype: LocomotionType::Ground,
+            speed: 1,
+        },
+        Name { name },
+        BlocksTile {},
+        CombatStats {
+            max_hp: hp,
+            hp,
+            defense: 0,
+            power: 1,
+            regen_rate: 1,
+        },
+        Hunger { hunger: 0, max: 200 },
+        Edible { nutrition },
+    ))
+}
+
 #[allow(dead_code)]
 pub fn big_monster(store: &mut AllStoragesViewMut, x: i32, y: i32) -> EntityId {
     store.add_entity((
@@ -330,10 +497,11 @@ pub fn big_monster(store: &mut AllStoragesViewMut, x: i32, y: i32) -> EntityId {
             order: RenderOrder::NPC,
             ..Default::default()
         },
-        Vision {
-            visible_tiles: Vec::new(),
-            range: 8,
-            dirty: true,
+        Vision::new(8),
+        Hearing {
+            acuity: 1,
+            heard: None,
+            heard_turn: 0,
         },
         Actor {
             faction: Faction::Orcs,
@@ -381,6 +549,79 @@ pub fn health_potion(store: &mut AllStoragesViewMut, x: i32, y: i32) -> EntityId
     ))
 }
 
+pub fn haste_potion(store: &mut AllStoragesViewMut, x: i32, y: i32) -> EntityId {
+    store.add_entity((
+        Position {
+            ps: vec![Point { x, y }],
+        },
+        Renderable {
+            glyph: rltk::to_cp437('p'),
+            fg: Palette::COLOR_AMBER,
+            bg: Palette::MAIN_BG,
+            order: RenderOrder::Items,
+            ..Default::default()
+        },
+        Name {
+            name: "Haste potion".to_string(),
+        },
+        Item { typ: ItemType::Potion },
+        Haste { turns: 8 },
+        Consumable {},
+    ))
+}
+
+pub fn oil_flask(store: &mut AllStoragesViewMut, x: i32, y: i32) -> EntityId {
+    store.add_entity((
+        Position {
+            ps: vec![Point { x, y }],
+        },
+        Renderable {
+            glyph: rltk::to_cp437('!'),
+            fg: Palette::COLOR_AMBER,
+            bg: Palette::MAIN_BG,
+            order: RenderOrder::Items,
+            ..Default::default()
+        },
+        Name {
+            name: "Flask of oil".to_string(),
+        },
+        Item { typ: ItemType::Potion },
+        Consumable {},
+        Ranged { range: 6 },
+        AreaOfEffect { radius: 1 },
+        SpillsFluid {
+            fluid: Fluid::Oil,
+            amount: 1.0,
+        },
+    ))
+}
+
+pub fn poison_gas_potion(store: &mut AllStoragesViewMut, x: i32, y: i32) -> EntityId {
+    store.add_entity((
+        Position {
+            ps: vec![Point { x, y }],
+        },
+        Renderable {
+            glyph: rltk::to_cp437('!'),
+            fg: Palette::COLOR_GREEN,
+            bg: Palette::MAIN_BG,
+            order: RenderOrder::Items,
+            ..Default::default()
+        },
+        Name {
+            name: "Poison gas potion".to_string(),
+        },
+        Item { typ: ItemType::Potion },
+        Consumable {},
+        Ranged { range: 6 },
+        AreaOfEffect { radius: 1 },
+        SpillsFluid {
+            fluid: Fluid::Poison,
+            amount: 3.0,
+        },
+    ))
+}
+
 pub fn magic_missile_scroll(store: &mut AllStoragesViewMut, x: i32, y: i32) -> EntityId {
     store.add_entity((
         Position {
@@ -496,6 +737,30 @@ pub fn longsword(store: &mut AllStoragesViewMut, x: i32, y: i32) -> EntityId {
     ))
 }
 
+pub fn pickaxe(store: &mut AllStoragesViewMut, x: i32, y: i32) -> EntityId {
+    store.add_entity((
+        Position {
+            ps: vec![Point { x, y }],
+        },
+        Renderable {
+            glyph: rltk::to_cp437('τ'),
+            fg: Palette::COLOR_3,
+            bg: Palette::MAIN_BG,
+            order: RenderOrder::Items,
+            ..Default::default()
+        },
+        Name {
+            name: "Pickaxe".to_string(),
+        },
+        Item { typ: ItemType::Tool },
+        Equippable {
+            slot: EquipmentSlot::RightHand,
+        },
+        MeleePowerBonus { power: 2 },
+        DigsTerrain { power: 1 },
+    ))
+}
+
 pub fn shield(store: &mut AllStoragesViewMut, x: i32, y: i32) -> EntityId {
     store.add_entity((
         Position {
@@ -542,6 +807,52 @@ pub fn tower_shield(store: &mut AllStoragesViewMut, x: i32, y: i32) -> EntityId
     ))
 }
 
+pub fn bucket(store: &mut AllStoragesViewMut, x: i32, y: i32) -> EntityId {
+    store.add_entity((
+        Position {
+            ps: vec![Point { x, y }],
+        },
+        Renderable {
+            glyph: rltk::to_cp437('u'),
+            fg: Palette::COLOR_WOOD,
+            bg: Palette::MAIN_BG,
+            order: RenderOrder::Items,
+            ..Default::default()
+        },
+        Name {
+            name: "Bucket".to_string(),
+        },
+        Item { typ: ItemType::Bucket },
+        Bucket { full: false },
+    ))
+}
+
+pub fn lantern(store: &mut AllStoragesViewMut, x: i32, y: i32) -> EntityId {
+    store.add_entity((
+        Position {
+            ps: vec![Point { x, y }],
+        },
+        Renderable {
+            glyph: rltk::to_cp437('¥'),
+            fg: Palette::COLOR_AMBER,
+            bg: Palette::MAIN_BG,
+            order: RenderOrder::Items,
+            ..Default::default()
+        },
+        Name {
+            name: "Lantern".to_string(),
+        },
+        Item { typ: ItemType::Light },
+        Equippable {
+            slot: EquipmentSlot::LeftHand,
+        },
+        LightSource {
+            color: Palette::COLOR_AMBER,
+            range: 8,
+        },
+    ))
+}
+
 pub fn log(store: &mut AllStoragesViewMut, x: i32, y: i32) -> EntityId {
     store.add_entity((
         Position {
@@ -558,12 +869,34 @@ pub fn log(store: &mut AllStoragesViewMut, x: i32, y: i32) -> EntityId {
             name: "Log".to_string(),
         },
         Item { typ: ItemType::Log },
-        Flammable {},
+        Flammable { fuel: 15 },
     ))
 }
 
 // structures
 
+pub fn torch(store: &mut AllStoragesViewMut, x: i32, y: i32) -> EntityId {
+    store.add_entity((
+        Position {
+            ps: vec![Point { x, y }],
+        },
+        Renderable {
+            glyph: rltk::to_cp437('i'),
+            fg: Palette::COLOR_FIRE,
+            bg: Palette::MAIN_BG,
+            order: RenderOrder::Items,
+            ..Default::default()
+        },
+        Name {
+            name: "Torch".to_string(),
+        },
+        LightSource {
+            color: Palette::COLOR_FIRE,
+            range: 6,
+        },
+    ))
+}
+
 pub fn spawner(
     store: &mut AllStoragesViewMut,
     x: i32,
@@ -571,6 +904,7 @@ pub fn spawner(
     faction: Faction,
     typ: SpawnerType,
     rate: i32,
+    cap: usize,
 ) -> EntityId {
     store.add_entity((
         Position {
@@ -586,7 +920,12 @@ pub fn spawner(
         Name {
             name: "Spawner".to_string(),
         },
-        Spawner { typ, rate },
+        Spawner {
+            typ,
+            rate,
+            cap,
+            spawned: vec![],
+        },
         Actor {
             atype: ActorType::Spawner,
             faction,
@@ -595,6 +934,35 @@ pub fn spawner(
     ))
 }
 
+pub fn door(store: &mut AllStoragesViewMut, x: i32, y: i32, lock: Option<DoorLock>) -> EntityId {
+    store.add_entity((
+        Position {
+            ps: vec![Point { x, y }],
+        },
+        Renderable {
+            glyph: rltk::to_cp437('+'),
+            fg: Palette::COLOR_WOOD,
+            bg: Palette::MAIN_BG,
+            order: RenderOrder::Items,
+            ..Default::default()
+        },
+        Name {
+            name: "Door".to_string(),
+        },
+        Door { open: false, lock },
+        BlocksTile {},
+        BlocksVisibility {},
+        Flammable { fuel: 12 },
+        CombatStats {
+            max_hp: 10,
+            hp: 10,
+            defense: 0,
+            power: 0,
+            regen_rate: 0,
+        },
+    ))
+}
+
 pub fn tree(store: &mut AllStoragesViewMut, x: i32, y: i32) -> EntityId {
     store.add_entity((
         Position {
@@ -610,19 +978,36 @@ pub fn tree(store: &mut AllStoragesViewMut, x: i32, y: i32) -> EntityId {
         Name {
             name: "Tree".to_string(),
         },
-        Flammable {},
+        Flammable { fuel: 20 },
         Tree {},
     ))
 }
 
-pub fn plank_house(store: &mut AllStoragesViewMut, x: i32, y: i32, width: i32, height: i32) -> EntityId {
+// a building only takes up its walls, leaving the inside open and a doorway in the middle of the front wall
+fn building_walls(x: i32, y: i32, width: i32, height: i32) -> (Vec<Point>, Point) {
+    let doorway = Point {
+        x: x + width / 2,
+        y: y + height - 1,
+    };
+
     let mut ps = vec![];
     for xi in 0..width {
         for yi in 0..height {
-            ps.push(Point { x: x + xi, y: y + yi });
+            let p = Point { x: x + xi, y: y + yi };
+            let wall = xi == 0 || yi == 0 || xi == width - 1 || yi == height - 1;
+            if wall && p != doorway {
+                ps.push(p);
+            }
         }
     }
 
+    (ps, doorway)
+}
+
+pub fn plank_house(store: &mut AllStoragesViewMut, x: i32, y: i32, width: i32, height: i32) -> EntityId {
+    let (ps, doorway) = building_walls(x, y, width, height);
+    door(store, doorway.x, doorway.y, None);
+
     // TODO pick colors for buildings, maybe glyph?
 
     store.add_entity((
@@ -637,7 +1022,7 @@ pub fn plank_house(store: &mut AllStoragesViewMut, x: i32, y: i32, width: i32, h
         Name {
             name: "Plank House".to_string(),
         },
-        Flammable {},
+        Flammable { fuel: 40 },
         PlankHouse {
             housing_cap: 5,
             villagers: vec![],
@@ -647,12 +1032,8 @@ pub fn plank_house(store: &mut AllStoragesViewMut, x: i32, y: i32, width: i32, h
 }
 
 pub fn chief_house(store: &mut AllStoragesViewMut, x: i32, y: i32, width: i32, height: i32) -> EntityId {
-    let mut ps = vec![];
-    for xi in 0..width {
-        for yi in 0..height {
-            ps.push(Point { x: x + xi, y: y + yi });
-        }
-    }
+    let (ps, doorway) = building_walls(x, y, width, height);
+    door(store, doorway.x, doorway.y, None);
 
     // TODO pick colors for buildings, maybe glyph?
 
@@ -668,19 +1049,15 @@ pub fn chief_house(store: &mut AllStoragesViewMut, x: i32, y: i32, width: i32, h
         Name {
             name: "chief_house".to_string(),
         },
-        Flammable {},
+        Flammable { fuel: 60 },
         ChiefHouse {},
         BlocksTile {},
     ))
 }
 
 pub fn fish_cleaner(store: &mut AllStoragesViewMut, x: i32, y: i32, width: i32, height: i32) -> EntityId {
-    let mut ps = vec![];
-    for xi in 0..width {
-        for yi in 0..height {
-            ps.push(Point { x: x + xi, y: y + yi });
-        }
-    }
+    let (ps, doorway) = building_walls(x, y, width, height);
+    door(store, doorway.x, doorway.y, None);
 
     // TODO pick colors for buildings, maybe glyph?
 
@@ -696,7 +1073,7 @@ pub fn fish_cleaner(store: &mut AllStoragesViewMut, x: i32, y: i32, width: i32,
         Name {
             name: "Fish Cleaner".to_string(),
         },
-        Flammable {},
+        Flammable { fuel: 30 },
         FishCleaner {},
         BlocksTile {},
         Inventory {
@@ -704,18 +1081,14 @@ pub fn fish_cleaner(store: &mut AllStoragesViewMut, x: i32, y: i32, width: i32,
             items: Vec::new(),
         },
         DijkstraMapToMe {
-            map: DijkstraMap::new_empty(0, 0, 0.),
+            map: Arc::new(DijkstraMap::new_empty(0, 0, 0.)),
         },
     ))
 }
 
 pub fn lumber_mill(store: &mut AllStoragesViewMut, x: i32, y: i32, width: i32, height: i32) -> EntityId {
-    let mut ps = vec![];
-    for xi in 0..width {
-        for yi in 0..height {
-            ps.push(Point { x: x + xi, y: y + yi });
-        }
-    }
+    let (ps, doorway) = building_walls(x, y, width, height);
+    door(store, doorway.x, doorway.y, None);
 
     // TODO pick colors for buildings, maybe glyph?
 
@@ -731,7 +1104,7 @@ pub fn lumber_mill(store: &mut AllStoragesViewMut, x: i32, y: i32, width: i32, h
         Name {
             name: "Lumber Mill".to_string(),
         },
-        Flammable {},
+        Flammable { fuel: 50 },
         LumberMill {},
         BlocksTile {},
         Inventory {
@@ -739,7 +1112,7 @@ pub fn lumber_mill(store: &mut AllStoragesViewMut, x: i32, y: i32, width: i32, h
             items: Vec::new(),
         },
         DijkstraMapToMe {
-            map: DijkstraMap::new_empty(0, 0, 0.),
+            map: Arc::new(DijkstraMap::new_empty(0, 0, 0.)),
         },
     ))
 }
@@ -0,0 +1,155 @@
+use std::ops::{Index, IndexMut};
+
+use rltk::RGBA;
+use serde::{Deserialize, Serialize};
+
+use crate::components::StatusKind;
+
+/// Gases and liquids that pool on tiles, how each behaves comes from `fluid_info`
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug, Serialize, Deserialize)]
+pub enum Fluid {
+    Smoke,
+    Poison,
+    Water,
+    Oil,
+}
+
+pub const ALL_FLUIDS: [Fluid; 4] = [Fluid::Smoke, Fluid::Poison, Fluid::Water, Fluid::Oil];
+
+/// How much of each fluid is on a tile, any number of them can share one
+#[derive(Default, Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Fluids([f32; ALL_FLUIDS.len()]);
+
+impl Index<Fluid> for Fluids {
+    type Output = f32;
+
+    fn index(&self, fluid: Fluid) -> &f32 {
+        &self.0[fluid as usize]
+    }
+}
+
+impl IndexMut<Fluid> for Fluids {
+    fn index_mut(&mut self, fluid: Fluid) -> &mut f32 {
+        &mut self.0[fluid as usize]
+    }
+}
+
+impl Fluids {
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|amount| *amount <= 0.)
+    }
+
+    /// Whether anything on the tile is thick enough to block sight
+    pub fn blocks_sight(&self) -> bool {
+        !self.is_empty()
+            && ALL_FLUIDS
+                .iter()
+                .any(|f| fluid_info(*f).opaque_at.map_or(false, |at| self[*f] >= at))
+    }
+
+    /// Whether the tile is too wet to burn
+    pub fn douses(&self) -> bool {
+        !self.is_empty()
+            && ALL_FLUIDS
+                .iter()
+                .any(|f| fluid_info(*f).extinguishes_at.map_or(false, |at| self[*f] >= at))
+    }
+
+    /// Turns of fire the fluids on the tile are good for
+    pub fn fuel(&self) -> i32 {
+        ALL_FLUIDS
+            .iter()
+            .map(|f| (self[*f] * fluid_info(*f).fuel as f32) as i32)
+            .sum()
+    }
+}
+
+/// How a kind of fluid looks and behaves, every `Fluid` has one in resources/fluids.json
+pub struct FluidInfo {
+    pub name: String,
+    pub color: RGBA,
+    pub gas: bool, // gases drift with the wind and through anything but walls, liquids only run over open ground
+    pub floats: bool, // liquids that don't float are lost into open water
+    pub spread: f32, // share of a tile's fluid that moves to its neighbors each turn
+    pub decay: f32, // share left after a turn, the rest evaporates or soaks away
+    pub thick: f32, // amount that fully colors a tile
+    pub opaque_at: Option<f32>,
+    pub harmful_at: Option<f32>,
+    pub damage: i32, // to anyone standing in it once it's harmful
+    // and this many turns of a status, put on again every turn they stay in it
+    pub status: Option<(StatusKind, i32)>,
+    pub extinguishes_at: Option<f32>,
+    pub fuel: i32, // turns of fire per unit of fluid
+}
+
+fn one() -> f32 {
+    1.
+}
+
+// a fluid as written in the data file
+#[derive(Deserialize)]
+struct FluidDef {
+    fluid: Fluid,
+    name: String,
+    color: [f32; 3],
+    #[serde(default)]
+    gas: bool,
+    #[serde(default)]
+    floats: bool,
+    spread: f32,
+    #[serde(default = "one")]
+    decay: f32,
+    #[serde(default = "one")]
+    thick: f32,
+    #[serde(default)]
+    opaque_at: Option<f32>,
+    #[serde(default)]
+    harmful_at: Option<f32>,
+    #[serde(default)]
+    damage: i32,
+    #[serde(default)]
+    status: Option<(StatusKind, i32)>,
+    #[serde(default)]
+    extinguishes_at: Option<f32>,
+    #[serde(default)]
+    fuel: i32,
+}
+
+lazy_static! {
+    // in the same order as the enum so lookups are just an index, they happen for every tile in a field of view
+    static ref FLUIDS: Vec<FluidInfo> = {
+        let mut defs: Vec<FluidDef> =
+            serde_json::from_str(include_str!("../../resources/fluids.json")).expect("Couldn't parse fluids.json");
+        defs.sort_by_key(|def| def.fluid as usize);
+
+        for (i, fluid) in ALL_FLUIDS.iter().enumerate() {
+            assert!(
+                defs.get(i).map_or(false, |def| def.fluid == *fluid),
+                "No single entry for {:?} in fluids.json",
+                fluid
+            );
+        }
+
+        defs.into_iter()
+            .map(|def| FluidInfo {
+                name: def.name,
+                color: RGBA::from_f32(def.color[0], def.color[1], def.color[2], 1.),
+                gas: def.gas,
+                floats: def.floats,
+                spread: def.spread,
+                decay: def.decay,
+                thick: def.thick,
+                opaque_at: def.opaque_at,
+                harmful_at: def.harmful_at,
+                damage: def.damage,
+                status: def.status,
+                extinguishes_at: def.extinguishes_at,
+                fuel: def.fuel,
+            })
+            .collect()
+    };
+}
+
+pub fn fluid_info(fluid: Fluid) -> &'static FluidInfo {
+    &FLUIDS[fluid as usize]
+}
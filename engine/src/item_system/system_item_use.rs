@@ -1,18 +1,30 @@
 use crate::components::{
-    AreaOfEffect, CombatStats, Confusion, Consumable, DealsDamage, Equippable, Equipped, Fire, InBackpack, Inventory,
-    Name, ProvidesHealing, WantsToUseItem,
+    AreaOfEffect, Bucket, CombatStats, Confusion, Consumable, DealsDamage, Equippable, Equipped, Fire, Haste,
+    InBackpack, Inventory, Name, ProvidesHealing, SpillsFluid, StatusKind, WantsToUseItem,
 };
 use crate::effects::add_effect;
+use crate::effects::noise::EXPLOSION_NOISE;
 use crate::effects::{EffectType, Targets};
+use crate::fluids::Fluid;
 use crate::map::Map;
 use crate::palette::Palette;
 use crate::uniques::PlayerID;
+use crate::utils::get_neighbors;
 use crate::{components::Position, systems::system_particle::ParticleBuilder, uniques::GameLog};
 use shipyard::{
     AddComponent, AllStoragesViewMut, EntityId, Get, IntoIter, IntoWithId, Remove, UniqueView, UniqueViewMut, View,
     ViewMut,
 };
 
+// water left on each tile a bucket is emptied over, enough to keep it from catching again for a while
+const BUCKET_WATER: f32 = 0.5;
+
+// damage an explosion deals for each point of wear it does to the walls around it
+const BLAST_DAMAGE_PER_WEAR: i32 = 10;
+
+// turns anyone caught in an explosion is left reeling
+const BLAST_STUN_TURNS: i32 = 2;
+
 pub fn run_item_use_system(store: AllStoragesViewMut) {
     let mut log = store.borrow::<UniqueViewMut<GameLog>>().unwrap(); //res.get_mut::<GameLog>().unwrap();
     let player_id = store.borrow::<UniqueView<PlayerID>>().ok().unwrap(); //res.get::<EntityId>().unwrap();
@@ -27,12 +39,15 @@ pub fn run_item_use_system(store: AllStoragesViewMut) {
     let vaoe = store.borrow::<View<AreaOfEffect>>().unwrap();
     let vstats = store.borrow::<ViewMut<CombatStats>>().unwrap();
     let vfire = store.borrow::<View<Fire>>().unwrap();
+    let vspills = store.borrow::<View<SpillsFluid>>().unwrap();
     let vprovideshealing = store.borrow::<View<ProvidesHealing>>().unwrap();
     let vname = store.borrow::<View<Name>>().unwrap();
     let vpos = store.borrow::<View<Position>>().unwrap();
     let vdealsdamage = store.borrow::<View<DealsDamage>>().unwrap();
     let vconfusion = store.borrow::<View<Confusion>>().unwrap();
+    let vhaste = store.borrow::<View<Haste>>().unwrap();
     let vconsumable = store.borrow::<View<Consumable>>().unwrap();
+    let mut vbucket = store.borrow::<ViewMut<Bucket>>().unwrap();
     let vequippable = store.borrow::<View<Equippable>>().unwrap();
     let mut vequipped = store.borrow::<ViewMut<Equipped>>().unwrap();
     let mut vinv = store.borrow::<ViewMut<Inventory>>().unwrap();
@@ -45,7 +60,7 @@ pub fn run_item_use_system(store: AllStoragesViewMut) {
         let mut targets: Vec<EntityId> = Vec::new();
         let mut target_tiles: Vec<usize> = Vec::new();
         match use_item.target {
-            None => targets.push(player_id.0),
+            None => targets.push(id),
             Some(t) => {
                 match vaoe.get(use_item.item) {
                     Err(_e) => {
@@ -63,6 +78,13 @@ pub fn run_item_use_system(store: AllStoragesViewMut) {
                         // AOE
                         let mut affected_tiles = rltk::field_of_view(t, aoe.radius, &*map);
                         affected_tiles.retain(|p| p.x > 0 && p.x < map.width - 1 && p.y > 0 && p.y < map.height - 1);
+                        add_effect(
+                            Some(id),
+                            EffectType::Noise {
+                                tile_idx: map.xy_idx(t.x, t.y),
+                                volume: EXPLOSION_NOISE,
+                            },
+                        );
                         for pt in affected_tiles.iter() {
                             let idx = map.xy_idx(pt.x, pt.y);
                             target_tiles.push(idx);
@@ -89,6 +111,55 @@ pub fn run_item_use_system(store: AllStoragesViewMut) {
             }
         }
 
+        // Flasks shatter over the target, or at our feet if there isn't one
+        if let Ok(spills) = vspills.get(use_item.item) {
+            let tiles = if target_tiles.is_empty() {
+                use_item
+                    .target
+                    .or_else(|| vpos.get(id).ok().map(|pos| pos.ps[0]))
+                    .map(|spot| vec![map.point_idx(spot)])
+                    .unwrap_or_default()
+            } else {
+                target_tiles.clone()
+            };
+            add_effect(
+                Some(id),
+                EffectType::Spill {
+                    fluid: spills.fluid,
+                    amount: spills.amount,
+                    target: Targets::Tiles { tiles },
+                },
+            );
+            used_item = true;
+        }
+
+        // Explosions blast chunks out of the walls they reach and stun whoever they catch
+        if let (Ok(_), Ok(dd)) = (vaoe.get(use_item.item), vdealsdamage.get(use_item.item)) {
+            for target in targets.iter() {
+                add_effect(
+                    Some(id),
+                    EffectType::Status {
+                        kind: StatusKind::Stunned,
+                        turns: BLAST_STUN_TURNS,
+                        target: Targets::Single { target: *target },
+                    },
+                );
+            }
+
+            let amount = dd.damage / BLAST_DAMAGE_PER_WEAR;
+            if amount > 0 && !target_tiles.is_empty() {
+                add_effect(
+                    Some(id),
+                    EffectType::Demolish {
+                        amount,
+                        target: Targets::Tiles {
+                            tiles: target_tiles.clone(),
+                        },
+                    },
+                );
+            }
+        }
+
         // Apply fire if it applies fire
         let item_fires = vfire.get(use_item.item);
         match item_fires {
@@ -105,6 +176,49 @@ pub fn run_item_use_system(store: AllStoragesViewMut) {
             }
         }
 
+        // Buckets douse the target and everything around it when full, and fill up from any water next to it when empty
+        if let Ok(bucket) = (&mut vbucket).get(use_item.item) {
+            let spot = use_item.target.or_else(|| vpos.get(id).ok().map(|pos| pos.ps[0]));
+            if let Some(spot) = spot {
+                let mut around: Vec<usize> = vec![map.point_idx(spot)];
+                around.extend(
+                    get_neighbors(spot)
+                        .iter()
+                        .filter(|p| map.in_bounds(p.x, p.y))
+                        .map(|p| map.point_idx(*p)),
+                );
+
+                if bucket.full {
+                    bucket.full = false;
+                    add_effect(
+                        Some(id),
+                        EffectType::Extinguish {
+                            target: Targets::Tiles { tiles: around.clone() },
+                        },
+                    );
+                    add_effect(
+                        Some(id),
+                        EffectType::Spill {
+                            fluid: Fluid::Water,
+                            amount: BUCKET_WATER,
+                            target: Targets::Tiles { tiles: around },
+                        },
+                    );
+                    if id == player_id.0 {
+                        log.messages.push("You empty the bucket".to_string());
+                    }
+                } else if around.iter().any(|idx| map.is_swimmable(*idx)) {
+                    bucket.full = true;
+                    if id == player_id.0 {
+                        log.messages.push("You fill the bucket".to_string());
+                    }
+                } else if id == player_id.0 {
+                    log.messages
+                        .push("There's no water here to fill the bucket".to_string());
+                }
+            }
+        }
+
         // Apply heal if it provides healing
         let item_heals = vprovideshealing.get(use_item.item);
         match item_heals {
@@ -203,7 +317,8 @@ pub fn run_item_use_system(store: AllStoragesViewMut) {
                 for target in targets.iter() {
                     add_effect(
                         Some(id),
-                        EffectType::Confusion {
+                        EffectType::Status {
+                            kind: StatusKind::Confused,
                             turns: confusion.turns,
                             target: Targets::Single { target: *target },
                         },
@@ -236,6 +351,23 @@ pub fn run_item_use_system(store: AllStoragesViewMut) {
             }
         }
 
+        // Apply haste
+        if let Ok(haste) = vhaste.get(use_item.item) {
+            for target in targets.iter() {
+                add_effect(
+                    Some(id),
+                    EffectType::Status {
+                        kind: StatusKind::Hasted,
+                        turns: haste.turns,
+                        target: Targets::Single { target: *target },
+                    },
+                );
+                if *target == player_id.0 {
+                    log.messages.push("You feel yourself speed up".to_string());
+                }
+            }
+        }
+
         // Remove item if it's consumable
         let consumable = vconsumable.get(use_item.item);
         match consumable {
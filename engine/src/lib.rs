@@ -3,19 +3,26 @@ extern crate lazy_static;
 
 use components::{Equipped, InBackpack, IsCamera, Player, Position, Vision};
 use map::{Map, TileType};
+use path_cache::PathCache;
 use rltk::Point;
+use spatial::SpatialIndex;
 use uniques::GameLog;
 
 mod item_system;
 
 pub mod ai;
 
+pub mod chunks;
 pub mod components;
 pub mod entity_factory;
+pub mod fluids;
 pub mod map;
 pub mod palette;
+pub mod path_cache;
 pub mod player;
 pub mod rect;
+pub mod spatial;
+pub mod tiles;
 pub mod uniques;
 pub mod utils;
 pub mod weighted_table;
@@ -27,10 +34,11 @@ use shipyard::{
     AllStoragesViewMut, EntitiesView, EntityId, Get, Unique, UniqueView, UniqueViewMut, View, ViewMut, World,
 };
 use systems::{
-    system_ai, system_ai_fish, system_dissasemble, system_fire, system_map_indexing, system_melee_combat,
-    system_particle, system_pathfinding, system_visibility,
+    system_ai, system_ai_fish, system_dissasemble, system_doors, system_fire, system_fluids, system_hunger,
+    system_lighting, system_map_indexing, system_melee_combat, system_particle, system_pathfinding, system_squads,
+    system_status, system_visibility,
 };
-use uniques::{FrameTime, PPoint, PlayerID, Turn, RNG};
+use uniques::{FrameTime, PPoint, PlayerID, Turn, Wind, RNG};
 
 pub mod effects;
 
@@ -76,16 +84,25 @@ pub struct Engine {
 
 impl Engine {
     pub fn run_systems(world: &mut World, _player_turn: bool, ai_turn: bool) {
-        // if player_turn {
-        world.run(system_fire::run_fire_system);
-        // }
+        // fire and fluids move once a round, in step with the statuses they put on whoever's caught in them
+        if ai_turn {
+            world.run(system_fire::run_fire_system);
+            world.run(system_fluids::run_fluid_system);
+        }
+        world.run(system_lighting::run_lighting_system);
         world.run(system_visibility::run_visibility_system);
 
+        if ai_turn {
+            world.run(system_status::run_status_system);
+        }
+
         world.run(effects::run_effects_queue);
 
         if ai_turn && !DISABLE_AI {
             world.run(system_pathfinding::run_pathfinding_system);
+            world.run(system_hunger::run_hunger_system);
             world.run(system_ai_fish::run_fish_ai);
+            world.run(system_squads::run_squad_system);
             world.run(system_ai::run_ai_system);
         }
 
@@ -93,6 +110,13 @@ impl Engine {
 
         world.run(system_map_indexing::run_map_indexing_system);
 
+        // anyone hasted or off quick ground gets their banked turn once the first moves have landed
+        if ai_turn && !DISABLE_AI {
+            world.run(system_ai::run_banked_ai_system);
+            world.run(effects::run_effects_queue);
+            world.run(system_map_indexing::run_map_indexing_system);
+        }
+
         world.run(system_melee_combat::run_melee_combat_system);
         world.run(item_system::run_inventory_system);
         world.run(system_dissasemble::run_dissasemble_system);
@@ -103,6 +127,7 @@ impl Engine {
 
         world.run(effects::run_effects_queue);
         world.run(system_map_indexing::run_map_indexing_system);
+        world.run(system_doors::run_door_system);
     }
 
     pub fn entities_to_delete_on_level_change(world: &mut World) -> Vec<EntityId> {
@@ -138,17 +163,14 @@ impl Engine {
         ids_to_delete
     }
 
-    pub fn generate_map(world: &mut World, new_depth: i32) {
+    /// Builds and populates a new map, returns the steps it took to build it for playback
+    pub fn generate_map(world: &mut World, new_depth: i32) -> Vec<Map> {
         // delete all entities
         let ids_to_delete = Self::entities_to_delete_on_level_change(world);
         for id in ids_to_delete {
             world.delete_entity(id);
         }
 
-        // self.mapgen_data.index = 0;
-        // self.mapgen_data.timer = 0.0;
-        // self.mapgen_data.history.clear();
-
         // get game mode
         let settings = *world.borrow::<UniqueView<GameSettings>>().unwrap();
 
@@ -162,12 +184,13 @@ impl Engine {
 
         map_builder.build_map();
 
-        // self.mapgen_data.history = map_builder.get_map_history();
+        let mut history = map_builder.get_map_history();
 
         let start_pos;
         {
             let mut map = world.borrow::<UniqueViewMut<Map>>().unwrap();
             *map = map_builder.get_map();
+            history.push(map.clone());
             start_pos = map_builder.get_starting_position().ps.first().unwrap().clone();
         }
 
@@ -190,23 +213,27 @@ impl Engine {
                 }
             },
         );
+
+        history
     }
 
-    pub fn next_level(world: &mut World) {
+    pub fn next_level(world: &mut World) -> Vec<Map> {
         // Generate new map
         let current_depth;
         {
             let map = world.borrow::<UniqueViewMut<Map>>().unwrap();
             current_depth = map.depth;
         }
-        Self::generate_map(world, current_depth + 1);
+        let history = Self::generate_map(world, current_depth + 1);
 
         // Notify player
         let mut log = world.borrow::<UniqueViewMut<GameLog>>().unwrap();
         log.messages.push("You descend in the staircase".to_string());
+
+        history
     }
 
-    pub fn reset_engine(&mut self, settings: GameSettings) {
+    pub fn reset_engine(&mut self, settings: GameSettings) -> Vec<Map> {
         // Delete everything
         // world.clear();
         self.world = World::new();
@@ -226,6 +253,9 @@ impl Engine {
         self.world.add_unique(GameLog { messages: vec![] });
         self.world.add_unique(system_particle::ParticleBuilder::new());
         self.world.add_unique(FrameTime(0.));
+        self.world.add_unique(PathCache::default());
+        self.world.add_unique(SpatialIndex::default());
+        self.world.add_unique(Wind { dir: 6, strength: 0.5 });
 
         match settings.mode {
             GameMode::VillageSim => {
@@ -235,6 +265,6 @@ impl Engine {
         }
 
         // Generate new map
-        Self::generate_map(&mut self.world, 1);
+        Self::generate_map(&mut self.world, 1)
     }
 }
@@ -1,14 +1,24 @@
+use std::collections::{HashSet, VecDeque};
+
 use rltk::{self};
-use rltk::{Algorithm2D, BaseMap, Point};
+use rltk::{Algorithm2D, BaseMap, Point, RGBA};
 use serde;
 use serde::{Deserialize, Serialize};
 use shipyard::{EntityId, Get, Unique, View};
 
 use crate::ai::decisions::Target;
-use crate::components::Position;
+use crate::chunks::Chunked;
+use crate::components::{LocomotionType, Position};
+use crate::fluids::Fluids;
+use crate::tiles::tile_info;
+use crate::utils::{dir_to_point, get_neighbors};
 use crate::{OFFSET_X, OFFSET_Y, SCALE};
 
-#[derive(PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
+// extra cost of pathing across a burning tile
+const BURNING_COST: f32 = 20.0;
+
+/// Kinds of tile, what each one looks like and how it behaves comes from `tiles::tile_info`
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug, Serialize, Deserialize)]
 pub enum TileType {
     Wall,
     Floor,
@@ -17,50 +27,119 @@ pub enum TileType {
     Grass,
     Wheat,
     Dirt,
+    Sand,
     Water,
+    ShallowWater,
+    Bridge,
     WoodWall,
     WoodDoor,
     WoodFloor,
+    Rubble,
+}
+
+// unloaded chunks read as solid rock
+impl Default for TileType {
+    fn default() -> Self {
+        TileType::Wall
+    }
 }
 
+// how many chunks around the player, villagers and spawners stay awake, everything further out sleeps
+pub const WAKE_RADIUS: i32 = 2;
+
 #[derive(Default, Serialize, Deserialize, Clone, Unique)]
 pub struct Map {
-    pub tiles: Vec<TileType>,
+    pub tiles: Chunked<TileType>,
     pub width: i32,
     pub height: i32,
-    pub blocked: Vec<bool>,
-    pub fire_turns: Vec<i32>,
+    pub blocked: Chunked<bool>,
+    pub view_blocked: Chunked<bool>, // entities like closed doors that can't be seen through
+    pub fire_turns: Chunked<i32>,    // turns of fuel left on burning tiles
+    pub tile_damage: Chunked<i32>,   // digging and blasting taken by tiles that haven't broken yet
+    pub fluids: Chunked<Fluids>,     // gases and liquids pooled on each tile
     pub depth: i32,
 
+    // numpad direction the water on each tile flows in, 5 is still water
+    pub currents: Chunked<usize>,
+    pub river_flow: usize, // numpad direction the map's rivers run off it in, 5 when there are none
+
+    // which named region, if any, each tile belongs to
+    pub regions: Chunked<Option<usize>>,
+    pub region_names: Vec<String>,
+
     #[serde(skip_serializing)]
     #[serde(skip_deserializing)]
-    pub tile_content: Vec<Vec<EntityId>>,
+    pub tile_content: Chunked<Vec<EntityId>>,
 
     // vec of numbers for debug. If it's not set, shouldn't affect anything
-    pub dijkstra_map: Vec<f32>,
+    pub dijkstra_map: Chunked<f32>,
+
+    // color and brightness of the light on each tile, recomputed every turn by the lighting system
+    #[serde(skip_serializing)]
+    #[serde(skip_deserializing)]
+    pub light: Chunked<RGBA>,
+
+    // per chunk, sleeping chunks aren't indexed and nothing on them is simulated
+    pub awake: Vec<bool>,
+
+    // tiles blocked by entities that stay put, like buildings and locked doors
+    #[serde(skip_serializing)]
+    #[serde(skip_deserializing)]
+    fixed_blockers: HashSet<usize>,
+
+    // chunks whose tiles or fixed blockers changed since `take_dirty_chunks` was last called
+    #[serde(skip_serializing)]
+    #[serde(skip_deserializing)]
+    dirty_chunks: HashSet<usize>,
     // TODO Maybe this doesn't belong here, a system would be better practice (but uglier)
     // pub influence_maps: Vec<Vec<f32>>,
 }
 
 impl Map {
     pub fn new(new_depth: i32, tile_type: TileType, size: (i32, i32)) -> Map {
-        let count = (size.0 * size.1) as usize;
+        let tiles = Chunked::new(size, tile_type);
+        let chunks = tiles.chunk_count();
         Map {
-            tiles: vec![tile_type; count],
+            tiles,
             width: size.0,
             height: size.1,
-            blocked: vec![false; count],
-            fire_turns: vec![0; count],
-            tile_content: vec![Vec::new(); count],
+            blocked: Chunked::new(size, false),
+            view_blocked: Chunked::new(size, false),
+            fire_turns: Chunked::new(size, 0),
+            tile_damage: Chunked::new(size, 0),
+            fluids: Chunked::new(size, Fluids::default()),
+            tile_content: Chunked::new(size, Vec::new()),
             depth: new_depth,
-            dijkstra_map: vec![-1.0; count],
+            currents: Chunked::new(size, 5),
+            river_flow: 5,
+            regions: Chunked::new(size, None),
+            region_names: Vec::new(),
+            dijkstra_map: Chunked::new(size, -1.0),
+            light: Chunked::new(size, RGBA::from_f32(0., 0., 0., 1.)),
+            awake: vec![true; chunks],
+            fixed_blockers: HashSet::new(),
+            dirty_chunks: (0..chunks).collect(),
             // influence_maps:vec![vec![0.0; count]; 2],// todo magic numbers
         }
     }
 
     pub fn set_tile(&mut self, x: i32, y: i32, value: TileType) {
         let idx = self.xy_idx(x, y);
-        self.tiles[idx] = value;
+        self.replace_tile(idx, value);
+    }
+
+    /// Changes a tile after the map is built, anything cached over it is rebuilt
+    pub fn replace_tile(&mut self, idx: usize, value: TileType) {
+        if self.tiles[idx] != value {
+            self.tiles[idx] = value;
+            self.dirty_chunks.insert(self.tiles.chunk_of(idx));
+        }
+    }
+
+    /// Chunks where the ground or what's built on it changed since the last call.
+    /// Walkers blocking tiles as they go aren't counted, paths check for them step by step.
+    pub fn take_dirty_chunks(&mut self) -> HashSet<usize> {
+        std::mem::take(&mut self.dirty_chunks)
     }
 
     pub fn xy_idx(&self, x: i32, y: i32) -> usize {
@@ -87,35 +166,221 @@ impl Map {
     }
 
     pub fn is_wall(&self, x: i32, y: i32) -> bool {
-        let idx = self.xy_idx(x, y);
-        self.tiles[idx] == TileType::Wall
-            || self.tiles[idx] == TileType::WoodWall
-            || self.tiles[idx] == TileType::WoodDoor
+        tile_info(self.tiles[self.xy_idx(x, y)]).wall
     }
 
     pub fn is_flammable(&self, idx: usize) -> bool {
-        self.tiles[idx] == TileType::Grass
-            || self.tiles[idx] == TileType::Wheat
-            || self.tiles[idx] == TileType::WoodWall
-            || self.tiles[idx] == TileType::WoodDoor
+        tile_info(self.tiles[idx]).flammable
+    }
+
+    /// Sets a tile burning for as long as its fuel lasts, returns false if there's nothing there to burn.
+    /// Oil spilled on a tile burns even if the ground doesn't, and a soaked tile won't catch at all
+    pub fn ignite(&mut self, idx: usize) -> bool {
+        let fluids = self.fluids[idx];
+        let mut fuel = fluids.fuel();
+        if self.is_flammable(idx) {
+            fuel += tile_info(self.tiles[idx]).fuel;
+        }
+        if fuel <= 0 || self.fire_turns[idx] > 0 || fluids.douses() {
+            return false;
+        }
+
+        self.fire_turns[idx] = fuel;
+        true
+    }
+
+    /// Whether digging or explosions can break the tile, the edge of the map always holds
+    pub fn is_breakable(&self, idx: usize) -> bool {
+        tile_info(self.tiles[idx]).hardness > 0 && !self.on_edge(self.idx_point(idx))
+    }
+
+    /// Wears a tile down, returning what it was if that broke it
+    pub fn damage_tile(&mut self, idx: usize, amount: i32) -> Option<TileType> {
+        if !self.is_breakable(idx) {
+            return None;
+        }
+
+        let info = tile_info(self.tiles[idx]);
+        self.tile_damage[idx] += amount;
+        if self.tile_damage[idx] < info.hardness {
+            return None;
+        }
+
+        let old = self.tiles[idx];
+        self.replace_tile(idx, info.breaks_into.unwrap_or(TileType::Floor));
+        self.tile_damage[idx] = 0;
+        self.fire_turns[idx] = 0;
+
+        // anything standing next to it can step in straight away, map indexing catches up with the rest next turn
+        self.blocked[idx] = self.blocks_movement(idx);
+        Some(old)
     }
 
     pub fn blocks_movement(&self, idx: usize) -> bool {
-        self.tiles[idx] == TileType::Wall
-            || self.tiles[idx] == TileType::Water
-            || self.tiles[idx] == TileType::WoodWall
-            || self.tiles[idx] == TileType::WoodDoor
+        !tile_info(self.tiles[idx]).walkable
+    }
+
+    pub fn is_swimmable(&self, idx: usize) -> bool {
+        tile_info(self.tiles[idx]).swimmable
+    }
+
+    /// Whether the ground lets something that moves this way onto a tile, ignoring entities in the way
+    pub fn is_passable(&self, idx: usize, mtype: LocomotionType) -> bool {
+        match mtype {
+            LocomotionType::Ground => !self.blocks_movement(idx),
+            LocomotionType::Water => self.is_swimmable(idx),
+        }
     }
 
+    /// Resets blocking on awake tiles to the ground's, ready for map indexing to add what's standing there.
+    /// Sleeping chunks keep whatever was blocked when they fell asleep
     pub fn set_blocked(&mut self) {
-        for (i, _t) in self.tiles.iter().enumerate() {
-            self.blocked[i] = self.blocks_movement(i);
+        self.update_awake(|map, i| {
+            map.blocked[i] = map.blocks_movement(i);
+            map.view_blocked[i] = false;
+        });
+    }
+
+    /// The tiles blocked by entities that stay put this turn, marking the chunks where that changed
+    pub fn set_fixed_blockers(&mut self, blockers: HashSet<usize>) {
+        for idx in self.fixed_blockers.symmetric_difference(&blockers) {
+            self.dirty_chunks.insert(self.tiles.chunk_of(*idx));
+        }
+        self.fixed_blockers = blockers;
+    }
+
+    pub fn is_awake(&self, idx: usize) -> bool {
+        self.awake[self.tiles.chunk_of(idx)]
+    }
+
+    /// How bright a tile is, the strongest channel of its light
+    pub fn light_level(&self, idx: usize) -> f32 {
+        let light = self.light[idx];
+        light.r.max(light.g).max(light.b)
+    }
+
+    pub fn awake_tiles(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.awake.len())
+            .filter(move |chunk| self.awake[*chunk])
+            .flat_map(move |chunk| self.tiles.chunk_tiles(chunk))
+    }
+
+    /// Runs `f` on every awake tile, for passes that change the map as they go
+    pub fn update_awake(&mut self, mut f: impl FnMut(&mut Map, usize)) {
+        for chunk in 0..self.awake.len() {
+            if self.awake[chunk] {
+                for idx in self.tiles.chunk_tiles(chunk) {
+                    f(self, idx);
+                }
+            }
         }
     }
 
+    /// Wakes the chunks within `WAKE_RADIUS` of any of `points` and puts the rest to sleep.
+    /// Chunks that fall asleep drop their entity index and debug values, they're rebuilt when they wake.
+    pub fn wake_around(&mut self, points: &[Point]) {
+        let near: Vec<(i32, i32)> = points
+            .iter()
+            .filter(|p| self.in_bounds(p.x, p.y))
+            .map(|p| self.tiles.chunk_xy(self.tiles.chunk_of(self.point_idx(*p))))
+            .collect();
+
+        for chunk in 0..self.awake.len() {
+            let (cx, cy) = self.tiles.chunk_xy(chunk);
+            let awake = near
+                .iter()
+                .any(|(x, y)| (cx - x).abs() <= WAKE_RADIUS && (cy - y).abs() <= WAKE_RADIUS);
+
+            if self.awake[chunk] && !awake {
+                self.tile_content.unload(chunk);
+                self.dijkstra_map.unload(chunk);
+                self.light.unload(chunk);
+
+                // layers that have settled back down don't need storage while nobody's around
+                self.fire_turns.unload_if_default(chunk);
+                self.tile_damage.unload_if_default(chunk);
+                self.fluids.unload_if_default(chunk);
+                self.view_blocked.unload_if_default(chunk);
+            }
+            self.awake[chunk] = awake;
+        }
+    }
+
+    fn on_edge(&self, p: Point) -> bool {
+        p.x <= 0 || p.x >= self.width - 1 || p.y <= 0 || p.y >= self.height - 1
+    }
+
+    // water tiles where a river flowing in `flow` enters the map
+    pub fn is_river_source(&self, idx: usize, flow: usize) -> bool {
+        self.is_swimmable(idx) && self.on_edge(dir_to_point(self.idx_point(idx), 10 - flow, 1))
+    }
+
+    // water tiles where a river flowing in `flow` leaves the map
+    pub fn is_river_sink(&self, idx: usize, flow: usize) -> bool {
+        self.is_swimmable(idx) && self.on_edge(dir_to_point(self.idx_point(idx), flow, 1))
+    }
+
+    /// Points every water tile downhill towards the nearest sink, preferring `flow` when there's a tie.
+    /// Lakes and backwaters connected to a river slowly drain into it, water that can't reach a sink stays still.
+    pub fn generate_currents(&mut self, flow: usize) {
+        let count = self.tiles.len();
+        self.currents = Chunked::new((self.width, self.height), 5);
+        self.river_flow = flow;
+
+        let mut dist = vec![i32::MAX; count];
+        let mut queue = VecDeque::new();
+        for idx in 0..count {
+            if self.is_river_sink(idx, flow) {
+                dist[idx] = 0;
+                queue.push_back(idx);
+            }
+        }
+
+        while let Some(idx) = queue.pop_front() {
+            for n in get_neighbors(self.idx_point(idx)) {
+                if !self.in_bounds(n.x, n.y) {
+                    continue;
+                }
+
+                let nidx = self.point_idx(n);
+                if self.is_swimmable(nidx) && dist[nidx] == i32::MAX {
+                    dist[nidx] = dist[idx] + 1;
+                    queue.push_back(nidx);
+                }
+            }
+        }
+
+        for idx in 0..count {
+            if dist[idx] == i32::MAX {
+                continue;
+            }
+
+            if dist[idx] == 0 {
+                self.currents[idx] = flow;
+                continue;
+            }
+
+            let p = self.idx_point(idx);
+            let mut best_dist = dist[idx];
+            for dir in [flow, 1, 2, 3, 4, 6, 7, 8, 9] {
+                let n = dir_to_point(p, dir, 1);
+                if self.in_bounds(n.x, n.y) && dist[self.point_idx(n)] < best_dist {
+                    best_dist = dist[self.point_idx(n)];
+                    self.currents[idx] = dir;
+                }
+            }
+        }
+    }
+
+    pub fn region_name(&self, idx: usize) -> Option<&str> {
+        self.regions[idx].map(|r| self.region_names[r].as_str())
+    }
+
     pub fn clear_tile_content(&mut self) {
-        for content in self.tile_content.iter_mut() {
-            content.clear();
+        for chunk in 0..self.awake.len() {
+            if self.awake[chunk] {
+                self.tile_content.unload(chunk);
+            }
         }
     }
 
@@ -131,6 +396,28 @@ impl Map {
         mouse_pos.0 >= 0 && mouse_pos.0 <= self.width && mouse_pos.1 >= 0 && mouse_pos.1 <= self.height
     }
 
+    fn tile_distance(&self, idx1: usize, idx2: usize) -> f32 {
+        let w = self.width as usize;
+        let p1 = Point::new(idx1 % w, idx1 / w);
+        let p2 = Point::new(idx2 % w, idx2 / w);
+        rltk::DistanceAlg::Pythagoras.distance2d(p1, p2)
+    }
+
+    // moving onto rough ground costs more
+    fn exit_cost(&self, idx: usize, distance: f32) -> f32 {
+        distance * self.move_cost(idx, LocomotionType::Ground)
+    }
+
+    /// Cost of moving onto a tile, burning tiles are only crossed when there's no way around
+    pub fn move_cost(&self, idx: usize, mtype: LocomotionType) -> f32 {
+        let cost = tile_info(self.tiles[idx]).move_cost(mtype);
+        if self.fire_turns[idx] > 0 {
+            cost + BURNING_COST
+        } else {
+            cost
+        }
+    }
+
     fn is_exit_valid(&self, x: i32, y: i32) -> bool {
         if x < 1 || x >= self.width || y < 1 || y >= self.height {
             return false;
@@ -165,7 +452,7 @@ impl Map {
         let mut min = f32::MAX;
         for i1 in idx1.iter() {
             for i2 in idx2.iter() {
-                let dist = self.get_pathing_distance(*i1, *i2);
+                let dist = self.tile_distance(*i1, *i2);
                 if dist < min {
                     min = dist;
                 }
@@ -267,17 +554,11 @@ impl Algorithm2D for Map {
 
 impl BaseMap for Map {
     fn is_opaque(&self, idx: usize) -> bool {
-        self.tiles[idx] == TileType::Wall
-            || self.tiles[idx] == TileType::Wheat
-            || self.tiles[idx] == TileType::WoodWall
-            || self.tiles[idx] == TileType::WoodDoor // TODO make fire block too?
+        tile_info(self.tiles[idx]).opaque || self.view_blocked[idx] || self.fluids[idx].blocks_sight()
     }
 
     fn get_pathing_distance(&self, idx1: usize, idx2: usize) -> f32 {
-        let w = self.width as usize;
-        let p1 = Point::new(idx1 % w, idx1 / w);
-        let p2 = Point::new(idx2 % w, idx2 / w);
-        rltk::DistanceAlg::Pythagoras.distance2d(p1, p2)
+        self.tile_distance(idx1, idx2)
     }
 
     fn get_available_exits(&self, idx: usize) -> rltk::SmallVec<[(usize, f32); 10]> {
@@ -286,31 +567,90 @@ impl BaseMap for Map {
         let w = self.width as usize;
 
         if self.is_exit_valid(x - 1, y) {
-            exits.push((idx - 1, 1.0))
+            exits.push((idx - 1, self.exit_cost(idx - 1, 1.0)))
         };
         if self.is_exit_valid(x + 1, y) {
-            exits.push((idx + 1, 1.0))
+            exits.push((idx + 1, self.exit_cost(idx + 1, 1.0)))
         };
         if self.is_exit_valid(x, y - 1) {
-            exits.push((idx - w, 1.0))
+            exits.push((idx - w, self.exit_cost(idx - w, 1.0)))
         };
         if self.is_exit_valid(x, y + 1) {
-            exits.push((idx + w, 1.0))
+            exits.push((idx + w, self.exit_cost(idx + w, 1.0)))
         };
 
         if self.is_exit_valid(x - 1, y - 1) {
-            exits.push((idx - w - 1, 1.45))
+            exits.push((idx - w - 1, self.exit_cost(idx - w - 1, 1.45)))
         };
         if self.is_exit_valid(x + 1, y - 1) {
-            exits.push((idx - w + 1, 1.45))
+            exits.push((idx - w + 1, self.exit_cost(idx - w + 1, 1.45)))
         };
         if self.is_exit_valid(x - 1, y + 1) {
-            exits.push((idx + w - 1, 1.45))
+            exits.push((idx + w - 1, self.exit_cost(idx + w - 1, 1.45)))
         };
         if self.is_exit_valid(x + 1, y + 1) {
-            exits.push((idx + w + 1, 1.45))
+            exits.push((idx + w + 1, self.exit_cost(idx + w + 1, 1.45)))
         };
 
         exits
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a floor with a wall in the middle of it, away from the edge
+    fn map_with(tile: TileType) -> (Map, usize) {
+        let mut map = Map::new(1, TileType::Floor, (10, 10));
+        let idx = map.xy_idx(5, 5);
+        map.set_tile(5, 5, tile);
+        map.blocked[idx] = map.blocks_movement(idx);
+        map.take_dirty_chunks();
+        (map, idx)
+    }
+
+    #[test]
+    fn wear_builds_up_until_the_tile_breaks() {
+        let (mut map, idx) = map_with(TileType::Wall);
+        let hardness = tile_info(TileType::Wall).hardness;
+
+        assert_eq!(
+            map.damage_tile(idx, hardness - 1),
+            None,
+            "the wall broke before its hardness"
+        );
+        assert_eq!(map.tiles[idx], TileType::Wall);
+        assert_eq!(map.tile_damage[idx], hardness - 1);
+        assert!(
+            map.take_dirty_chunks().is_empty(),
+            "wear alone doesn't change any paths"
+        );
+
+        assert_eq!(map.damage_tile(idx, 1), Some(TileType::Wall));
+        assert_eq!(map.tiles[idx], TileType::Floor);
+        assert_eq!(map.tile_damage[idx], 0, "wear carried over to the broken tile");
+        assert!(!map.blocked[idx], "the hole is still blocked");
+        assert!(map.take_dirty_chunks().contains(&map.tiles.chunk_of(idx)));
+    }
+
+    #[test]
+    fn tiles_break_into_their_broken_tile() {
+        let (mut map, idx) = map_with(TileType::WoodWall);
+
+        assert_eq!(map.damage_tile(idx, 100), Some(TileType::WoodWall));
+        assert_eq!(map.tiles[idx], TileType::WoodFloor);
+    }
+
+    #[test]
+    fn unbreakable_and_edge_tiles_hold() {
+        let (mut map, idx) = map_with(TileType::Floor);
+        assert_eq!(map.damage_tile(idx, 100), None);
+        assert_eq!(map.tile_damage[idx], 0, "an unbreakable tile took wear");
+
+        let edge = map.xy_idx(0, 5);
+        map.set_tile(0, 5, TileType::Wall);
+        assert_eq!(map.damage_tile(edge, 100), None);
+        assert_eq!(map.tiles[edge], TileType::Wall, "the edge of the map broke");
+    }
+}
@@ -0,0 +1,64 @@
+use rltk::{Point, RandomNumberGenerator};
+
+use super::{BuildData, MetaMapBuilder, Position, TileType};
+
+#[allow(dead_code)]
+pub enum XStart {
+    Left,
+    Center,
+    Right,
+}
+
+#[allow(dead_code)]
+pub enum YStart {
+    Top,
+    Center,
+    Bottom,
+}
+
+/// Starts the player on the open tile closest to a part of the map
+pub struct AreaStartingPosition {
+    x: XStart,
+    y: YStart,
+}
+
+impl MetaMapBuilder for AreaStartingPosition {
+    fn build_map(&mut self, _rng: &mut RandomNumberGenerator, data: &mut BuildData) {
+        let seed = Point {
+            x: match self.x {
+                XStart::Left => 1,
+                XStart::Center => data.map.width / 2,
+                XStart::Right => data.map.width - 2,
+            },
+            y: match self.y {
+                YStart::Top => 1,
+                YStart::Center => data.map.height / 2,
+                YStart::Bottom => data.map.height - 2,
+            },
+        };
+
+        let mut closest: Option<(usize, f32)> = None;
+        for idx in 0..data.map.tiles.len() {
+            if data.map.blocks_movement(idx) || data.map.tiles[idx] == TileType::StairsDown {
+                continue;
+            }
+
+            let dist = rltk::DistanceAlg::PythagorasSquared.distance2d(seed, data.map.idx_point(idx));
+            if closest.map_or(true, |(_, d)| dist < d) {
+                closest = Some((idx, dist));
+            }
+        }
+
+        if let Some((idx, _)) = closest {
+            data.starting_position = Some(Position {
+                ps: vec![data.map.idx_point(idx)],
+            });
+        }
+    }
+}
+
+impl AreaStartingPosition {
+    pub fn new(x: XStart, y: YStart) -> Box<AreaStartingPosition> {
+        Box::new(AreaStartingPosition { x, y })
+    }
+}
@@ -6,12 +6,16 @@ use crate::{
     entity_factory, SHOW_MAPGEN_ANIMATION,
 };
 
-use super::{Map, MapBuilder, Position, TileType};
+use super::{
+    connectivity::{ensure_connectivity, ConnectivityMode},
+    ConnectivityReport, Map, MapBuilder, Position, TileType,
+};
 
 pub struct AernaBuilder {
     map: Map,
     starting_position: Position,
     history: Vec<Map>,
+    connectivity: Option<ConnectivityReport>,
 }
 
 impl MapBuilder for AernaBuilder {
@@ -23,7 +27,11 @@ impl MapBuilder for AernaBuilder {
         self.starting_position.clone()
     }
     fn build_map(&mut self) {
-        self.build()
+        self.build();
+
+        let start = self.map.point_idx(self.starting_position.any_point());
+        self.connectivity = Some(ensure_connectivity(&mut self.map, start, &ConnectivityMode::Cull));
+        self.take_snapshot();
     }
 
     fn spawn_entities(&mut self, world: &mut World) {
@@ -35,6 +43,7 @@ impl MapBuilder for AernaBuilder {
                 Faction::Wizard1,
                 SpawnerType::Orc,
                 10,
+                30,
             )
         });
         world.run(|mut store: AllStoragesViewMut| {
@@ -45,6 +54,7 @@ impl MapBuilder for AernaBuilder {
                 Faction::Wizard2,
                 SpawnerType::Orc,
                 10,
+                30,
             )
         });
     }
@@ -58,6 +68,10 @@ impl MapBuilder for AernaBuilder {
             self.history.push(self.map.clone());
         }
     }
+
+    fn get_connectivity(&self) -> Option<ConnectivityReport> {
+        self.connectivity.clone()
+    }
 }
 
 impl AernaBuilder {
@@ -68,6 +82,7 @@ impl AernaBuilder {
                 ps: vec![Point { x: 0, y: 0 }],
             },
             history: Vec::new(),
+            connectivity: None,
         }
     }
 
@@ -3,7 +3,11 @@ use shipyard::{AllStoragesViewMut, World};
 
 use crate::{entity_factory, SHOW_MAPGEN_ANIMATION};
 
-use super::{apply_room_to_map, Map, MapBuilder, Position, Rect, TileType};
+use super::{
+    apply_room_to_map,
+    connectivity::{ensure_connectivity, ConnectivityMode},
+    ConnectivityReport, Map, MapBuilder, Position, Rect, TileType,
+};
 
 pub struct BspDungeonBuilder {
     map: Map,
@@ -11,6 +15,7 @@ pub struct BspDungeonBuilder {
     depth: i32,
     rooms: Vec<Rect>,
     history: Vec<Map>,
+    connectivity: Option<ConnectivityReport>,
     rects: Vec<Rect>,
 }
 
@@ -24,7 +29,11 @@ impl MapBuilder for BspDungeonBuilder {
     }
 
     fn build_map(&mut self) {
-        self.build()
+        self.build();
+
+        let start = self.map.point_idx(self.starting_position.any_point());
+        self.connectivity = Some(ensure_connectivity(&mut self.map, start, &ConnectivityMode::Cull));
+        self.take_snapshot();
     }
 
     fn spawn_entities(&mut self, world: &mut World) {
@@ -44,6 +53,14 @@ impl MapBuilder for BspDungeonBuilder {
             self.history.push(self.map.clone());
         }
     }
+
+    fn get_rooms(&self) -> Vec<Rect> {
+        self.rooms.clone()
+    }
+
+    fn get_connectivity(&self) -> Option<ConnectivityReport> {
+        self.connectivity.clone()
+    }
 }
 
 impl BspDungeonBuilder {
@@ -56,6 +73,7 @@ impl BspDungeonBuilder {
             depth: new_depth,
             rooms: Vec::new(),
             history: Vec::new(),
+            connectivity: None,
             rects: Vec::new(),
         }
     }
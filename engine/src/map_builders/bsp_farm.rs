@@ -1,9 +1,16 @@
 use rltk::{Point, RandomNumberGenerator};
 use shipyard::{AllStoragesViewMut, World};
 
-use crate::{entity_factory, SHOW_MAPGEN_ANIMATION};
+use crate::{
+    components::{DoorLock, Faction},
+    entity_factory, SHOW_MAPGEN_ANIMATION,
+};
 
-use super::{common::apply_room_to_map, Map, MapBuilder, Position, Rect, TileType};
+use super::{
+    common::apply_room_to_map,
+    connectivity::{ensure_connectivity, ConnectivityMode},
+    ConnectivityReport, Map, MapBuilder, Position, Rect, TileType,
+};
 
 const MIN_ROOM_SIZE: i32 = 10;
 
@@ -15,7 +22,9 @@ pub struct BspFarmBuilder {
     depth: i32,
     rooms: Vec<Rect>,
     history: Vec<Map>,
+    connectivity: Option<ConnectivityReport>,
     rects: Vec<Rect>,
+    doors: Vec<(usize, Option<DoorLock>)>,
 }
 
 impl MapBuilder for BspFarmBuilder {
@@ -28,7 +37,11 @@ impl MapBuilder for BspFarmBuilder {
     }
 
     fn build_map(&mut self) {
-        self.build()
+        self.build();
+
+        let start = self.map.point_idx(self.starting_position.any_point());
+        self.connectivity = Some(ensure_connectivity(&mut self.map, start, &ConnectivityMode::Cull));
+        self.take_snapshot();
     }
 
     fn spawn_entities(&mut self, world: &mut World) {
@@ -36,6 +49,11 @@ impl MapBuilder for BspFarmBuilder {
             for room in self.rooms.iter().skip(1) {
                 entity_factory::spawn_room(&mut store, &self.map, room, self.depth);
             }
+
+            for (idx, lock) in self.doors.iter() {
+                let (x, y) = self.map.idx_xy(*idx);
+                entity_factory::door(&mut store, x, y, lock.clone());
+            }
         });
     }
 
@@ -48,6 +66,14 @@ impl MapBuilder for BspFarmBuilder {
             self.history.push(self.map.clone());
         }
     }
+
+    fn get_rooms(&self) -> Vec<Rect> {
+        self.rooms.clone()
+    }
+
+    fn get_connectivity(&self) -> Option<ConnectivityReport> {
+        self.connectivity.clone()
+    }
 }
 
 impl BspFarmBuilder {
@@ -60,7 +86,9 @@ impl BspFarmBuilder {
             depth: new_depth,
             rooms: Vec::new(),
             history: Vec::new(),
+            connectivity: None,
             rects: Vec::new(),
+            doors: Vec::new(),
         }
     }
 
@@ -98,6 +126,16 @@ impl BspFarmBuilder {
                     let by = rng.range(0, room.height() - b_height);
                     let room = Rect::new(room.x1 + bx, room.y1 + by, b_width, b_height);
                     apply_room_to_map(&mut self.map, &room, TileType::WoodWall, false);
+
+                    // a door somewhere along the front, some farmers keep theirs locked
+                    let door_idx = self.map.xy_idx(rng.range(room.x1 + 1, room.x2 - 1), room.y2 - 1);
+                    self.map.tiles[door_idx] = TileType::WoodDoor;
+                    let lock = if rng.roll_dice(1, 3) == 1 {
+                        Some(DoorLock::Faction(Faction::Villager))
+                    } else {
+                        None
+                    };
+                    self.doors.push((door_idx, lock));
                 }
             }
 
@@ -3,7 +3,10 @@ use shipyard::{AllStoragesViewMut, World};
 
 use crate::{entity_factory, SHOW_MAPGEN_ANIMATION};
 
-use super::{Map, MapBuilder, Position, Rect, TileType};
+use super::{
+    connectivity::{ensure_connectivity, ConnectivityMode},
+    ConnectivityReport, Map, MapBuilder, Position, Rect, TileType,
+};
 
 const MIN_ROOM_SIZE: i32 = 4;
 
@@ -13,6 +16,7 @@ pub struct BspInteriorBuilder {
     depth: i32,
     rooms: Vec<Rect>,
     history: Vec<Map>,
+    connectivity: Option<ConnectivityReport>,
     rects: Vec<Rect>,
 }
 
@@ -26,7 +30,11 @@ impl MapBuilder for BspInteriorBuilder {
     }
 
     fn build_map(&mut self) {
-        self.build()
+        self.build();
+
+        let start = self.map.point_idx(self.starting_position.any_point());
+        self.connectivity = Some(ensure_connectivity(&mut self.map, start, &ConnectivityMode::Cull));
+        self.take_snapshot();
     }
 
     fn spawn_entities(&mut self, world: &mut World) {
@@ -46,6 +54,14 @@ impl MapBuilder for BspInteriorBuilder {
             self.history.push(self.map.clone());
         }
     }
+
+    fn get_rooms(&self) -> Vec<Rect> {
+        self.rooms.clone()
+    }
+
+    fn get_connectivity(&self) -> Option<ConnectivityReport> {
+        self.connectivity.clone()
+    }
 }
 
 impl BspInteriorBuilder {
@@ -58,6 +74,7 @@ impl BspInteriorBuilder {
             depth: new_depth,
             rooms: Vec::new(),
             history: Vec::new(),
+            connectivity: None,
             rects: Vec::new(),
         }
     }
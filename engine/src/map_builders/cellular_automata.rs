@@ -5,13 +5,17 @@ use shipyard::{AllStoragesViewMut, World};
 
 use crate::{entity_factory, SHOW_MAPGEN_ANIMATION};
 
-use super::{Map, MapBuilder, Position, TileType};
+use super::{
+    connectivity::{ensure_connectivity, ConnectivityMode},
+    ConnectivityReport, Map, MapBuilder, Position, TileType,
+};
 
 pub struct CellularAutomataBuilder {
     map: Map,
     starting_position: Position,
     depth: i32,
     history: Vec<Map>,
+    connectivity: Option<ConnectivityReport>,
     noise_areas: HashMap<i32, Vec<usize>>,
 }
 
@@ -24,7 +28,11 @@ impl MapBuilder for CellularAutomataBuilder {
         self.starting_position.clone()
     }
     fn build_map(&mut self) {
-        self.build()
+        self.build();
+
+        let start = self.map.point_idx(self.starting_position.any_point());
+        self.connectivity = Some(ensure_connectivity(&mut self.map, start, &ConnectivityMode::Connect));
+        self.take_snapshot();
     }
 
     fn spawn_entities(&mut self, world: &mut World) {
@@ -44,6 +52,10 @@ impl MapBuilder for CellularAutomataBuilder {
             self.history.push(self.map.clone());
         }
     }
+
+    fn get_connectivity(&self) -> Option<ConnectivityReport> {
+        self.connectivity.clone()
+    }
 }
 
 impl CellularAutomataBuilder {
@@ -55,6 +67,7 @@ impl CellularAutomataBuilder {
             },
             depth: new_depth,
             history: Vec::new(),
+            connectivity: None,
             noise_areas: HashMap::new(),
         }
     }
@@ -137,7 +150,7 @@ impl CellularAutomataBuilder {
         let map_starts: Vec<usize> = vec![start_idx];
         let dijkstra_map = rltk::DijkstraMap::new(self.map.width, self.map.height, &map_starts, &self.map, 200.0);
         let mut exit_tile = (0, 0.0f32);
-        for (i, tile) in self.map.tiles.iter_mut().enumerate() {
+        self.map.tiles.update(|i, tile| {
             if *tile == TileType::Floor {
                 let distance_to_start = dijkstra_map.map[i];
                 // We can't get to this tile - so we'll make it a wall
@@ -151,7 +164,7 @@ impl CellularAutomataBuilder {
                     }
                 }
             }
-        }
+        });
         self.take_snapshot();
 
         self.map.tiles[exit_tile.0] = TileType::StairsDown;
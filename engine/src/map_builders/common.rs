@@ -1,6 +1,8 @@
 use super::{Map, Rect, TileType};
+use crate::utils::get_neighbors;
 use rltk::RandomNumberGenerator;
 use std::cmp;
+use std::collections::VecDeque;
 
 pub fn rect_in_bounds(map: &mut Map, room: &Rect) -> bool {
     map.in_bounds(room.x1, room.y1) && map.in_bounds(room.x2, room.y2)
@@ -65,6 +67,31 @@ pub fn apply_drunkards_corrider(map: &mut Map, x1: i32, y1: i32, x2: i32, y2: i3
     }
 }
 
+/// Steps needed to walk from `start` to every tile, tiles that can't be reached are i32::MAX.
+/// Builders can't use the map's `blocked` list since it's only filled in once the map is indexed.
+pub fn walk_distances(map: &Map, start: usize) -> Vec<i32> {
+    let mut dist = vec![i32::MAX; map.tiles.len()];
+    let mut queue = VecDeque::new();
+    dist[start] = 0;
+    queue.push_back(start);
+
+    while let Some(idx) = queue.pop_front() {
+        for n in get_neighbors(map.idx_point(idx)) {
+            if !map.in_bounds(n.x, n.y) {
+                continue;
+            }
+
+            let nidx = map.point_idx(n);
+            if dist[nidx] == i32::MAX && !map.blocks_movement(nidx) {
+                dist[nidx] = dist[idx] + 1;
+                queue.push_back(nidx);
+            }
+        }
+    }
+
+    dist
+}
+
 // pub fn remove_useless_walls(map: &mut Map) {
 //     let mut to_remove: Vec<(i32, i32)> = Vec::new();
 
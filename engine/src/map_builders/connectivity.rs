@@ -0,0 +1,268 @@
+use std::collections::VecDeque;
+
+use rltk::RandomNumberGenerator;
+
+use crate::utils::get_neighbors;
+
+use super::{common::walk_distances, BuildData, Map, MetaMapBuilder, TileType};
+
+// pockets smaller than this aren't worth a corridor and get filled in
+const MIN_REGION_SIZE: usize = 6;
+
+/// What the connectivity pass found and fixed, for judging how good a builder's maps are
+#[derive(Clone, Debug, Default)]
+pub struct ConnectivityReport {
+    /// Walkable tiles once the pass is done
+    pub open_tiles: usize,
+    /// Walkable tiles that can be reached from the start once the pass is done
+    pub reachable_tiles: usize,
+    /// Separate walkable regions before the pass, counting the one the start is in
+    pub regions: usize,
+    pub regions_connected: usize,
+    pub tiles_culled: usize,
+    pub tiles_dug: usize,
+    /// Whether the stairs had to be put somewhere new
+    pub stairs_placed: bool,
+}
+
+impl ConnectivityReport {
+    pub fn reachable_ratio(&self) -> f32 {
+        if self.open_tiles == 0 {
+            return 0.0;
+        }
+        self.reachable_tiles as f32 / self.open_tiles as f32
+    }
+}
+
+pub enum ConnectivityMode {
+    /// Wall off everything that can't be reached
+    Cull,
+    /// Dig corridors to regions big enough to be worth it and wall off the rest
+    Connect,
+}
+
+/// Makes sure every open tile and a way down can be walked to from the starting position
+pub struct Connectivity {
+    mode: ConnectivityMode,
+}
+
+impl MetaMapBuilder for Connectivity {
+    fn build_map(&mut self, _rng: &mut RandomNumberGenerator, data: &mut BuildData) {
+        let start = match &data.starting_position {
+            Some(pos) => data.map.point_idx(pos.any_point()),
+            None => panic!("Checking connectivity requires a starting position"),
+        };
+        if data.map.blocks_movement(start) {
+            panic!("The starting position is inside a wall");
+        }
+
+        let mut report = ensure_connectivity(&mut data.map, start, &self.mode);
+
+        // nothing gets to spawn inside the rock that filled its pocket in
+        let map = &data.map;
+        data.spawn_list.retain(|(idx, _)| !map.blocks_movement(*idx));
+
+        // the pass already put back any stairs it walled in, this is for samples that never had any
+        if !has_stairs(&data.map) {
+            report.stairs_placed = place_stairs(&mut data.map, start);
+        }
+
+        data.connectivity = Some(report);
+        data.take_snapshot();
+    }
+}
+
+impl Connectivity {
+    pub fn cull() -> Box<Connectivity> {
+        Box::new(Connectivity {
+            mode: ConnectivityMode::Cull,
+        })
+    }
+
+    pub fn connect() -> Box<Connectivity> {
+        Box::new(Connectivity {
+            mode: ConnectivityMode::Connect,
+        })
+    }
+}
+
+/// Connects or walls off every region that can't be walked to from `start`, for builders that aren't chains.
+/// A start inside a wall counts the biggest region as the one everything has to reach.
+/// Stairs walled off along with their region are put back as far from the start as can be walked to.
+pub fn ensure_connectivity(map: &mut Map, start: usize, mode: &ConnectivityMode) -> ConnectivityReport {
+    let mut report = ConnectivityReport::default();
+    let had_stairs = has_stairs(map);
+    let (mut labels, sizes) = label_regions(map);
+    report.regions = sizes.len();
+
+    let main = match labels[start] {
+        Some(region) => region,
+        None => match (0..sizes.len()).max_by_key(|r| sizes[*r]) {
+            Some(region) => region,
+            None => return report,
+        },
+    };
+
+    for (region, size) in sizes.iter().enumerate() {
+        if region == main {
+            continue;
+        }
+
+        let path = match mode {
+            ConnectivityMode::Connect if *size >= MIN_REGION_SIZE => dig_path(map, &labels, region, main),
+            _ => vec![],
+        };
+
+        if path.is_empty() {
+            for (idx, label) in labels.iter_mut().enumerate() {
+                if *label == Some(region) {
+                    map.tiles[idx] = TileType::Wall;
+                    *label = None;
+                    report.tiles_culled += 1;
+                }
+            }
+        } else {
+            for idx in path {
+                if map.blocks_movement(idx) {
+                    map.tiles[idx] = dug_tile(map.tiles[idx]);
+                    report.tiles_dug += 1;
+                }
+                labels[idx] = Some(main);
+            }
+            for label in labels.iter_mut() {
+                if *label == Some(region) {
+                    *label = Some(main);
+                }
+            }
+            report.regions_connected += 1;
+        }
+    }
+
+    let from = match labels.iter().position(|l| *l == Some(main)) {
+        Some(idx) => idx,
+        None => return report,
+    };
+    if had_stairs && !has_stairs(map) {
+        let from = if labels[start] == Some(main) { start } else { from };
+        report.stairs_placed = place_stairs(map, from);
+    }
+
+    let dist = walk_distances(map, from);
+    for (idx, d) in dist.iter().enumerate() {
+        if !map.blocks_movement(idx) {
+            report.open_tiles += 1;
+            if *d != i32::MAX {
+                report.reachable_tiles += 1;
+            }
+        }
+    }
+
+    report
+}
+
+fn has_stairs(map: &Map) -> bool {
+    map.tiles.iter().any(|t| *t == TileType::StairsDown)
+}
+
+// puts the stairs on the tile farthest from `from` that can be walked to, false if there's nowhere but `from`
+fn place_stairs(map: &mut Map, from: usize) -> bool {
+    let dist = walk_distances(map, from);
+    let farthest = (0..dist.len())
+        .filter(|idx| dist[*idx] != i32::MAX && *idx != from)
+        .max_by_key(|idx| dist[*idx]);
+    match farthest {
+        Some(idx) => {
+            map.tiles[idx] = TileType::StairsDown;
+            true
+        }
+        None => false,
+    }
+}
+
+// what a corridor turns each tile it's dug through into, so it fits in with what's around it
+fn dug_tile(tile: TileType) -> TileType {
+    match tile {
+        TileType::Water => TileType::Bridge,
+        TileType::WoodWall => TileType::WoodDoor,
+        _ => TileType::Floor,
+    }
+}
+
+/// Flood fills every walkable region, returns the region of each tile and the size of each region
+pub fn label_regions(map: &Map) -> (Vec<Option<usize>>, Vec<usize>) {
+    let mut labels: Vec<Option<usize>> = vec![None; map.tiles.len()];
+    let mut sizes: Vec<usize> = vec![];
+
+    for seed in 0..map.tiles.len() {
+        if labels[seed].is_some() || map.blocks_movement(seed) {
+            continue;
+        }
+
+        let region = sizes.len();
+        let mut size = 0;
+        let mut queue = VecDeque::new();
+        labels[seed] = Some(region);
+        queue.push_back(seed);
+
+        while let Some(idx) = queue.pop_front() {
+            size += 1;
+            for n in get_neighbors(map.idx_point(idx)) {
+                if !map.in_bounds(n.x, n.y) {
+                    continue;
+                }
+
+                let nidx = map.point_idx(n);
+                if labels[nidx].is_none() && !map.blocks_movement(nidx) {
+                    labels[nidx] = Some(region);
+                    queue.push_back(nidx);
+                }
+            }
+        }
+
+        sizes.push(size);
+    }
+
+    (labels, sizes)
+}
+
+// the shortest straight-stepping run of tiles from one region to another, never through the map's outer wall
+fn dig_path(map: &Map, labels: &[Option<usize>], from: usize, to: usize) -> Vec<usize> {
+    let mut parent: Vec<Option<usize>> = vec![None; map.tiles.len()];
+    let mut seen = vec![false; map.tiles.len()];
+    let mut queue = VecDeque::new();
+
+    for (idx, label) in labels.iter().enumerate() {
+        if *label == Some(from) {
+            seen[idx] = true;
+            queue.push_back(idx);
+        }
+    }
+
+    while let Some(idx) = queue.pop_front() {
+        if labels[idx] == Some(to) {
+            let mut path = vec![];
+            let mut step = parent[idx];
+            while let Some(p) = step {
+                path.push(p);
+                step = parent[p];
+            }
+            return path;
+        }
+
+        let (x, y) = map.idx_xy(idx);
+        for (nx, ny) in [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)].iter() {
+            if *nx < 1 || *ny < 1 || *nx >= map.width - 1 || *ny >= map.height - 1 {
+                continue;
+            }
+
+            let nidx = map.xy_idx(*nx, *ny);
+            if !seen[nidx] {
+                seen[nidx] = true;
+                parent[nidx] = Some(idx);
+                queue.push_back(nidx);
+            }
+        }
+    }
+
+    vec![]
+}
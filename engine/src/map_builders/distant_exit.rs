@@ -0,0 +1,41 @@
+use rltk::RandomNumberGenerator;
+
+use super::{common::walk_distances, BuildData, MetaMapBuilder, TileType};
+
+/// Moves the way down to the reachable tile furthest from the start
+pub struct DistantExit {}
+
+impl MetaMapBuilder for DistantExit {
+    fn build_map(&mut self, _rng: &mut RandomNumberGenerator, data: &mut BuildData) {
+        let start = match &data.starting_position {
+            Some(pos) => pos.any_point(),
+            None => panic!("Placing a distant exit requires a starting position"),
+        };
+
+        // only one way down
+        data.map.tiles.update(|_, tile| {
+            if *tile == TileType::StairsDown {
+                *tile = TileType::Floor;
+            }
+        });
+
+        let dist = walk_distances(&data.map, data.map.point_idx(start));
+        let mut exit: Option<(usize, i32)> = None;
+        for (idx, d) in dist.iter().enumerate() {
+            if *d != i32::MAX && exit.map_or(true, |(_, best)| *d > best) {
+                exit = Some((idx, *d));
+            }
+        }
+
+        if let Some((idx, _)) = exit {
+            data.map.tiles[idx] = TileType::StairsDown;
+            data.take_snapshot();
+        }
+    }
+}
+
+impl DistantExit {
+    pub fn new() -> Box<DistantExit> {
+        Box::new(DistantExit {})
+    }
+}
@@ -0,0 +1,70 @@
+use rltk::RandomNumberGenerator;
+
+use super::{BuildData, Map, MetaMapBuilder, TileType};
+
+/// Hangs doors in the narrow gaps where corridors open up into rooms
+pub struct DoorPlacement {}
+
+impl MetaMapBuilder for DoorPlacement {
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator, data: &mut BuildData) {
+        let start = data
+            .starting_position
+            .as_ref()
+            .map(|pos| data.map.point_idx(pos.any_point()));
+
+        let mut doors: Vec<usize> = vec![];
+        for y in 1..data.map.height - 1 {
+            for x in 1..data.map.width - 1 {
+                let idx = data.map.xy_idx(x, y);
+                if Some(idx) == start || !is_door_candidate(&data.map, x, y) {
+                    continue;
+                }
+
+                // a row of doors down a corridor looks silly
+                let next_to_door = doors.iter().any(|d| {
+                    let (dx, dy) = data.map.idx_xy(*d);
+                    (dx - x).abs() <= 1 && (dy - y).abs() <= 1
+                });
+                if next_to_door {
+                    continue;
+                }
+
+                // leave some openings bare
+                if rng.roll_dice(1, 3) == 1 {
+                    continue;
+                }
+
+                doors.push(idx);
+            }
+        }
+
+        for idx in doors {
+            data.spawn_list.push((idx, "Door".to_string()));
+        }
+    }
+}
+
+impl DoorPlacement {
+    pub fn new() -> Box<DoorPlacement> {
+        Box::new(DoorPlacement {})
+    }
+}
+
+// open on two opposite sides, walled on the other two, with open space diagonally on at least one end
+fn is_door_candidate(map: &Map, x: i32, y: i32) -> bool {
+    let open = |x: i32, y: i32| !map.blocks_movement(map.xy_idx(x, y));
+
+    if !open(x, y) || map.tiles[map.xy_idx(x, y)] == TileType::StairsDown {
+        return false;
+    }
+
+    let horizontal = open(x - 1, y) && open(x + 1, y) && !open(x, y - 1) && !open(x, y + 1);
+    let vertical = open(x, y - 1) && open(x, y + 1) && !open(x - 1, y) && !open(x + 1, y);
+    if !horizontal && !vertical {
+        return false;
+    }
+
+    [(-1, -1), (1, -1), (-1, 1), (1, 1)]
+        .iter()
+        .any(|(dx, dy)| open(x + dx, y + dy))
+}
@@ -1,4 +1,4 @@
-use crate::{entity_factory, SHOW_MAPGEN_ANIMATION};
+use crate::{chunks::Chunked, entity_factory, SHOW_MAPGEN_ANIMATION};
 use rand::seq::SliceRandom;
 use rand::thread_rng;
 use rltk::{Point, RandomNumberGenerator};
@@ -6,7 +6,10 @@ use shipyard::{AllStoragesViewMut, World};
 use std::cmp;
 
 use super::common::apply_drunkards_corrider;
-use super::{Map, MapBuilder, Position, Rect, TileType};
+use super::{
+    connectivity::{ensure_connectivity, ConnectivityMode},
+    ConnectivityReport, Map, MapBuilder, Position, Rect, TileType,
+};
 
 pub struct DrunkardsBombingRunBuilder {
     map: Map,
@@ -14,6 +17,7 @@ pub struct DrunkardsBombingRunBuilder {
     depth: i32,
     rooms: Vec<Rect>,
     history: Vec<Map>,
+    connectivity: Option<ConnectivityReport>,
 }
 
 impl MapBuilder for DrunkardsBombingRunBuilder {
@@ -27,6 +31,10 @@ impl MapBuilder for DrunkardsBombingRunBuilder {
 
     fn build_map(&mut self) {
         self.rooms_and_corridors(20, 4, 8);
+
+        let start = self.map.point_idx(self.starting_position.any_point());
+        self.connectivity = Some(ensure_connectivity(&mut self.map, start, &ConnectivityMode::Connect));
+        self.take_snapshot();
     }
 
     fn spawn_entities(&mut self, world: &mut World) {
@@ -46,6 +54,14 @@ impl MapBuilder for DrunkardsBombingRunBuilder {
             self.history.push(self.map.clone());
         }
     }
+
+    fn get_rooms(&self) -> Vec<Rect> {
+        self.rooms.clone()
+    }
+
+    fn get_connectivity(&self) -> Option<ConnectivityReport> {
+        self.connectivity.clone()
+    }
 }
 
 impl DrunkardsBombingRunBuilder {
@@ -58,6 +74,7 @@ impl DrunkardsBombingRunBuilder {
             depth: new_depth,
             rooms: Vec::new(),
             history: Vec::new(),
+            connectivity: None,
         }
     }
 
@@ -266,7 +283,7 @@ impl DrunkardsBombingRunBuilder {
     // get all connected tiles of same type, and size of fill (number of trues in vec)
     pub fn get_flood_fill(
         &self,
-        image: &Vec<TileType>,
+        image: &Chunked<TileType>,
         sidx: usize,
         // new_color: i32,
     ) -> (Vec<bool>, i32) {
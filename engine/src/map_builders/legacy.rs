@@ -0,0 +1,30 @@
+use rltk::RandomNumberGenerator;
+
+use super::{BuildData, InitialMapBuilder, MapBuilder};
+
+/// Wraps one of the standalone builders so it can start a chain, its own spawning is left to later steps
+pub struct LegacyBuilder {
+    builder: Box<dyn MapBuilder>,
+}
+
+impl InitialMapBuilder for LegacyBuilder {
+    fn build_map(&mut self, _rng: &mut RandomNumberGenerator, data: &mut BuildData) {
+        self.builder.build_map();
+
+        data.history.append(&mut self.builder.get_map_history());
+        data.map = self.builder.get_map();
+        data.starting_position = Some(self.builder.get_starting_position());
+        data.connectivity = self.builder.get_connectivity();
+
+        let rooms = self.builder.get_rooms();
+        if !rooms.is_empty() {
+            data.rooms = Some(rooms);
+        }
+    }
+}
+
+impl LegacyBuilder {
+    pub fn new(builder: Box<dyn MapBuilder>) -> Box<LegacyBuilder> {
+        Box::new(LegacyBuilder { builder })
+    }
+}
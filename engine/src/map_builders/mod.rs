@@ -25,20 +25,95 @@ use self::village::VillageBuilder;
 mod village_world;
 use self::village_world::VillageWorldBuilder;
 
+mod terrain;
+
+mod orc_halls;
+use self::orc_halls::OrcHallsBuilder;
+
+mod legacy;
+use self::legacy::LegacyBuilder;
+
+mod connectivity;
+use self::connectivity::Connectivity;
+pub use self::connectivity::ConnectivityReport;
+
+mod area_starting_points;
+use self::area_starting_points::{AreaStartingPosition, XStart, YStart};
+
+mod distant_exit;
+use self::distant_exit::DistantExit;
+
+mod door_placement;
+use self::door_placement::DoorPlacement;
+
+mod region_spawner;
+use self::region_spawner::RegionSpawner;
+
+mod prefab_builder;
+pub use self::prefab_builder::load_prefabs;
+use self::prefab_builder::{PrefabBuilder, CROSSROADS_LEVEL, ORC_FORT};
+
+mod voronoi;
+use self::voronoi::VoronoiBuilder;
+
+mod wave_function_collapse;
+use self::wave_function_collapse::WaveFunctionCollapseBuilder;
+
 mod common;
 use common::*;
-use shipyard::World;
+use rltk::{Point, RandomNumberGenerator};
+use shipyard::{AllStoragesViewMut, World};
 
 use crate::components::Position;
+use crate::entity_factory;
 use crate::map::{Map, TileType};
 use crate::rect::Rect;
+use crate::SHOW_MAPGEN_ANIMATION;
 
 pub struct MapGenData {
     pub history: Vec<Map>,
+    pub connectivity: Option<ConnectivityReport>,
     pub index: usize,
     pub timer: f32,
+    pub paused: bool,
 }
 
+impl MapGenData {
+    pub fn new(history: Vec<Map>) -> MapGenData {
+        MapGenData {
+            history,
+            connectivity: None,
+            index: 0,
+            timer: 0.0,
+            paused: false,
+        }
+    }
+}
+
+/// Every builder the map gen viewer can show
+pub const VIEWER_BUILDERS: [&str; 16] = [
+    "Simple Map",
+    "BSP Dungeon",
+    "BSP Interior",
+    "BSP Farm",
+    "Cellular Automata",
+    "Drunkard's Bombing Run",
+    "Arena",
+    "Orc Halls",
+    "Village",
+    "Village World",
+    "Crossroads Level",
+    "Wave Function Collapse",
+    "Crossroads Collapse",
+    "Voronoi Districts",
+    "Random Dungeon",
+    "Roguelike",
+];
+
+const VIEWER_DUNGEON_SIZE: (i32, i32) = (80, 40);
+const VIEWER_VILLAGE_SIZE: (i32, i32) = (200, 80);
+const VIEWER_WORLD_SIZE: (i32, i32) = (300, 160);
+
 pub trait MapBuilder {
     fn build_map(&mut self);
     fn spawn_entities(&mut self, world: &mut World);
@@ -46,19 +121,162 @@ pub trait MapBuilder {
     fn get_starting_position(&mut self) -> Position;
     fn get_map_history(&self) -> Vec<Map>;
     fn take_snapshot(&mut self);
+
+    fn get_rooms(&self) -> Vec<Rect> {
+        Vec::new()
+    }
+
+    fn get_connectivity(&self) -> Option<ConnectivityReport> {
+        None
+    }
+}
+
+/// Everything a builder chain step can read and change
+pub struct BuildData {
+    pub map: Map,
+    pub starting_position: Option<Position>,
+    pub rooms: Option<Vec<Rect>>,
+    pub spawn_list: Vec<(usize, String)>,
+    pub history: Vec<Map>,
+    pub connectivity: Option<ConnectivityReport>,
+}
+
+impl BuildData {
+    pub fn take_snapshot(&mut self) {
+        if SHOW_MAPGEN_ANIMATION {
+            self.history.push(self.map.clone());
+        }
+    }
+}
+
+/// Lays down the first version of the map
+pub trait InitialMapBuilder {
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator, data: &mut BuildData);
+}
+
+/// Modifies a map that an earlier step built
+pub trait MetaMapBuilder {
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator, data: &mut BuildData);
+}
+
+/// Runs one initial builder followed by any number of meta builders
+pub struct BuilderChain {
+    starter: Option<Box<dyn InitialMapBuilder>>,
+    builders: Vec<Box<dyn MetaMapBuilder>>,
+    data: BuildData,
+}
+
+impl BuilderChain {
+    pub fn new(new_depth: i32, size: (i32, i32)) -> BuilderChain {
+        BuilderChain {
+            starter: None,
+            builders: Vec::new(),
+            data: BuildData {
+                map: Map::new(new_depth, TileType::Wall, size),
+                starting_position: None,
+                rooms: None,
+                spawn_list: Vec::new(),
+                history: Vec::new(),
+                connectivity: None,
+            },
+        }
+    }
+
+    pub fn start_with(mut self, starter: Box<dyn InitialMapBuilder>) -> BuilderChain {
+        match self.starter {
+            None => self.starter = Some(starter),
+            Some(_) => panic!("A builder chain can only have one starting builder"),
+        };
+        self
+    }
+
+    pub fn with(mut self, metabuilder: Box<dyn MetaMapBuilder>) -> BuilderChain {
+        self.builders.push(metabuilder);
+        self
+    }
+}
+
+impl MapBuilder for BuilderChain {
+    fn build_map(&mut self) {
+        let mut rng = RandomNumberGenerator::new();
+
+        match &mut self.starter {
+            None => panic!("Cannot run a builder chain without a starting builder"),
+            Some(starter) => starter.build_map(&mut rng, &mut self.data),
+        }
+
+        for metabuilder in self.builders.iter_mut() {
+            metabuilder.build_map(&mut rng, &mut self.data);
+        }
+    }
+
+    fn spawn_entities(&mut self, world: &mut World) {
+        world.run(|mut store: AllStoragesViewMut| {
+            for spawn in self.data.spawn_list.iter() {
+                entity_factory::spawn_entity(&mut store, spawn);
+            }
+        });
+    }
+
+    fn get_map(&mut self) -> Map {
+        self.data.map.clone()
+    }
+
+    fn get_starting_position(&mut self) -> Position {
+        match &self.data.starting_position {
+            Some(pos) => pos.clone(),
+            None => Position {
+                ps: vec![Point {
+                    x: self.data.map.width / 2,
+                    y: self.data.map.height / 2,
+                }],
+            },
+        }
+    }
+
+    fn get_map_history(&self) -> Vec<Map> {
+        self.data.history.clone()
+    }
+
+    fn take_snapshot(&mut self) {
+        self.data.take_snapshot();
+    }
+
+    fn get_rooms(&self) -> Vec<Rect> {
+        self.data.rooms.clone().unwrap_or_default()
+    }
+
+    fn get_connectivity(&self) -> Option<ConnectivityReport> {
+        self.data.connectivity.clone()
+    }
 }
 
 pub fn random_builder(new_depth: i32, size: (i32, i32)) -> Box<dyn MapBuilder> {
     let mut rng = rltk::RandomNumberGenerator::new();
     let builder = rng.roll_dice(1, 5);
-    match builder {
+    let starter: Box<dyn MapBuilder> = match builder {
         1 => Box::new(BspDungeonBuilder::new(new_depth, size)),
         2 => Box::new(BspInteriorBuilder::new(new_depth, size)),
         3 => Box::new(CellularAutomataBuilder::new(new_depth, size)),
         4 => Box::new(DrunkardsBombingRunBuilder::new(new_depth, size)),
         5 => Box::new(BspFarmBuilder::new(new_depth, size)),
         _ => Box::new(SimpleMapBuilder::new(new_depth, size)),
+    };
+
+    let mut chain = BuilderChain::new(new_depth, size).start_with(LegacyBuilder::new(starter));
+    if rng.roll_dice(1, 4) == 1 {
+        chain = chain.with(WaveFunctionCollapseBuilder::new());
     }
+
+    Box::new(
+        chain
+            .with(AreaStartingPosition::new(XStart::Center, YStart::Center))
+            .with(Connectivity::connect())
+            .with(DistantExit::new())
+            .with(DoorPlacement::new())
+            .with(PrefabBuilder::vaults())
+            .with(RegionSpawner::new()),
+    )
 }
 
 pub fn village_builder(new_depth: i32, size: (i32, i32)) -> Box<dyn MapBuilder> {
@@ -69,8 +287,94 @@ pub fn village_world_builder(new_depth: i32, size: (i32, i32)) -> Box<dyn MapBui
     Box::new(VillageWorldBuilder::new(new_depth, size))
 }
 
+fn districts_builder(new_depth: i32, size: (i32, i32)) -> Box<dyn MapBuilder> {
+    Box::new(
+        BuilderChain::new(new_depth, size)
+            .start_with(VoronoiBuilder::new())
+            .with(AreaStartingPosition::new(XStart::Center, YStart::Center))
+            .with(Connectivity::connect())
+            .with(DistantExit::new())
+            .with(DoorPlacement::new())
+            .with(PrefabBuilder::vaults()),
+    )
+}
+
+// regrows a sample map into a new layout
+fn wfc_builder(new_depth: i32, size: (i32, i32), wfc: Box<WaveFunctionCollapseBuilder>) -> Box<dyn MapBuilder> {
+    Box::new(
+        BuilderChain::new(new_depth, size)
+            .start_with(wfc)
+            .with(AreaStartingPosition::new(XStart::Center, YStart::Center))
+            .with(Connectivity::connect())
+            .with(DistantExit::new())
+            .with(DoorPlacement::new())
+            .with(RegionSpawner::new()),
+    )
+}
+
+fn crossroads_builder(new_depth: i32, size: (i32, i32)) -> Box<dyn MapBuilder> {
+    Box::new(
+        BuilderChain::new(new_depth, size)
+            .start_with(PrefabBuilder::level(CROSSROADS_LEVEL))
+            .with(Connectivity::cull())
+            .with(PrefabBuilder::vaults())
+            .with(RegionSpawner::new()),
+    )
+}
+
 pub fn rl_builder(new_depth: i32, size: (i32, i32)) -> Box<dyn MapBuilder> {
-    Box::new(DrunkardsBombingRunBuilder::new(new_depth, size))
+    let mut rng = RandomNumberGenerator::new();
+
+    // now and then the whole level is a hand drawn one, or split into districts
+    match rng.roll_dice(1, 8) {
+        1 => return crossroads_builder(new_depth, size),
+        2 => return districts_builder(new_depth, size),
+        _ => {}
+    }
+
+    let starter = Box::new(DrunkardsBombingRunBuilder::new(new_depth, size));
+    let mut chain = BuilderChain::new(new_depth, size).start_with(LegacyBuilder::new(starter));
+    if rng.roll_dice(1, 3) == 1 {
+        chain = chain.with(PrefabBuilder::sectional(ORC_FORT));
+    }
+
+    Box::new(
+        chain
+            .with(Connectivity::connect())
+            .with(DistantExit::new())
+            .with(DoorPlacement::new())
+            .with(PrefabBuilder::vaults())
+            .with(RegionSpawner::new()),
+    )
+}
+
+/// Makes one of the `VIEWER_BUILDERS` by name, at a size that suits it
+pub fn viewer_builder(name: &str, new_depth: i32) -> Box<dyn MapBuilder> {
+    let size = VIEWER_DUNGEON_SIZE;
+
+    match name {
+        "Simple Map" => Box::new(SimpleMapBuilder::new(new_depth, size)),
+        "BSP Dungeon" => Box::new(BspDungeonBuilder::new(new_depth, size)),
+        "BSP Interior" => Box::new(BspInteriorBuilder::new(new_depth, size)),
+        "BSP Farm" => Box::new(BspFarmBuilder::new(new_depth, size)),
+        "Cellular Automata" => Box::new(CellularAutomataBuilder::new(new_depth, size)),
+        "Drunkard's Bombing Run" => Box::new(DrunkardsBombingRunBuilder::new(new_depth, size)),
+        "Arena" => arena_builder(new_depth, size),
+        "Orc Halls" => orc_halls_builder(new_depth, size),
+        "Village" => village_builder(new_depth, VIEWER_VILLAGE_SIZE),
+        "Village World" => village_world_builder(new_depth, VIEWER_WORLD_SIZE),
+        "Crossroads Level" => crossroads_builder(new_depth, size),
+        "Wave Function Collapse" => wfc_builder(new_depth, size, WaveFunctionCollapseBuilder::new()),
+        "Crossroads Collapse" => wfc_builder(
+            new_depth,
+            size,
+            WaveFunctionCollapseBuilder::with_sample(PrefabBuilder::level(CROSSROADS_LEVEL)),
+        ),
+        "Voronoi Districts" => districts_builder(new_depth, size),
+        "Random Dungeon" => random_builder(new_depth, size),
+        "Roguelike" => rl_builder(new_depth, size),
+        _ => unreachable!(),
+    }
 }
 
 pub fn arena_builder(new_depth: i32, size: (i32, i32)) -> Box<dyn MapBuilder> {
@@ -78,5 +382,55 @@ pub fn arena_builder(new_depth: i32, size: (i32, i32)) -> Box<dyn MapBuilder> {
 }
 
 pub fn orc_halls_builder(new_depth: i32, size: (i32, i32)) -> Box<dyn MapBuilder> {
-    Box::new(BspDungeonBuilder::new(new_depth, size))
+    Box::new(OrcHallsBuilder::new(new_depth, size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the builders roll their own rng, so each one is built a few times to cover different layouts
+    const RUNS: usize = 5;
+
+    // surface maps and the arena are left without a way down
+    const NO_STAIRS: [&str; 3] = ["Arena", "Village", "Village World"];
+
+    #[test]
+    fn every_open_tile_is_reachable_from_the_start() {
+        load_prefabs();
+
+        for name in VIEWER_BUILDERS.iter() {
+            for run in 0..RUNS {
+                let mut builder = viewer_builder(name, 1);
+                builder.build_map();
+
+                let map = builder.get_map();
+                let start = map.point_idx(builder.get_starting_position().any_point());
+                let dist = walk_distances(&map, start);
+                for idx in 0..map.tiles.len() {
+                    assert!(
+                        map.blocks_movement(idx) || dist[idx] != i32::MAX,
+                        "{} run {}: {:?} can't be reached from the start",
+                        name,
+                        run,
+                        map.idx_point(idx)
+                    );
+                }
+
+                let report = builder
+                    .get_connectivity()
+                    .expect("every builder checks its connectivity");
+                assert_eq!(report.reachable_tiles, report.open_tiles, "{} run {}", name, run);
+
+                if !NO_STAIRS.contains(name) {
+                    assert!(
+                        (0..map.tiles.len()).any(|idx| map.tiles[idx] == TileType::StairsDown && dist[idx] != i32::MAX),
+                        "{} run {}: no stairs down can be reached from the start",
+                        name,
+                        run
+                    );
+                }
+            }
+        }
+    }
 }
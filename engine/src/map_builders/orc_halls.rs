@@ -0,0 +1,98 @@
+use rltk::{Point, RandomNumberGenerator};
+use shipyard::{AllStoragesViewMut, World};
+
+use crate::entity_factory;
+
+use super::{bsp_dungeon::BspDungeonBuilder, ConnectivityReport, Map, MapBuilder, Position, Rect, TileType};
+
+const MIN_SQUAD_SIZE: i32 = 2;
+const MAX_SQUAD_SIZE: i32 = 5;
+
+// Same halls as the bsp dungeon, but orcs spawn in squads instead of one at a time
+pub struct OrcHallsBuilder {
+    dungeon: BspDungeonBuilder,
+    depth: i32,
+}
+
+impl MapBuilder for OrcHallsBuilder {
+    fn get_map(&mut self) -> Map {
+        self.dungeon.get_map()
+    }
+
+    fn get_starting_position(&mut self) -> Position {
+        self.dungeon.get_starting_position()
+    }
+
+    fn build_map(&mut self) {
+        self.dungeon.build_map()
+    }
+
+    fn spawn_entities(&mut self, world: &mut World) {
+        let map = self.dungeon.get_map();
+        let start = self.dungeon.get_starting_position().any_point();
+        let rooms = self.dungeon.get_rooms();
+        let depth = self.depth;
+
+        world.run(|mut store: AllStoragesViewMut| {
+            let mut rng = RandomNumberGenerator::new();
+
+            for (i, room) in rooms.iter().enumerate() {
+                if start.x >= room.x1 && start.x < room.x2 && start.y >= room.y1 && start.y < room.y2 {
+                    continue;
+                }
+
+                // every other room holds a squad, the rest get the usual mix of items and stragglers
+                if i % 2 == 1 {
+                    entity_factory::spawn_room(&mut store, &map, room, depth);
+                    continue;
+                }
+
+                let mut free: Vec<Point> = vec![];
+                for y in room.y1 + 1..room.y2 {
+                    for x in room.x1 + 1..room.x2 {
+                        if map.tiles[map.xy_idx(x, y)] == TileType::Floor {
+                            free.push(Point { x, y });
+                        }
+                    }
+                }
+
+                let size = rng.range(MIN_SQUAD_SIZE, MAX_SQUAD_SIZE + 1) + depth / 3;
+                let mut points = vec![];
+                for _ in 0..size {
+                    if free.is_empty() {
+                        break;
+                    }
+                    let idx = rng.range(0, free.len());
+                    points.push(free.remove(idx));
+                }
+
+                entity_factory::orc_squad(&mut store, &points);
+            }
+        });
+    }
+
+    fn get_map_history(&self) -> Vec<Map> {
+        self.dungeon.get_map_history()
+    }
+
+    fn take_snapshot(&mut self) {
+        self.dungeon.take_snapshot()
+    }
+
+    fn get_rooms(&self) -> Vec<Rect> {
+        self.dungeon.get_rooms()
+    }
+
+    fn get_connectivity(&self) -> Option<ConnectivityReport> {
+        self.dungeon.get_connectivity()
+    }
+}
+
+impl OrcHallsBuilder {
+    pub fn new(new_depth: i32, size: (i32, i32)) -> OrcHallsBuilder {
+        OrcHallsBuilder {
+            dungeon: BspDungeonBuilder::new(new_depth, size),
+            depth: new_depth,
+        }
+    }
+}
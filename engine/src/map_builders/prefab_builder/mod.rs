@@ -0,0 +1,350 @@
+use rltk::{rex::XpFile, Point, RandomNumberGenerator};
+
+use super::{common::walk_distances, BuildData, InitialMapBuilder, MetaMapBuilder, Position, TileType};
+
+mod prefabs;
+pub use prefabs::*;
+
+/// Where a prefab's drawing comes from, see `template_tile` and `template_spawn` for what each character means
+#[derive(Clone, Copy)]
+pub enum PrefabTemplate {
+    /// Rows of legend characters written out in code
+    Rows(&'static [&'static str]),
+    /// A REX Paint file registered by `load_prefabs`
+    Rex(&'static str),
+}
+
+/// A room sized prefab dropped somewhere it fits, turned and flipped at random
+pub struct PrefabVault {
+    pub template: PrefabTemplate,
+    pub first_depth: i32,
+    pub last_depth: i32,
+}
+
+#[allow(dead_code)]
+#[derive(Clone, Copy)]
+pub enum HorizontalPlacement {
+    Left,
+    Center,
+    Right,
+}
+
+#[allow(dead_code)]
+#[derive(Clone, Copy)]
+pub enum VerticalPlacement {
+    Top,
+    Center,
+    Bottom,
+}
+
+/// A prefab pinned to one side of the map, stamped over whatever is there
+#[derive(Clone, Copy)]
+pub struct PrefabSection {
+    pub template: PrefabTemplate,
+    pub placement: (HorizontalPlacement, VerticalPlacement),
+}
+
+pub enum PrefabMode {
+    Level { template: PrefabTemplate },
+    Vaults,
+    Sectional { section: PrefabSection },
+}
+
+// how many spots we try before giving up on fitting a vault in
+const PLACEMENT_ATTEMPTS: usize = 50;
+
+/// Stamps premade pieces onto the map, or lays down a whole premade level
+pub struct PrefabBuilder {
+    mode: PrefabMode,
+}
+
+impl InitialMapBuilder for PrefabBuilder {
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator, data: &mut BuildData) {
+        self.build(rng, data);
+    }
+}
+
+impl MetaMapBuilder for PrefabBuilder {
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator, data: &mut BuildData) {
+        self.build(rng, data);
+    }
+}
+
+impl PrefabBuilder {
+    pub fn level(template: PrefabTemplate) -> Box<PrefabBuilder> {
+        Box::new(PrefabBuilder {
+            mode: PrefabMode::Level { template },
+        })
+    }
+
+    pub fn vaults() -> Box<PrefabBuilder> {
+        Box::new(PrefabBuilder {
+            mode: PrefabMode::Vaults,
+        })
+    }
+
+    pub fn sectional(section: PrefabSection) -> Box<PrefabBuilder> {
+        Box::new(PrefabBuilder {
+            mode: PrefabMode::Sectional { section },
+        })
+    }
+
+    fn build(&mut self, rng: &mut RandomNumberGenerator, data: &mut BuildData) {
+        match self.mode {
+            PrefabMode::Level { template } => self.apply_level(data, template),
+            PrefabMode::Vaults => self.apply_vaults(rng, data),
+            PrefabMode::Sectional { section } => self.apply_section(data, section),
+        }
+    }
+
+    fn apply_level(&mut self, data: &mut BuildData, template: PrefabTemplate) {
+        let level = Template::load(template);
+
+        // anything the level doesn't cover is solid rock
+        data.map.tiles.update(|_, tile| *tile = TileType::Wall);
+        data.spawn_list.clear();
+
+        let width = i32::min(level.width, data.map.width);
+        let height = i32::min(level.height, data.map.height);
+        stamp(data, &level, 0, 0, width, height);
+        data.take_snapshot();
+    }
+
+    fn apply_vaults(&mut self, rng: &mut RandomNumberGenerator, data: &mut BuildData) {
+        let depth = data.map.depth;
+        let options: Vec<&PrefabVault> = VAULTS
+            .iter()
+            .filter(|v| depth >= v.first_depth && depth <= v.last_depth)
+            .collect();
+        if options.is_empty() {
+            return;
+        }
+
+        let mut vault = Template::load(options[rng.range(0, options.len())].template);
+        for _ in 0..rng.range(0, 4) {
+            vault = vault.rotated();
+        }
+        if rng.roll_dice(1, 2) == 1 {
+            vault = vault.mirrored();
+        }
+
+        if vault.width + 2 >= data.map.width || vault.height + 2 >= data.map.height {
+            return;
+        }
+
+        for _ in 0..PLACEMENT_ATTEMPTS {
+            let x = rng.range(1, data.map.width - vault.width - 1);
+            let y = rng.range(1, data.map.height - vault.height - 1);
+
+            if vault_fits(data, &vault, x, y) {
+                stamp(data, &vault, x, y, vault.width, vault.height);
+                data.take_snapshot();
+                return;
+            }
+        }
+    }
+
+    fn apply_section(&mut self, data: &mut BuildData, section: PrefabSection) {
+        let template = Template::load(section.template);
+
+        // keep the map's outer wall intact
+        let x = match section.placement.0 {
+            HorizontalPlacement::Left => 1,
+            HorizontalPlacement::Center => (data.map.width - template.width) / 2,
+            HorizontalPlacement::Right => data.map.width - template.width - 1,
+        };
+        let y = match section.placement.1 {
+            VerticalPlacement::Top => 1,
+            VerticalPlacement::Center => (data.map.height - template.height) / 2,
+            VerticalPlacement::Bottom => data.map.height - template.height - 1,
+        };
+        if x < 1 || y < 1 {
+            return;
+        }
+
+        // earlier spawns under the section are buried by it
+        let map = &data.map;
+        data.spawn_list.retain(|(idx, _)| {
+            let (sx, sy) = map.idx_xy(*idx);
+            let (tx, ty) = (sx - x, sy - y);
+            tx < 0 || ty < 0 || tx >= template.width || ty >= template.height || template.get(tx, ty) == ' '
+        });
+
+        stamp(data, &template, x, y, template.width, template.height);
+        data.take_snapshot();
+    }
+}
+
+/// A loaded prefab, ' ' cells leave the map underneath alone
+struct Template {
+    width: i32,
+    height: i32,
+    cells: Vec<char>,
+}
+
+impl Template {
+    fn load(template: PrefabTemplate) -> Template {
+        match template {
+            PrefabTemplate::Rows(rows) => Template {
+                width: rows[0].chars().count() as i32,
+                height: rows.len() as i32,
+                cells: rows.iter().flat_map(|row| row.chars()).collect(),
+            },
+            PrefabTemplate::Rex(path) => Template::from_rex(path),
+        }
+    }
+
+    // layers are stacked in order, REX Paint marks see-through cells with a magenta background
+    fn from_rex(path: &str) -> Template {
+        let xp = XpFile::from_resource(path).unwrap_or_else(|e| panic!("Could not load prefab {}: {}", path, e));
+        let width = xp.layers.iter().map(|layer| layer.width).max().unwrap_or(0);
+        let height = xp.layers.iter().map(|layer| layer.height).max().unwrap_or(0);
+
+        let mut cells = vec![' '; width * height];
+        for layer in xp.layers.iter() {
+            for y in 0..layer.height {
+                for x in 0..layer.width {
+                    if let Some(cell) = layer.get(x, y) {
+                        let transparent = cell.bg.r == 255 && cell.bg.g == 0 && cell.bg.b == 255;
+                        if !transparent {
+                            cells[y * width + x] = rltk::to_char(cell.ch as u8);
+                        }
+                    }
+                }
+            }
+        }
+
+        Template {
+            width: width as i32,
+            height: height as i32,
+            cells,
+        }
+    }
+
+    fn get(&self, x: i32, y: i32) -> char {
+        self.cells[(y * self.width + x) as usize]
+    }
+
+    // a quarter turn clockwise
+    fn rotated(&self) -> Template {
+        let mut cells = Vec::with_capacity(self.cells.len());
+        for y in 0..self.width {
+            for x in 0..self.height {
+                cells.push(self.get(y, self.height - 1 - x));
+            }
+        }
+
+        Template {
+            width: self.height,
+            height: self.width,
+            cells,
+        }
+    }
+
+    // flipped left to right
+    fn mirrored(&self) -> Template {
+        let mut cells = Vec::with_capacity(self.cells.len());
+        for y in 0..self.height {
+            for x in 0..self.width {
+                cells.push(self.get(self.width - 1 - x, y));
+            }
+        }
+
+        Template {
+            width: self.width,
+            height: self.height,
+            cells,
+        }
+    }
+}
+
+// the whole footprint has to be plain floor and stamping it can't cut the rest of the map off
+fn vault_fits(data: &BuildData, vault: &Template, x: i32, y: i32) -> bool {
+    let start = match &data.starting_position {
+        Some(pos) => data.map.point_idx(pos.any_point()),
+        None => return false,
+    };
+
+    let mut footprint: Vec<usize> = vec![];
+    let mut stamped = data.map.clone();
+    for ty in 0..vault.height {
+        for tx in 0..vault.width {
+            let ch = vault.get(tx, ty);
+            if ch == ' ' {
+                continue;
+            }
+
+            let idx = data.map.xy_idx(x + tx, y + ty);
+            if data.map.tiles[idx] != TileType::Floor
+                || idx == start
+                || data.spawn_list.iter().any(|(sidx, _)| *sidx == idx)
+            {
+                return false;
+            }
+
+            footprint.push(idx);
+            if let Some(tile) = template_tile(ch) {
+                stamped.tiles[idx] = tile;
+            }
+        }
+    }
+
+    let before = walk_distances(&data.map, start);
+    let after = walk_distances(&stamped, start);
+    (0..before.len()).all(|idx| {
+        let walled_in = footprint.contains(&idx) && stamped.blocks_movement(idx);
+        before[idx] == i32::MAX || after[idx] != i32::MAX || walled_in
+    })
+}
+
+// copies the top left width x height of a template onto the map at x, y
+fn stamp(data: &mut BuildData, template: &Template, x: i32, y: i32, width: i32, height: i32) {
+    for ty in 0..height {
+        for tx in 0..width {
+            let ch = template.get(tx, ty);
+            let (px, py) = (x + tx, y + ty);
+
+            if let Some(tile) = template_tile(ch) {
+                data.map.set_tile(px, py, tile);
+            }
+
+            if ch == '@' {
+                data.starting_position = Some(Position {
+                    ps: vec![Point { x: px, y: py }],
+                });
+            }
+
+            if let Some(name) = template_spawn(ch) {
+                let idx = data.map.xy_idx(px, py);
+                data.spawn_list.push((idx, name.to_string()));
+            }
+        }
+    }
+}
+
+fn template_tile(ch: char) -> Option<TileType> {
+    match ch {
+        ' ' => None,
+        '#' | '█' => Some(TileType::Wall),
+        '~' => Some(TileType::Water),
+        '=' => Some(TileType::Bridge),
+        ',' => Some(TileType::Sand),
+        '"' => Some(TileType::Grass),
+        '>' => Some(TileType::StairsDown),
+        _ => Some(TileType::Floor),
+    }
+}
+
+fn template_spawn(ch: char) -> Option<&'static str> {
+    match ch {
+        'g' => Some("Goblin"),
+        'o' => Some("Orc"),
+        'w' => Some("Wolf"),
+        '!' => Some("Health Potion"),
+        '/' => Some("Dagger"),
+        ')' => Some("Shield"),
+        '?' => Some("Magic Missile Scroll"),
+        '+' => Some("Door"),
+        _ => None,
+    }
+}
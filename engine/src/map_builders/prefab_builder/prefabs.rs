@@ -0,0 +1,65 @@
+use super::{HorizontalPlacement, PrefabSection, PrefabTemplate, PrefabVault, VerticalPlacement};
+
+// paths are relative to this file, the same string is used to look them up again
+rltk::embedded_resource!(SHRINE_XP, "../../../../resources/vault_shrine.xp");
+rltk::embedded_resource!(FORT_XP, "../../../../resources/section_fort.xp");
+rltk::embedded_resource!(CROSSROADS_XP, "../../../../resources/level_crossroads.xp");
+
+/// Registers the REX Paint prefabs with rltk, has to run before any map is built
+pub fn load_prefabs() {
+    rltk::link_resource!(SHRINE_XP, "../../../../resources/vault_shrine.xp");
+    rltk::link_resource!(FORT_XP, "../../../../resources/section_fort.xp");
+    rltk::link_resource!(CROSSROADS_XP, "../../../../resources/level_crossroads.xp");
+}
+
+pub const CROSSROADS_LEVEL: PrefabTemplate = PrefabTemplate::Rex("../../../../resources/level_crossroads.xp");
+
+pub const ORC_FORT: PrefabSection = PrefabSection {
+    template: PrefabTemplate::Rex("../../../../resources/section_fort.xp"),
+    placement: (HorizontalPlacement::Right, VerticalPlacement::Bottom),
+};
+
+const GOBLIN_GUARD_POST: PrefabVault = PrefabVault {
+    template: PrefabTemplate::Rows(&[
+        "#######", //
+        "#g...g#", //
+        "#..!..#", //
+        "###.###", //
+        "...+...",
+    ]),
+    first_depth: 0,
+    last_depth: 100,
+};
+
+const ARMORY: PrefabVault = PrefabVault {
+    template: PrefabTemplate::Rows(&[
+        ".......", //
+        ".#####.", //
+        ".#/.)#.", //
+        ".#.o.#.", //
+        ".##+##.", //
+        ".......",
+    ]),
+    first_depth: 2,
+    last_depth: 100,
+};
+
+const WOLF_LAIR: PrefabVault = PrefabVault {
+    template: PrefabTemplate::Rows(&[
+        "..#..", //
+        ".#w#.", //
+        "#w!w#", //
+        ".#.#.", //
+        ".....",
+    ]),
+    first_depth: 3,
+    last_depth: 100,
+};
+
+const SHRINE: PrefabVault = PrefabVault {
+    template: PrefabTemplate::Rex("../../../../resources/vault_shrine.xp"),
+    first_depth: 1,
+    last_depth: 100,
+};
+
+pub const VAULTS: [PrefabVault; 4] = [GOBLIN_GUARD_POST, ARMORY, WOLF_LAIR, SHRINE];
@@ -0,0 +1,53 @@
+use std::collections::HashMap;
+
+use rltk::RandomNumberGenerator;
+
+use crate::entity_factory;
+
+use super::{BuildData, MetaMapBuilder};
+
+/// Splits the open tiles into noise regions and rolls spawns for each of them
+pub struct RegionSpawner {}
+
+impl MetaMapBuilder for RegionSpawner {
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator, data: &mut BuildData) {
+        let start = data
+            .starting_position
+            .as_ref()
+            .map(|pos| data.map.point_idx(pos.any_point()));
+
+        let mut noise = rltk::FastNoise::seeded(rng.roll_dice(1, 65536) as u64);
+        noise.set_noise_type(rltk::NoiseType::Cellular);
+        noise.set_frequency(0.08);
+        noise.set_cellular_distance_function(rltk::CellularDistanceFunction::Manhattan);
+
+        let mut noise_areas: HashMap<i32, Vec<usize>> = HashMap::new();
+        for y in 1..data.map.height - 1 {
+            for x in 1..data.map.width - 1 {
+                let idx = data.map.xy_idx(x, y);
+                if data.map.blocks_movement(idx) || Some(idx) == start {
+                    continue;
+                }
+
+                // don't stack spawns on top of what earlier steps placed
+                if data.spawn_list.iter().any(|(sidx, _)| *sidx == idx) {
+                    continue;
+                }
+
+                let cell_value = (noise.get_noise(x as f32, y as f32) * 10240.0) as i32;
+                noise_areas.entry(cell_value).or_insert_with(Vec::new).push(idx);
+            }
+        }
+
+        for area in noise_areas.values() {
+            data.spawn_list
+                .extend(entity_factory::region_spawns(area, data.map.depth));
+        }
+    }
+}
+
+impl RegionSpawner {
+    pub fn new() -> Box<RegionSpawner> {
+        Box::new(RegionSpawner {})
+    }
+}
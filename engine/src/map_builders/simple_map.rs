@@ -4,7 +4,9 @@ use shipyard::{AllStoragesViewMut, World};
 use crate::{entity_factory, SHOW_MAPGEN_ANIMATION};
 
 use super::{
-    apply_horizontal_corridor, apply_room_to_map, apply_vertical_corridor, Map, MapBuilder, Position, Rect, TileType,
+    apply_horizontal_corridor, apply_room_to_map, apply_vertical_corridor,
+    connectivity::{ensure_connectivity, ConnectivityMode},
+    ConnectivityReport, Map, MapBuilder, Position, Rect, TileType,
 };
 
 pub struct SimpleMapBuilder {
@@ -13,6 +15,7 @@ pub struct SimpleMapBuilder {
     depth: i32,
     rooms: Vec<Rect>,
     history: Vec<Map>,
+    connectivity: Option<ConnectivityReport>,
 }
 
 impl MapBuilder for SimpleMapBuilder {
@@ -26,6 +29,10 @@ impl MapBuilder for SimpleMapBuilder {
 
     fn build_map(&mut self) {
         self.rooms_and_corridors(10, 4, 8);
+
+        let start = self.map.point_idx(self.starting_position.any_point());
+        self.connectivity = Some(ensure_connectivity(&mut self.map, start, &ConnectivityMode::Cull));
+        self.take_snapshot();
     }
 
     fn spawn_entities(&mut self, world: &mut World) {
@@ -45,6 +52,14 @@ impl MapBuilder for SimpleMapBuilder {
             self.history.push(self.map.clone());
         }
     }
+
+    fn get_rooms(&self) -> Vec<Rect> {
+        self.rooms.clone()
+    }
+
+    fn get_connectivity(&self) -> Option<ConnectivityReport> {
+        self.connectivity.clone()
+    }
 }
 
 impl SimpleMapBuilder {
@@ -57,6 +72,7 @@ impl SimpleMapBuilder {
             depth: new_depth,
             rooms: Vec::new(),
             history: Vec::new(),
+            connectivity: None,
         }
     }
 
@@ -0,0 +1,474 @@
+use std::collections::VecDeque;
+
+use rltk::{Point, RandomNumberGenerator};
+use shipyard::AllStoragesViewMut;
+
+use crate::{
+    components::{Faction, SpawnerType},
+    entity_factory,
+    utils::{dir_to_point, get_neighbors},
+    SHOW_MAPGEN_ANIMATION,
+};
+
+use super::{Map, Rect, TileType};
+
+// how much of the elevation comes from sloping down towards where the river flows
+const TILT: f32 = 0.5;
+const LAKE_LEVEL: f32 = 0.12;
+const RIVER_RADIUS: i32 = 2;
+// pulls the main river along its flow so it can't get stuck in a hollow
+const RIVER_PULL: f32 = 0.04;
+const TRIBUTARIES: i32 = 3;
+const TRIBUTARY_MIN_ELEVATION: f32 = 0.55;
+const POND_RADIUS: i32 = 3;
+const SHORE_LEVEL: f32 = 0.35;
+
+const FOREST_MOISTURE: f32 = 0.45;
+const MAX_TREE_CHANCE: f32 = 0.6;
+const FIELD_RANGE: i32 = 12; // wheat grows at most this far from water
+const FIELD_NOISE: f32 = 0.25;
+const FIELD_MAX_ELEVATION: f32 = 0.5;
+const FIELD_MAX_FOREST: f32 = 0.1;
+
+const FISH_SPAWNERS: usize = 3;
+const FISH_RATE: i32 = 3;
+const FISH_CAP: usize = 60;
+
+pub const VILLAGE_SIZE: (i32, i32) = (64, 30);
+const SITE_STEP: i32 = 4;
+const SITE_MARGIN: i32 = 6; // keeps sites off the map edge and apart from each other
+const WOOD_RANGE: i32 = 10;
+const STREET_ROW: i32 = 11;
+const LANE_COLUMN: i32 = 35;
+const BUCKETS: i32 = 6; // fewer than there are villagers, the rest pass them along
+
+/// Noise fields for an overworld map, indexed like the map's tiles and roughly within 0..1
+pub struct Terrain {
+    pub elevation: Vec<f32>,
+    pub moisture: Vec<f32>,
+    pub forest: Vec<f32>, // chance of a tree growing on the tile
+    pub water_distance: Vec<i32>,
+}
+
+impl Terrain {
+    pub fn empty() -> Terrain {
+        Terrain {
+            elevation: vec![],
+            moisture: vec![],
+            forest: vec![],
+            water_distance: vec![],
+        }
+    }
+}
+
+fn noise_field(map: &Map, rng: &mut RandomNumberGenerator, frequency: f32) -> Vec<f32> {
+    let mut noise = rltk::FastNoise::seeded(rng.roll_dice(1, 65536) as u64);
+    noise.set_noise_type(rltk::NoiseType::SimplexFractal);
+    noise.set_fractal_octaves(4);
+    noise.set_frequency(frequency);
+
+    (0..map.tiles.len())
+        .map(|idx| {
+            let p = map.idx_point(idx);
+            ((noise.get_noise(p.x as f32, p.y as f32) + 1.) / 2.).clamp(0., 1.)
+        })
+        .collect()
+}
+
+// how far along the flow a point is, 0 where the river enters and 1 where it leaves
+fn downstream(map: &Map, p: Point, flow: Point) -> f32 {
+    let fx = p.x as f32 / map.width as f32 - 0.5;
+    let fy = p.y as f32 / map.height as f32 - 0.5;
+    (fx * flow.x as f32 + fy * flow.y as f32) / (flow.x.abs() + flow.y.abs()) as f32 + 0.5
+}
+
+fn interior(map: &Map, p: Point) -> bool {
+    p.x > 0 && p.x < map.width - 1 && p.y > 0 && p.y < map.height - 1
+}
+
+fn flood(map: &mut Map, center: Point, radius: i32) {
+    for y in center.y - radius..=center.y + radius {
+        for x in center.x - radius..=center.x + radius {
+            let p = Point { x, y };
+            if interior(map, p) && rltk::DistanceAlg::Pythagoras.distance2d(center, p) <= radius as f32 {
+                let idx = map.point_idx(p);
+                map.tiles[idx] = TileType::Water;
+            }
+        }
+    }
+}
+
+// the ways a river can run across the map, along either axis
+const RIVER_FLOWS: [usize; 4] = [2, 4, 6, 8];
+
+/// Numpad direction for the main river to run off the map in
+pub fn random_river_flow(rng: &mut RandomNumberGenerator) -> usize {
+    RIVER_FLOWS[rng.range(0, RIVER_FLOWS.len() as i32) as usize]
+}
+
+/// Lays out grass, lakes and rivers from noise and returns the fields the rest of the overworld is grown from.
+/// The land slopes down towards `river_flow` so the main river crosses the whole map and leaves through a sink.
+/// Currents are left to the caller, to work out once it's done changing the water.
+pub fn generate_terrain(
+    map: &mut Map,
+    rng: &mut RandomNumberGenerator,
+    river_flow: usize,
+    history: &mut Vec<Map>,
+) -> Terrain {
+    let flow = dir_to_point(Point { x: 0, y: 0 }, river_flow, 1);
+
+    let noise = noise_field(map, rng, 0.02);
+    let elevation: Vec<f32> = noise
+        .iter()
+        .enumerate()
+        .map(|(idx, n)| n * (1. - TILT) + (1. - downstream(map, map.idx_point(idx), flow)) * TILT)
+        .collect();
+    let moisture = noise_field(map, rng, 0.03);
+
+    for idx in 0..map.tiles.len() {
+        if interior(map, map.idx_point(idx)) {
+            map.tiles[idx] = if elevation[idx] < LAKE_LEVEL {
+                TileType::Water
+            } else {
+                TileType::Grass
+            };
+        }
+    }
+    if SHOW_MAPGEN_ANIMATION {
+        history.push(map.clone());
+    }
+
+    // the main river enters somewhere along the upstream edge and runs downhill to the opposite one
+    let mut p = Point {
+        x: match flow.x {
+            1 => 1,
+            -1 => map.width - 2,
+            _ => rng.range(map.width / 4, map.width * 3 / 4),
+        },
+        y: match flow.y {
+            1 => 1,
+            -1 => map.height - 2,
+            _ => rng.range(map.height / 4, map.height * 3 / 4),
+        },
+    };
+    let mut visited = vec![false; map.tiles.len()];
+    loop {
+        flood(map, p, RIVER_RADIUS);
+        visited[map.point_idx(p)] = true;
+
+        if !interior(map, dir_to_point(p, river_flow, 1)) {
+            break;
+        }
+
+        // never step back upstream, so the river is sure to reach the sink edge
+        let next = get_neighbors(p)
+            .into_iter()
+            .filter(|n| interior(map, *n) && !visited[map.point_idx(*n)])
+            .filter(|n| (n.x - p.x) * flow.x + (n.y - p.y) * flow.y >= 0)
+            .map(|n| {
+                let progress = ((n.x - p.x) * flow.x + (n.y - p.y) * flow.y) as f32;
+                let jitter = rng.range(0, 100) as f32 / 10000.;
+                (n, elevation[map.point_idx(n)] - progress * RIVER_PULL + jitter)
+            })
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+
+        match next {
+            Some((n, _)) => p = n,
+            None => break,
+        }
+    }
+    if SHOW_MAPGEN_ANIMATION {
+        history.push(map.clone());
+    }
+
+    // streams start in the hills and run downhill until they join other water or pool in a hollow
+    for _ in 0..TRIBUTARIES {
+        let mut spring = None;
+        for _ in 0..100 {
+            let idx = rng.range(0, map.tiles.len() as i32) as usize;
+            if map.tiles[idx] == TileType::Grass && elevation[idx] > TRIBUTARY_MIN_ELEVATION {
+                spring = Some(map.idx_point(idx));
+                break;
+            }
+        }
+        let mut p = match spring {
+            Some(p) => p,
+            None => continue,
+        };
+
+        loop {
+            let idx = map.point_idx(p);
+            if map.tiles[idx] == TileType::Water {
+                break;
+            }
+            map.tiles[idx] = TileType::Water;
+
+            let lowest = get_neighbors(p)
+                .into_iter()
+                .filter(|n| interior(map, *n))
+                .min_by(|a, b| {
+                    elevation[map.point_idx(*a)]
+                        .partial_cmp(&elevation[map.point_idx(*b)])
+                        .unwrap()
+                });
+
+            match lowest {
+                Some(n) if elevation[map.point_idx(n)] < elevation[idx] => p = n,
+                _ => {
+                    flood(map, p, POND_RADIUS);
+                    break;
+                }
+            }
+        }
+    }
+    if SHOW_MAPGEN_ANIMATION {
+        history.push(map.clone());
+    }
+
+    // the edges of rivers and lakes are shallow enough to wade through
+    let shallows: Vec<usize> = (0..map.tiles.len())
+        .filter(|idx| map.tiles[*idx] == TileType::Water)
+        .filter(|idx| {
+            get_neighbors(map.idx_point(*idx)).iter().any(|n| {
+                let nidx = map.point_idx(*n);
+                interior(map, *n) && map.tiles[nidx] != TileType::Water
+            })
+        })
+        .collect();
+    for idx in shallows {
+        map.tiles[idx] = TileType::ShallowWater;
+    }
+
+    let water_distance = water_distances(map);
+
+    // low lying shores wash out into sand
+    for idx in 0..map.tiles.len() {
+        if map.tiles[idx] == TileType::Grass && water_distance[idx] == 1 && elevation[idx] < SHORE_LEVEL {
+            map.tiles[idx] = TileType::Sand;
+        }
+    }
+
+    // wetter land grows thicker woods
+    let forest: Vec<f32> = moisture
+        .iter()
+        .map(|m| ((m - FOREST_MOISTURE) / (1. - FOREST_MOISTURE)).clamp(0., 1.) * MAX_TREE_CHANCE)
+        .collect();
+
+    // wheat takes to low, well watered land that isn't already wooded
+    let fields = noise_field(map, rng, 0.08);
+    for idx in 0..map.tiles.len() {
+        let fertile = water_distance[idx] > 1
+            && water_distance[idx] <= FIELD_RANGE
+            && elevation[idx] < FIELD_MAX_ELEVATION
+            && forest[idx] < FIELD_MAX_FOREST
+            && fields[idx] > 1. - FIELD_NOISE;
+        if map.tiles[idx] == TileType::Grass && fertile {
+            map.tiles[idx] = TileType::Wheat;
+        }
+    }
+    if SHOW_MAPGEN_ANIMATION {
+        history.push(map.clone());
+    }
+
+    Terrain {
+        elevation,
+        moisture,
+        forest,
+        water_distance,
+    }
+}
+
+// steps from each tile to the nearest water, i32::MAX if there is none
+fn water_distances(map: &Map) -> Vec<i32> {
+    let mut dist = vec![i32::MAX; map.tiles.len()];
+    let mut queue = VecDeque::new();
+    for (idx, tile) in map.tiles.iter().enumerate() {
+        if map.is_swimmable(idx) && *tile != TileType::Bridge {
+            dist[idx] = 0;
+            queue.push_back(idx);
+        }
+    }
+
+    while let Some(idx) = queue.pop_front() {
+        for n in get_neighbors(map.idx_point(idx)) {
+            if !map.in_bounds(n.x, n.y) {
+                continue;
+            }
+
+            let nidx = map.point_idx(n);
+            if dist[nidx] == i32::MAX {
+                dist[nidx] = dist[idx] + 1;
+                queue.push_back(nidx);
+            }
+        }
+    }
+
+    dist
+}
+
+fn wood_around(map: &Map, terrain: &Terrain, x1: i32, y1: i32, x2: i32, y2: i32) -> f32 {
+    let mut wood = 0.;
+    for y in y1.max(0)..y2.min(map.height) {
+        for x in x1.max(0)..x2.min(map.width) {
+            wood += terrain.forest[map.xy_idx(x, y)];
+        }
+    }
+    wood
+}
+
+/// Finds dry land for a village as close to water and woods as it can, away from the `taken` sites.
+/// Clears any wheat off the chosen site.
+pub fn find_village_site(map: &mut Map, terrain: &Terrain, taken: &[Rect]) -> Option<Rect> {
+    let (w, h) = VILLAGE_SIZE;
+    let mut best: Option<(Rect, f32)> = None;
+
+    let mut y = SITE_MARGIN;
+    while y + h + SITE_MARGIN < map.height {
+        let mut x = SITE_MARGIN;
+        while x + w + SITE_MARGIN < map.width {
+            let site = Rect::new(x, y, w, h);
+            let grown = Rect::new(
+                x - SITE_MARGIN,
+                y - SITE_MARGIN,
+                w + 2 * SITE_MARGIN,
+                h + 2 * SITE_MARGIN,
+            );
+            x += SITE_STEP;
+
+            if taken.iter().any(|t| t.intersect(&grown)) {
+                continue;
+            }
+
+            let mut dry = true;
+            let mut to_water = i32::MAX;
+            for sy in site.y1..site.y2 {
+                for sx in site.x1..site.x2 {
+                    let idx = map.xy_idx(sx, sy);
+                    dry &= !map.is_swimmable(idx);
+                    to_water = to_water.min(terrain.water_distance[idx]);
+                }
+            }
+            if !dry || to_water == i32::MAX {
+                continue;
+            }
+
+            let wood = wood_around(
+                map,
+                terrain,
+                site.x1 - WOOD_RANGE,
+                site.y1 - WOOD_RANGE,
+                site.x2 + WOOD_RANGE,
+                site.y2 + WOOD_RANGE,
+            ) - wood_around(map, terrain, site.x1, site.y1, site.x2, site.y2);
+            let score = to_water as f32 - wood / WOOD_RANGE as f32;
+
+            let better = match &best {
+                Some((_, s)) => score < *s,
+                None => true,
+            };
+            if better {
+                best = Some((site, score));
+            }
+        }
+        y += SITE_STEP;
+    }
+
+    let (site, _) = best?;
+    clear_village_site(map, &site);
+
+    Some(site)
+}
+
+/// Grasses over a village site and lays its roads, for sites picked by `find_village_site` or anywhere else
+pub fn clear_village_site(map: &mut Map, site: &Rect) {
+    for y in site.y1..site.y2 {
+        for x in site.x1..site.x2 {
+            map.set_tile(x, y, TileType::Grass);
+        }
+    }
+
+    // dirt roads along the street in front of the big buildings and down between the rows of houses
+    for x in site.x1..site.x2 {
+        map.set_tile(x, site.y1 + STREET_ROW, TileType::Dirt);
+    }
+    for y in site.y1 + STREET_ROW..site.y2 {
+        map.set_tile(site.x1 + LANE_COLUMN, y, TileType::Dirt);
+    }
+}
+
+/// Grows trees on open grass by forest density, leaving the `clear` areas alone
+pub fn spawn_forest(
+    store: &mut AllStoragesViewMut,
+    map: &Map,
+    terrain: &Terrain,
+    rng: &mut RandomNumberGenerator,
+    clear: &dyn Fn(Point) -> bool,
+) {
+    for (idx, tile) in map.tiles.iter().enumerate() {
+        let p = map.idx_point(idx);
+        if *tile != TileType::Grass || clear(p) {
+            continue;
+        }
+
+        if (rng.roll_dice(1, 100) as f32) < terrain.forest[idx] * 100. {
+            entity_factory::tree(store, p.x, p.y);
+        }
+    }
+}
+
+/// Fish enter where the river does, spreads the spawners across its mouth
+pub fn spawn_fish(store: &mut AllStoragesViewMut, map: &Map) {
+    let sources: Vec<usize> = (0..map.tiles.len())
+        .filter(|idx| map.is_river_source(*idx, map.river_flow))
+        .collect();
+    if sources.is_empty() {
+        return;
+    }
+
+    for i in 0..FISH_SPAWNERS {
+        let p = map.idx_point(sources[(2 * i + 1) * sources.len() / (2 * FISH_SPAWNERS)]);
+        entity_factory::spawner(store, p.x, p.y, Faction::Nature, SpawnerType::Fish, FISH_RATE, FISH_CAP);
+    }
+}
+
+/// Builds a village on a site from `find_village_site`, the fish cleaner on the side nearest the water and the
+/// lumber mill on the other
+pub fn spawn_village(store: &mut AllStoragesViewMut, map: &Map, terrain: &Terrain, site: &Rect) {
+    let side_water = |x: i32| {
+        (site.y1..site.y2)
+            .map(|y| terrain.water_distance[map.xy_idx(x, y)])
+            .min()
+            .unwrap_or(i32::MAX)
+    };
+    let water_west = side_water(site.x1) <= side_water(site.x2 - 1);
+
+    let (cleaner_x, mill_x) = if water_west {
+        (site.x1 + 2, site.x2 - 10)
+    } else {
+        (site.x2 - 7, site.x1 + 2)
+    };
+
+    entity_factory::fish_cleaner(store, cleaner_x, site.y1 + 2, 5, 5);
+    entity_factory::lumber_mill(store, mill_x, site.y1 + 2, 8, 8);
+    entity_factory::chief_house(store, site.x1 + site.width() / 2 - 10, site.y1 + 2, 20, 8);
+
+    for row in 0..2 {
+        for i in 0..5 {
+            entity_factory::plank_house(store, site.x1 + 6 + 11 * i, site.y1 + 14 + 8 * row, 4, 4);
+        }
+    }
+
+    // the village's buckets wait by the fish cleaner, nearest the water, for whoever gets to a fire first
+    for i in 0..BUCKETS {
+        entity_factory::bucket(store, cleaner_x + i, site.y1 + STREET_ROW - 1);
+    }
+
+    // torches along the main street keep it lit at night
+    for i in 0..5 {
+        entity_factory::torch(store, site.x1 + 10 + 11 * i, site.y1 + STREET_ROW - 1);
+    }
+
+    for i in 0..20 {
+        entity_factory::villager(store, site.x1 + 4 + 2 * i, site.y1 + 12);
+    }
+}
@@ -3,17 +3,34 @@ use shipyard::{AllStoragesViewMut, World};
 
 use crate::{
     components::{Faction, SpawnerType},
-    entity_factory, SHOW_MAPGEN_ANIMATION,
+    entity_factory,
+    utils::get_neighbors,
+    SHOW_MAPGEN_ANIMATION,
 };
 
-use super::{Map, MapBuilder, Position, TileType};
+use super::{
+    connectivity::{ensure_connectivity, ConnectivityMode},
+    terrain::{self, Terrain},
+    ConnectivityReport, Map, MapBuilder, Position, Rect, TileType,
+};
 
 pub struct VillageBuilder {
     map: Map,
     starting_position: Position,
     history: Vec<Map>,
+    connectivity: Option<ConnectivityReport>,
+    terrain: Terrain,
+    site: Rect,
 }
 
+const CLEARING_RADIUS: f32 = 3.0;
+const CLEARING_DISTANCE: i32 = 20;
+const WOLF_PACK_SIZE: usize = 3;
+const DEER_RATE: i32 = 100;
+const DEER_CAP: usize = 12;
+const RABBIT_RATE: i32 = 40;
+const RABBIT_CAP: usize = 20;
+
 impl MapBuilder for VillageBuilder {
     fn get_map(&mut self) -> Map {
         self.map.clone()
@@ -23,41 +40,75 @@ impl MapBuilder for VillageBuilder {
         self.starting_position.clone()
     }
     fn build_map(&mut self) {
-        self.build()
+        self.build();
+
+        let start = self.map.point_idx(self.starting_position.any_point());
+        self.connectivity = Some(ensure_connectivity(&mut self.map, start, &ConnectivityMode::Connect));
+        self.take_snapshot();
     }
 
     fn spawn_entities(&mut self, world: &mut World) {
         let mut rng = RandomNumberGenerator::new();
+        let map = &self.map;
+        let terrain = &self.terrain;
+        let site = self.site;
+
+        // wildlife keeps away from the village, wolves den deep in the hills and deer graze open meadows
+        let mut clearings: Vec<Point> = vec![];
+        let den = self.pick_clearing(&clearings, |idx| terrain.forest[idx] + terrain.elevation[idx]);
+        clearings.extend(den);
+        let meadow = self.pick_clearing(&clearings, |idx| terrain.moisture[idx] - terrain.forest[idx]);
+        clearings.extend(meadow);
+        let warren = self.pick_clearing(&clearings, |idx| 1. - terrain.forest[idx]);
+        clearings.extend(warren);
+
         world.run(|mut store: AllStoragesViewMut| {
-            for y in 1..self.map.height / 2 {
-                for x in 1..self.map.width - 1 {
-                    let roll = rng.roll_dice(1, 100);
-                    if roll < 35 {
-                        entity_factory::tree(&mut store, x, y);
-                    }
-                }
+            let clear = |p: Point| {
+                near_site(&site, p, 2)
+                    || clearings
+                        .iter()
+                        .any(|c| rltk::DistanceAlg::Pythagoras.distance2d(*c, p) <= CLEARING_RADIUS)
+            };
+            terrain::spawn_forest(&mut store, map, terrain, &mut rng, &clear);
+
+            terrain::spawn_fish(&mut store, map);
+
+            if let Some(den) = den {
+                let pack: Vec<Point> = open_neighbors(map, den).into_iter().take(WOLF_PACK_SIZE).collect();
+                entity_factory::wolf_den(&mut store, den.x, den.y, &pack);
             }
 
-            entity_factory::spawner(
-                &mut store,
-                1,
-                self.map.height - 7,
-                Faction::Nature,
-                SpawnerType::Fish,
-                1,
-            );
-
-            for i in 1..=10 {
-                entity_factory::plank_house(&mut store, 20 + 10 * i, self.map.height - 14, 4, 4);
+            if let Some(meadow) = meadow {
+                entity_factory::spawner(
+                    &mut store,
+                    meadow.x,
+                    meadow.y,
+                    Faction::Nature,
+                    SpawnerType::Deer,
+                    DEER_RATE,
+                    DEER_CAP,
+                );
+                for p in open_neighbors(map, meadow).into_iter().take(DEER_CAP / 2) {
+                    entity_factory::deer(&mut store, p.x, p.y);
+                }
             }
 
-            entity_factory::chief_house(&mut store, 40, self.map.height - 27, 20, 8);
-            entity_factory::lumber_mill(&mut store, 20, self.map.height - 27, 8, 8);
-            entity_factory::fish_cleaner(&mut store, 10, self.map.height - 17, 5, 5);
-
-            for i in 0..20 {
-                entity_factory::villager(&mut store, 15, self.map.height - 25 - i);
+            if let Some(warren) = warren {
+                entity_factory::spawner(
+                    &mut store,
+                    warren.x,
+                    warren.y,
+                    Faction::Nature,
+                    SpawnerType::Rabbit,
+                    RABBIT_RATE,
+                    RABBIT_CAP,
+                );
+                for p in open_neighbors(map, warren).into_iter().take(RABBIT_CAP / 3) {
+                    entity_factory::rabbit(&mut store, p.x, p.y);
+                }
             }
+
+            terrain::spawn_village(&mut store, map, terrain, &site);
         });
     }
 
@@ -70,6 +121,10 @@ impl MapBuilder for VillageBuilder {
             self.history.push(self.map.clone());
         }
     }
+
+    fn get_connectivity(&self) -> Option<ConnectivityReport> {
+        self.connectivity.clone()
+    }
 }
 
 impl VillageBuilder {
@@ -80,30 +135,71 @@ impl VillageBuilder {
                 ps: vec![Point { x: 0, y: 0 }],
             },
             history: Vec::new(),
+            connectivity: None,
+            terrain: Terrain::empty(),
+            site: Rect::new(0, 0, 0, 0),
         }
     }
 
     fn build(&mut self) {
-        // Set the map to grass with a river
-        for y in 1..self.map.height - 1 {
-            for x in 1..self.map.width - 1 {
-                let idx = self.map.xy_idx(x, y);
-
-                if y > self.map.height - 10 && y < self.map.height - 3 {
-                    self.map.tiles[idx] = TileType::Water;
-                } else {
-                    self.map.tiles[idx] = TileType::Grass;
-                }
+        let mut rng = RandomNumberGenerator::new();
+
+        let mut history = vec![];
+        let river_flow = terrain::random_river_flow(&mut rng);
+        self.terrain = terrain::generate_terrain(&mut self.map, &mut rng, river_flow, &mut history);
+        self.history.append(&mut history);
+
+        // fall back on the middle of the map if there's no dry land big enough
+        let (w, h) = terrain::VILLAGE_SIZE;
+        self.site = match terrain::find_village_site(&mut self.map, &self.terrain, &[]) {
+            Some(site) => site,
+            None => {
+                let site = Rect::new((self.map.width - w) / 2, (self.map.height - h) / 2, w, h);
+                terrain::clear_village_site(&mut self.map, &site);
+                site
             }
-        }
+        };
+        self.map.generate_currents(river_flow);
 
         self.take_snapshot();
 
+        let (x, y) = self.site.center();
         self.starting_position = Position {
-            ps: vec![Point {
-                x: self.map.width / 2,
-                y: self.map.height / 2,
-            }],
+            ps: vec![Point { x, y }],
         };
     }
+
+    // best scoring dry grass away from the village and the other clearings, if there's any left
+    fn pick_clearing(&self, avoid: &[Point], score: impl Fn(usize) -> f32) -> Option<Point> {
+        let mut best: Option<(Point, f32)> = None;
+        for (idx, tile) in self.map.tiles.iter().enumerate() {
+            let p = self.map.idx_point(idx);
+            if *tile != TileType::Grass
+                || near_site(&self.site, p, CLEARING_DISTANCE)
+                || avoid
+                    .iter()
+                    .any(|a| rltk::DistanceAlg::Pythagoras.distance2d(*a, p) < CLEARING_DISTANCE as f32)
+            {
+                continue;
+            }
+
+            let s = score(idx);
+            if best.map_or(true, |(_, b)| s > b) {
+                best = Some((p, s));
+            }
+        }
+
+        best.map(|(p, _)| p)
+    }
+}
+
+fn near_site(site: &Rect, p: Point, range: i32) -> bool {
+    p.x >= site.x1 - range && p.x < site.x2 + range && p.y >= site.y1 - range && p.y < site.y2 + range
+}
+
+fn open_neighbors(map: &Map, p: Point) -> Vec<Point> {
+    get_neighbors(p)
+        .into_iter()
+        .filter(|n| map.in_bounds(n.x, n.y) && !map.blocks_movement(map.point_idx(*n)))
+        .collect()
 }
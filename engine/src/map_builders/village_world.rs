@@ -1,16 +1,25 @@
 use rltk::{Point, RandomNumberGenerator};
 use shipyard::{AllStoragesViewMut, World};
 
-use crate::{entity_factory, SHOW_MAPGEN_ANIMATION};
+use crate::SHOW_MAPGEN_ANIMATION;
 
-use super::{Map, MapBuilder, Position, TileType};
+use super::{
+    connectivity::{ensure_connectivity, ConnectivityMode},
+    terrain::{self, Terrain},
+    ConnectivityReport, Map, MapBuilder, Position, Rect, TileType,
+};
 
 pub struct VillageWorldBuilder {
     map: Map,
     starting_position: Position,
     history: Vec<Map>,
+    connectivity: Option<ConnectivityReport>,
+    terrain: Terrain,
+    sites: Vec<Rect>,
 }
 
+const VILLAGE_AREA: (i32, i32) = (150, 80);
+
 impl MapBuilder for VillageWorldBuilder {
     fn get_map(&mut self) -> Map {
         self.map.clone()
@@ -20,51 +29,32 @@ impl MapBuilder for VillageWorldBuilder {
         self.starting_position.clone()
     }
     fn build_map(&mut self) {
-        self.build()
+        self.build();
+
+        let start = self.map.point_idx(self.starting_position.any_point());
+        self.connectivity = Some(ensure_connectivity(&mut self.map, start, &ConnectivityMode::Connect));
+        self.take_snapshot();
     }
 
     fn spawn_entities(&mut self, world: &mut World) {
         let mut rng = RandomNumberGenerator::new();
-
-        let mut used_idx = vec![];
-
-        for _ in 0..100 {
-            let x = rng.roll_dice(1, self.map.width - 1);
-            let y = rng.roll_dice(1, self.map.height - 1);
-            let idx = self.map.xy_idx(x, y);
-            if !self.map.is_wall(x, y) && self.map.tiles[idx] != TileType::Water && !used_idx.contains(&idx) {
-                used_idx.push(idx);
-                world.run(|mut store: AllStoragesViewMut| {
-                    entity_factory::villager(&mut store, x, y);
-                });
+        let map = &self.map;
+        let terrain = &self.terrain;
+        let sites = &self.sites;
+
+        world.run(|mut store: AllStoragesViewMut| {
+            let clear = |p: Point| {
+                sites
+                    .iter()
+                    .any(|s| p.x >= s.x1 - 2 && p.x < s.x2 + 2 && p.y >= s.y1 - 2 && p.y < s.y2 + 2)
+            };
+            terrain::spawn_forest(&mut store, map, terrain, &mut rng, &clear);
+            terrain::spawn_fish(&mut store, map);
+
+            for site in sites.iter() {
+                terrain::spawn_village(&mut store, map, terrain, site);
             }
-        }
-
-        // world.run(|mut store: AllStoragesViewMut|{
-
-        //     for y in 1..self.map.height/2 {
-        //         for x in 1..self.map.width-1 {
-        //             let roll = rng.roll_dice(1, 100);
-        //             if roll < 35 {
-        //                 entity_factory::tree(&mut store, x, y);
-        //             }
-        //         }
-        //     }
-
-        //     entity_factory::spawner(&mut store, 1, self.map.height - 7, 0, SpawnerType::Fish, 1);
-
-        //     for i in 1..=10 {
-        //         entity_factory::plank_house(&mut store, 20 + 10 * i, self.map.height - 14, 4, 4);
-        //     }
-
-        //     entity_factory::chief_house(&mut store, 40, self.map.height - 27, 20, 8);
-        //     entity_factory::lumber_mill(&mut store, 20, self.map.height - 27, 8, 8);
-        //     entity_factory::fish_cleaner(&mut store, 10, self.map.height - 17, 5, 5);
-
-        //     for i in 0..20{
-        //         entity_factory::villager(&mut store, 15, self.map.height - 25 - i);
-        //     }
-        // });
+        });
     }
 
     fn get_map_history(&self) -> Vec<Map> {
@@ -76,6 +66,10 @@ impl MapBuilder for VillageWorldBuilder {
             self.history.push(self.map.clone());
         }
     }
+
+    fn get_connectivity(&self) -> Option<ConnectivityReport> {
+        self.connectivity.clone()
+    }
 }
 
 impl VillageWorldBuilder {
@@ -86,73 +80,38 @@ impl VillageWorldBuilder {
                 ps: vec![Point { x: 0, y: 0 }],
             },
             history: Vec::new(),
+            connectivity: None,
+            terrain: Terrain::empty(),
+            sites: Vec::new(),
         }
     }
 
     fn build(&mut self) {
-        // Set the map to grass
-        for y in 1..self.map.height - 1 {
-            for x in 1..self.map.width - 1 {
-                let idx = self.map.xy_idx(x, y);
-                self.map.tiles[idx] = TileType::Grass;
-            }
-        }
-
-        self.take_snapshot();
-
-        let villsize = (150, 80);
-
-        // let numvillhori = self.map.width / villsize.0;
-        // let numvillvert = self.map.height / villsize.1;
-
-        let mut x = 0;
-        let mut y = 0;
-        while x <= self.map.width - villsize.0 {
-            // todo test with width multiple of villwidth
-            while y <= self.map.height - villsize.1 {
-                let mut villbuilder = super::village_builder(0, villsize);
-                villbuilder.build_map();
-
-                let map = villbuilder.get_map();
-
-                for i in 0..map.tiles.len() {
-                    let pos = map.idx_point(i);
-                    let targetpos = Point {
-                        x: x + pos.x,
-                        y: y + pos.y,
-                    };
-                    let targetposidx = self.map.point_idx(targetpos);
-                    self.map.tiles[targetposidx] = map.tiles[i];
-                }
-
-                y += villsize.1;
+        let mut rng = RandomNumberGenerator::new();
 
-                self.take_snapshot();
+        let mut history = vec![];
+        let river_flow = terrain::random_river_flow(&mut rng);
+        self.terrain = terrain::generate_terrain(&mut self.map, &mut rng, river_flow, &mut history);
+        self.history.append(&mut history);
+
+        // roughly one village for every stretch of land a single village map would cover
+        let villages = (self.map.width / VILLAGE_AREA.0).max(1) * (self.map.height / VILLAGE_AREA.1).max(1);
+        for _ in 0..villages {
+            match terrain::find_village_site(&mut self.map, &self.terrain, &self.sites) {
+                Some(site) => self.sites.push(site),
+                None => break,
             }
-
-            x += villsize.0;
         }
-
-        // // Set the map to grass with a river
-        // for y in 1..self.map.height-1 {
-        //     for x in 1..self.map.width-1 {
-        //         let idx = self.map.xy_idx(x, y);
-
-        //         if y > self.map.height - 10 && y < self.map.height - 3 {
-        //             self.map.tiles[idx] = TileType::Water;
-        //         } else {
-        //             self.map.tiles[idx] = TileType::Grass;
-        //         }
-        //     }
-        // }
+        self.map.generate_currents(river_flow);
 
         self.take_snapshot();
 
+        let (x, y) = match self.sites.first() {
+            Some(site) => site.center(),
+            None => (self.map.width / 2, self.map.height / 2),
+        };
         self.starting_position = Position {
-            ps: vec![Point {
-                x: self.map.width / 2,
-                y: self.map.height / 2,
-            }],
+            ps: vec![Point { x, y }],
         };
     }
 }
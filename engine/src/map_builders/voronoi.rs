@@ -0,0 +1,261 @@
+use rltk::{Point, RandomNumberGenerator};
+
+use crate::{entity_factory, weighted_table::WeightedTable};
+
+use super::{BuildData, InitialMapBuilder, TileType};
+
+const MIN_DISTRICTS: i32 = 8;
+const MAX_DISTRICTS: i32 = 14;
+
+// chance out of 100 that a cave tile starts out as rock before smoothing
+const CAVE_FILL: i32 = 45;
+const CAVE_SMOOTHING: usize = 4;
+const FLOOD_LEVEL: f32 = 0.2;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum DistrictTheme {
+    Crypt,
+    Cavern,
+    Flooded,
+    Barracks,
+}
+
+const THEMES: [DistrictTheme; 4] = [
+    DistrictTheme::Crypt,
+    DistrictTheme::Cavern,
+    DistrictTheme::Flooded,
+    DistrictTheme::Barracks,
+];
+
+// tells apart districts that would otherwise share a name, one for every district there can be so they never run out
+const QUALIFIERS: [&str; MAX_DISTRICTS as usize] = [
+    "upper",
+    "lower",
+    "inner",
+    "outer",
+    "old",
+    "new",
+    "deep",
+    "lesser",
+    "greater",
+    "forgotten",
+    "silent",
+    "sunken",
+    "broken",
+    "hidden",
+];
+
+impl DistrictTheme {
+    fn noun(self) -> &'static str {
+        match self {
+            DistrictTheme::Crypt => "crypt",
+            DistrictTheme::Cavern => "cavern",
+            DistrictTheme::Flooded => "flooded cavern",
+            DistrictTheme::Barracks => "barracks",
+        }
+    }
+
+    fn is_cave(self) -> bool {
+        self == DistrictTheme::Cavern || self == DistrictTheme::Flooded
+    }
+
+    fn spawn_table(self, depth: i32) -> WeightedTable {
+        match self {
+            DistrictTheme::Crypt => WeightedTable::new()
+                .add("Goblin", 10)
+                .add("Magic Missile Scroll", 4)
+                .add("Confusion Scroll", 2 + depth)
+                .add("Health Potion", 3),
+            DistrictTheme::Cavern => WeightedTable::new()
+                .add("Wolf", 12)
+                .add("Goblin", 4)
+                .add("Health Potion", 5),
+            DistrictTheme::Flooded => WeightedTable::new()
+                .add("Wolf", 3)
+                .add("Health Potion", 8)
+                .add("Fireball Scroll", 2 + depth),
+            DistrictTheme::Barracks => WeightedTable::new()
+                .add("Orc", 8 + depth)
+                .add("Dagger", 4)
+                .add("Shield", 4)
+                .add("Longsword", depth)
+                .add("Tower Shield", depth - 1),
+        }
+    }
+}
+
+/// Splits the map into voronoi districts, each walled off from the others and built and stocked to a theme
+pub struct VoronoiBuilder {}
+
+impl InitialMapBuilder for VoronoiBuilder {
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator, data: &mut BuildData) {
+        let (width, height) = (data.map.width, data.map.height);
+
+        let count = rng.range(MIN_DISTRICTS, MAX_DISTRICTS + 1);
+        let seeds: Vec<Point> = (0..count)
+            .map(|_| Point {
+                x: rng.range(2, width - 2),
+                y: rng.range(2, height - 2),
+            })
+            .collect();
+
+        // names are drawn without replacement, themes that would repeat a name are passed over while any are left
+        let mut themes: Vec<DistrictTheme> = vec![];
+        let mut names: Vec<String> = vec![];
+        let mut qualifiers: Vec<&str> = QUALIFIERS.to_vec();
+        for seed in seeds.iter() {
+            let direction = compass(*seed, width, height);
+            let fresh: Vec<DistrictTheme> = THEMES
+                .iter()
+                .copied()
+                .filter(|t| !names.contains(&district_name(direction.as_deref(), None, *t)))
+                .collect();
+
+            let (theme, qualifier) = if fresh.is_empty() {
+                let qualifier = qualifiers.remove(rng.range(0, qualifiers.len()));
+                (THEMES[rng.range(0, THEMES.len())], Some(qualifier))
+            } else {
+                (fresh[rng.range(0, fresh.len())], None)
+            };
+
+            themes.push(theme);
+            names.push(district_name(direction.as_deref(), qualifier, theme));
+        }
+
+        data.map.regions.update(|idx, region| {
+            let p = Point::new(idx as i32 % width, idx as i32 / width);
+            *region = (0..seeds.len()).min_by_key(|s| {
+                let dist = rltk::DistanceAlg::PythagorasSquared.distance2d(p, seeds[*s]);
+                dist as i32
+            });
+        });
+        data.map.region_names = names;
+
+        // districts are walled off from each other, connecting them is left to later steps
+        for y in 0..height {
+            for x in 0..width {
+                let idx = data.map.xy_idx(x, y);
+                let edge = x == 0 || y == 0 || x == width - 1 || y == height - 1;
+                let border = !edge
+                    && [(x - 1, y), (x + 1, y), (x, y - 1), (x, y + 1)]
+                        .iter()
+                        .any(|(nx, ny)| data.map.regions[data.map.xy_idx(*nx, *ny)] != data.map.regions[idx]);
+
+                data.map.tiles[idx] = if edge || border {
+                    TileType::Wall
+                } else {
+                    match themes[data.map.regions[idx].unwrap()] {
+                        DistrictTheme::Crypt if x % 3 == 0 && y % 3 == 0 => TileType::Wall,
+                        DistrictTheme::Cavern | DistrictTheme::Flooded if rng.roll_dice(1, 100) <= CAVE_FILL => {
+                            TileType::Wall
+                        }
+                        _ => TileType::Floor,
+                    }
+                };
+            }
+        }
+        data.take_snapshot();
+
+        // smooth the caves out, leaving the walls between districts alone
+        for _ in 0..CAVE_SMOOTHING {
+            let mut tiles = data.map.tiles.clone();
+            for y in 1..height - 1 {
+                for x in 1..width - 1 {
+                    let idx = data.map.xy_idx(x, y);
+                    let region = data.map.regions[idx].unwrap();
+                    if !themes[region].is_cave() {
+                        continue;
+                    }
+
+                    let mut walls = 0;
+                    let mut border = false;
+                    for (dx, dy) in [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)].iter() {
+                        let nidx = data.map.xy_idx(x + dx, y + dy);
+                        if data.map.tiles[nidx] == TileType::Wall {
+                            walls += 1;
+                        }
+                        if dx.abs() + dy.abs() == 1 && data.map.regions[nidx] != Some(region) {
+                            border = true;
+                        }
+                    }
+
+                    if !border {
+                        tiles[idx] = if walls > 4 || walls == 0 {
+                            TileType::Wall
+                        } else {
+                            TileType::Floor
+                        };
+                    }
+                }
+            }
+            data.map.tiles = tiles;
+            data.take_snapshot();
+        }
+
+        let mut noise = rltk::FastNoise::seeded(rng.roll_dice(1, 65536) as u64);
+        noise.set_noise_type(rltk::NoiseType::Simplex);
+        noise.set_frequency(0.1);
+        let regions = &data.map.regions;
+        data.map.tiles.update(|idx, tile| {
+            let (x, y) = (idx as i32 % width, idx as i32 / width);
+            let flooded = regions[idx].map_or(false, |r| themes[r] == DistrictTheme::Flooded);
+            if flooded && *tile == TileType::Floor && noise.get_noise(x as f32, y as f32) > FLOOD_LEVEL {
+                *tile = TileType::Water;
+            }
+        });
+        data.take_snapshot();
+
+        // stock each district from its own table
+        let depth = data.map.depth;
+        for (region, theme) in themes.iter().enumerate() {
+            let area: Vec<usize> = (0..data.map.tiles.len())
+                .filter(|idx| data.map.regions[*idx] == Some(region) && !data.map.blocks_movement(*idx))
+                .collect();
+            if !area.is_empty() {
+                let spawns = entity_factory::region_spawns_from(&area, depth, &theme.spawn_table(depth));
+                data.spawn_list.extend(spawns);
+            }
+        }
+    }
+}
+
+impl VoronoiBuilder {
+    pub fn new() -> Box<VoronoiBuilder> {
+        Box::new(VoronoiBuilder {})
+    }
+}
+
+// the words go qualifier, direction, theme, as in "the sunken northern crypt"
+fn district_name(direction: Option<&str>, qualifier: Option<&str>, theme: DistrictTheme) -> String {
+    let words: Vec<&str> = [qualifier, direction, Some(theme.noun())]
+        .iter()
+        .flatten()
+        .copied()
+        .collect();
+    format!("the {}", words.join(" "))
+}
+
+// which part of the map a point is in, by thirds
+fn compass(p: Point, width: i32, height: i32) -> Option<String> {
+    let ns = if p.y < height / 3 {
+        "north"
+    } else if p.y >= height * 2 / 3 {
+        "south"
+    } else {
+        ""
+    };
+    let ew = if p.x < width / 3 {
+        "west"
+    } else if p.x >= width * 2 / 3 {
+        "east"
+    } else {
+        ""
+    };
+
+    match (ns.is_empty(), ew.is_empty()) {
+        (true, true) => None,
+        (false, false) => Some(format!("{}-{}ern", ns, ew)),
+        (false, true) => Some(format!("{}ern", ns)),
+        (true, false) => Some(format!("{}ern", ew)),
+    }
+}
@@ -0,0 +1,357 @@
+use std::collections::VecDeque;
+
+use rltk::RandomNumberGenerator;
+
+use crate::SHOW_MAPGEN_ANIMATION;
+
+use super::{
+    cellular_automata::CellularAutomataBuilder, legacy::LegacyBuilder, BuildData, InitialMapBuilder, Map,
+    MetaMapBuilder, TileType,
+};
+
+// output is stitched together from squares of this many tiles cut out of the sample
+const CHUNK_SIZE: i32 = 6;
+const MAX_BACKTRACKS: usize = 500;
+const MAX_RESTARTS: usize = 5;
+const SNAPSHOT_EVERY: usize = 4;
+
+// north, east, south, west
+const DIRECTIONS: [(i32, i32); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+fn opposite(dir: usize) -> usize {
+    (dir + 2) % 4
+}
+
+/// A chunk of the sample, how often it turned up and the ids of its four edges
+struct Pattern {
+    tiles: Vec<TileType>,
+    weight: usize,
+    edges: [usize; 4],
+}
+
+/// Learns which chunks of the previous map can sit next to each other and grows a new map out of them.
+/// Starting a chain it builds its own sample first, a cave unless it was given something else to learn from.
+pub struct WaveFunctionCollapseBuilder {
+    sample: Option<Box<dyn InitialMapBuilder>>,
+}
+
+impl MetaMapBuilder for WaveFunctionCollapseBuilder {
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator, data: &mut BuildData) {
+        regrow(rng, data);
+    }
+}
+
+impl InitialMapBuilder for WaveFunctionCollapseBuilder {
+    fn build_map(&mut self, rng: &mut RandomNumberGenerator, data: &mut BuildData) {
+        let size = (data.map.width, data.map.height);
+        let mut sample = match self.sample.take() {
+            Some(sample) => sample,
+            None => LegacyBuilder::new(Box::new(CellularAutomataBuilder::new(data.map.depth, size))),
+        };
+
+        sample.build_map(rng, data);
+        regrow(rng, data);
+    }
+}
+
+impl WaveFunctionCollapseBuilder {
+    pub fn new() -> Box<WaveFunctionCollapseBuilder> {
+        Box::new(WaveFunctionCollapseBuilder { sample: None })
+    }
+
+    /// Starts a chain by regrowing whatever `sample` builds
+    pub fn with_sample(sample: Box<dyn InitialMapBuilder>) -> Box<WaveFunctionCollapseBuilder> {
+        Box::new(WaveFunctionCollapseBuilder { sample: Some(sample) })
+    }
+}
+
+fn regrow(rng: &mut RandomNumberGenerator, data: &mut BuildData) {
+    let patterns = learn_patterns(&data.map);
+    if patterns.is_empty() {
+        return;
+    }
+
+    let grid = (data.map.width / CHUNK_SIZE, data.map.height / CHUNK_SIZE);
+    for _ in 0..MAX_RESTARTS {
+        if let Some(wave) = collapse(rng, data, &patterns, grid) {
+            write_wave(&mut data.map, &patterns, &wave, grid);
+
+            // the old layout's rooms, start and spawns don't mean anything anymore
+            data.rooms = None;
+            data.starting_position = None;
+            data.spawn_list.clear();
+            data.take_snapshot();
+            return;
+        }
+    }
+
+    // every attempt failed, the sample is still untouched and its rooms, start and spawns still fit it.
+    // the last frame goes back to it so the animation doesn't end on a half grown map
+    data.take_snapshot();
+}
+
+fn learn_patterns(sample: &Map) -> Vec<Pattern> {
+    let mut chunks: Vec<(Vec<TileType>, usize)> = vec![];
+
+    let mut add = |tiles: Vec<TileType>| match chunks.iter_mut().find(|(t, _)| *t == tiles) {
+        Some(chunk) => chunk.1 += 1,
+        None => chunks.push((tiles, 1)),
+    };
+
+    for cy in 0..sample.height / CHUNK_SIZE {
+        for cx in 0..sample.width / CHUNK_SIZE {
+            let mut tiles = Vec::with_capacity((CHUNK_SIZE * CHUNK_SIZE) as usize);
+            for y in 0..CHUNK_SIZE {
+                for x in 0..CHUNK_SIZE {
+                    tiles.push(sample.tiles[sample.xy_idx(cx * CHUNK_SIZE + x, cy * CHUNK_SIZE + y)]);
+                }
+            }
+
+            // every turn and flip of a chunk is fair game
+            for _ in 0..4 {
+                add(mirrored(&tiles));
+                tiles = rotated(&tiles);
+                add(tiles.clone());
+            }
+        }
+    }
+
+    let mut edges: Vec<Vec<TileType>> = vec![];
+    let mut edge_id = |edge: Vec<TileType>| -> usize {
+        match edges.iter().position(|e| *e == edge) {
+            Some(id) => id,
+            None => {
+                edges.push(edge);
+                edges.len() - 1
+            }
+        }
+    };
+
+    chunks
+        .into_iter()
+        .map(|(tiles, weight)| {
+            let edges = [
+                edge_id(chunk_edge(&tiles, 0)),
+                edge_id(chunk_edge(&tiles, 1)),
+                edge_id(chunk_edge(&tiles, 2)),
+                edge_id(chunk_edge(&tiles, 3)),
+            ];
+            Pattern { tiles, weight, edges }
+        })
+        .collect()
+}
+
+fn chunk_tile(tiles: &[TileType], x: i32, y: i32) -> TileType {
+    tiles[(y * CHUNK_SIZE + x) as usize]
+}
+
+// edges run left to right or top to bottom so that touching edges of neighbors compare equal
+fn chunk_edge(tiles: &[TileType], dir: usize) -> Vec<TileType> {
+    let last = CHUNK_SIZE - 1;
+    (0..CHUNK_SIZE)
+        .map(|i| match dir {
+            0 => chunk_tile(tiles, i, 0),
+            1 => chunk_tile(tiles, last, i),
+            2 => chunk_tile(tiles, i, last),
+            _ => chunk_tile(tiles, 0, i),
+        })
+        .collect()
+}
+
+// a quarter turn clockwise
+fn rotated(tiles: &[TileType]) -> Vec<TileType> {
+    let mut out = Vec::with_capacity(tiles.len());
+    for y in 0..CHUNK_SIZE {
+        for x in 0..CHUNK_SIZE {
+            out.push(chunk_tile(tiles, y, CHUNK_SIZE - 1 - x));
+        }
+    }
+    out
+}
+
+fn mirrored(tiles: &[TileType]) -> Vec<TileType> {
+    let mut out = Vec::with_capacity(tiles.len());
+    for y in 0..CHUNK_SIZE {
+        for x in 0..CHUNK_SIZE {
+            out.push(chunk_tile(tiles, CHUNK_SIZE - 1 - x, y));
+        }
+    }
+    out
+}
+
+fn neighbor(cell: usize, dir: usize, grid: (i32, i32)) -> Option<usize> {
+    let (x, y) = (cell as i32 % grid.0, cell as i32 / grid.0);
+    let (nx, ny) = (x + DIRECTIONS[dir].0, y + DIRECTIONS[dir].1);
+    if nx < 0 || ny < 0 || nx >= grid.0 || ny >= grid.1 {
+        None
+    } else {
+        Some((ny * grid.0 + nx) as usize)
+    }
+}
+
+// narrows down neighbors until nothing changes, false if some cell ran out of options
+fn propagate(wave: &mut [Vec<bool>], patterns: &[Pattern], grid: (i32, i32), start: Vec<usize>) -> bool {
+    let mut queue: VecDeque<usize> = start.into_iter().collect();
+
+    while let Some(cell) = queue.pop_front() {
+        for dir in 0..4 {
+            let n = match neighbor(cell, dir, grid) {
+                Some(n) => n,
+                None => continue,
+            };
+
+            let open_edges: Vec<usize> = (0..patterns.len())
+                .filter(|p| wave[cell][*p])
+                .map(|p| patterns[p].edges[dir])
+                .collect();
+
+            let mut changed = false;
+            for q in 0..patterns.len() {
+                if wave[n][q] && !open_edges.contains(&patterns[q].edges[opposite(dir)]) {
+                    wave[n][q] = false;
+                    changed = true;
+                }
+            }
+
+            if changed {
+                if !wave[n].iter().any(|o| *o) {
+                    return false;
+                }
+                queue.push_back(n);
+            }
+        }
+    }
+
+    true
+}
+
+fn collapse(
+    rng: &mut RandomNumberGenerator,
+    data: &mut BuildData,
+    patterns: &[Pattern],
+    grid: (i32, i32),
+) -> Option<Vec<Vec<bool>>> {
+    let cells = (grid.0 * grid.1) as usize;
+    let mut wave = vec![vec![true; patterns.len()]; cells];
+
+    // keep the map walled in where the sample has chunks that allow it
+    let walled: Vec<[bool; 4]> = patterns
+        .iter()
+        .map(|p| {
+            let mut sides = [false; 4];
+            for (dir, side) in sides.iter_mut().enumerate() {
+                *side = chunk_edge(&p.tiles, dir).iter().all(|t| *t == TileType::Wall);
+            }
+            sides
+        })
+        .collect();
+    for (cell, options) in wave.iter_mut().enumerate() {
+        for dir in 0..4 {
+            if neighbor(cell, dir, grid).is_some() {
+                continue;
+            }
+
+            if walled.iter().zip(options.iter()).any(|(w, o)| w[dir] && *o) {
+                for (o, w) in options.iter_mut().zip(walled.iter()) {
+                    *o = *o && w[dir];
+                }
+            }
+        }
+    }
+    if !propagate(&mut wave, patterns, grid, (0..cells).collect()) {
+        return None;
+    }
+
+    let mut stack: Vec<(Vec<Vec<bool>>, usize, usize)> = vec![];
+    let mut backtracks = 0;
+    let mut collapsed = 0;
+
+    loop {
+        // the undecided cell with the fewest options left, ties broken at random
+        let counts: Vec<usize> = wave.iter().map(|o| o.iter().filter(|x| **x).count()).collect();
+        let fewest = match counts.iter().filter(|c| **c > 1).min() {
+            Some(fewest) => *fewest,
+            None => return Some(wave),
+        };
+        let candidates: Vec<usize> = (0..cells).filter(|c| counts[*c] == fewest).collect();
+        let cell = candidates[rng.range(0, candidates.len())];
+
+        let total: usize = (0..patterns.len())
+            .filter(|p| wave[cell][*p])
+            .map(|p| patterns[p].weight)
+            .sum();
+        let mut roll = rng.range(0, total as i32) as usize;
+        let mut choice = 0;
+        for p in 0..patterns.len() {
+            if !wave[cell][p] {
+                continue;
+            }
+            if roll < patterns[p].weight {
+                choice = p;
+                break;
+            }
+            roll -= patterns[p].weight;
+        }
+
+        stack.push((wave.clone(), cell, choice));
+        for (p, option) in wave[cell].iter_mut().enumerate() {
+            *option = p == choice;
+        }
+
+        // on a contradiction go back to before the last choice and rule it out
+        let mut ok = propagate(&mut wave, patterns, grid, vec![cell]);
+        while !ok {
+            backtracks += 1;
+            if backtracks > MAX_BACKTRACKS {
+                return None;
+            }
+
+            let (saved, cell, choice) = stack.pop()?;
+            wave = saved;
+            wave[cell][choice] = false;
+            ok = wave[cell].iter().any(|o| *o) && propagate(&mut wave, patterns, grid, vec![cell]);
+        }
+
+        // snapshots are drawn on a copy, the sample has to survive in case this attempt fails
+        collapsed += 1;
+        if SHOW_MAPGEN_ANIMATION && collapsed % SNAPSHOT_EVERY == 0 {
+            let mut snapshot = data.map.clone();
+            write_wave(&mut snapshot, patterns, &wave, grid);
+            data.history.push(snapshot);
+        }
+    }
+}
+
+// decided cells get their chunk, everything else is left as wall
+fn write_wave(map: &mut Map, patterns: &[Pattern], wave: &[Vec<bool>], grid: (i32, i32)) {
+    map.tiles.update(|_, tile| *tile = TileType::Wall);
+
+    for (cell, options) in wave.iter().enumerate() {
+        let mut live = options.iter().enumerate().filter(|(_, o)| **o);
+        let pattern = match (live.next(), live.next()) {
+            (Some((p, _)), None) => &patterns[p],
+            _ => continue,
+        };
+
+        let (cx, cy) = (cell as i32 % grid.0, cell as i32 / grid.0);
+        for y in 0..CHUNK_SIZE {
+            for x in 0..CHUNK_SIZE {
+                map.set_tile(
+                    cx * CHUNK_SIZE + x,
+                    cy * CHUNK_SIZE + y,
+                    chunk_tile(&pattern.tiles, x, y),
+                );
+            }
+        }
+    }
+
+    // nothing gets out past the edge of the map
+    for x in 0..map.width {
+        map.set_tile(x, 0, TileType::Wall);
+        map.set_tile(x, map.height - 1, TileType::Wall);
+    }
+    for y in 0..map.height {
+        map.set_tile(0, y, TileType::Wall);
+        map.set_tile(map.width - 1, y, TileType::Wall);
+    }
+}
@@ -74,6 +74,42 @@ impl Palette {
         b: 0.82,
         a: 1.,
     };
+    pub const COLOR_WATER_DEEP: RGBA = RGBA {
+        r: 0.0,
+        g: 0.0,
+        b: 0.574,
+        a: 1.,
+    };
+    pub const COLOR_SHALLOW_WATER: RGBA = RGBA {
+        r: 0.3,
+        g: 0.5,
+        b: 0.9,
+        a: 1.,
+    };
+    pub const COLOR_SHALLOW_WATER_DARK: RGBA = RGBA {
+        r: 0.1,
+        g: 0.2,
+        b: 0.6,
+        a: 1.,
+    };
+    pub const COLOR_SAND: RGBA = RGBA {
+        r: 0.86,
+        g: 0.78,
+        b: 0.5,
+        a: 1.,
+    };
+    pub const COLOR_SAND_DARK: RGBA = RGBA {
+        r: 0.3,
+        g: 0.26,
+        b: 0.14,
+        a: 1.,
+    };
+    pub const COLOR_RUBBLE: RGBA = RGBA {
+        r: 0.4,
+        g: 0.36,
+        b: 0.32,
+        a: 1.,
+    };
     pub const COLOR_FIRE: RGBA = RGBA {
         r: 0.88,
         g: 0.34,
@@ -106,4 +142,33 @@ impl Palette {
             a: 1.,
         },
     ];
+
+    /// Looks up a color by its constant name, so data files can refer to the palette
+    pub fn named(name: &str) -> Option<RGBA> {
+        let color = match name {
+            "MAIN_BG" => Palette::MAIN_BG,
+            "MAIN_FG" => Palette::MAIN_FG,
+            "COLOR_PURPLE" => Palette::COLOR_PURPLE,
+            "COLOR_RED" => Palette::COLOR_RED,
+            "COLOR_GREEN" => Palette::COLOR_GREEN,
+            "COLOR_GREEN_DARK" => Palette::COLOR_GREEN_DARK,
+            "COLOR_3" => Palette::COLOR_3,
+            "COLOR_4" => Palette::COLOR_4,
+            "COLOR_AMBER" => Palette::COLOR_AMBER,
+            "COLOR_WOOD" => Palette::COLOR_WOOD,
+            "COLOR_DIRT" => Palette::COLOR_DIRT,
+            "COLOR_WATER" => Palette::COLOR_WATER,
+            "COLOR_WATER_DEEP" => Palette::COLOR_WATER_DEEP,
+            "COLOR_SHALLOW_WATER" => Palette::COLOR_SHALLOW_WATER,
+            "COLOR_SHALLOW_WATER_DARK" => Palette::COLOR_SHALLOW_WATER_DARK,
+            "COLOR_SAND" => Palette::COLOR_SAND,
+            "COLOR_SAND_DARK" => Palette::COLOR_SAND_DARK,
+            "COLOR_RUBBLE" => Palette::COLOR_RUBBLE,
+            "COLOR_FIRE" => Palette::COLOR_FIRE,
+            "COLOR_CEDAR" => Palette::COLOR_CEDAR,
+            "COLOR_CLEAR" => Palette::COLOR_CLEAR,
+            _ => return None,
+        };
+        Some(color)
+    }
 }
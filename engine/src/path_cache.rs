@@ -0,0 +1,192 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use rltk::{DijkstraMap, NavigationPath, Point};
+use shipyard::Unique;
+
+use crate::components::LocomotionType;
+use crate::map::Map;
+use crate::utils::get_footprint_path;
+
+// cached results only hear about changed tiles and fixed blockers, anything older than this is rebuilt to get around
+// fires and crowds
+const MAX_AGE: i32 = 20;
+
+// how many paths to keep per goal, villagers heading the same way share them
+const PATHS_PER_GOAL: usize = 8;
+
+/// How often queries were answered from the cache, for profiling big village sims
+#[derive(Default, Clone, Copy, Debug)]
+pub struct PathStats {
+    pub path_hits: u32,
+    pub path_misses: u32,
+    pub dijkstra_hits: u32,
+    pub dijkstra_misses: u32,
+    pub invalidated: u32,
+}
+
+struct CachedPath {
+    steps: Vec<usize>,
+    chunks: HashSet<usize>,
+    turn: i32,
+}
+
+struct CachedDijkstra {
+    map: Arc<DijkstraMap>,
+    max_depth: f32,
+    chunks: HashSet<usize>,
+    turn: i32,
+}
+
+/// Shared A* paths and Dijkstra maps, kept until the map says the chunks under them changed.
+/// Paths are keyed by goal, body shape and way of moving so anyone standing on a cached path picks it up from there,
+/// Dijkstra maps are keyed by their set of starts.
+#[derive(Default, Unique)]
+pub struct PathCache {
+    chunk_count: usize,
+    turn: i32,
+    paths: HashMap<(usize, Vec<Point>, LocomotionType), Vec<CachedPath>>,
+    dijkstras: HashMap<Vec<usize>, CachedDijkstra>,
+    pub stats: PathStats,
+}
+
+impl PathCache {
+    /// Drops everything built over chunks the map has marked dirty since the last refresh, or that is too old.
+    /// Run once a turn before anything queries the cache.
+    pub fn refresh(&mut self, map: &mut Map, turn: i32) {
+        self.turn = turn;
+
+        // a different sized map means a new level, nothing carries over
+        let chunk_count = map.tiles.chunk_count();
+        if chunk_count != self.chunk_count {
+            self.chunk_count = chunk_count;
+            self.paths.clear();
+            self.dijkstras.clear();
+        }
+        let dirty = map.take_dirty_chunks();
+        self.invalidate(|chunks, built| turn - built > MAX_AGE || !chunks.is_disjoint(&dirty));
+    }
+
+    /// Drops everything built over any of `chunks` right away, for changes that can't wait for the next refresh
+    pub fn invalidate_chunks(&mut self, chunks: &HashSet<usize>) {
+        self.invalidate(|built_over, _| !built_over.is_disjoint(chunks));
+    }
+
+    fn invalidate(&mut self, stale: impl Fn(&HashSet<usize>, i32) -> bool) {
+        let mut invalidated = 0;
+        for paths in self.paths.values_mut() {
+            let before = paths.len();
+            paths.retain(|path| !stale(&path.chunks, path.turn));
+            invalidated += before - paths.len();
+        }
+        self.paths.retain(|_, paths| !paths.is_empty());
+
+        let before = self.dijkstras.len();
+        self.dijkstras
+            .retain(|_, dijkstra| !stale(&dijkstra.chunks, dijkstra.turn));
+        invalidated += before - self.dijkstras.len();
+
+        self.stats.invalidated += invalidated as u32;
+    }
+
+    /// A path for a body from where it stands to `to`, reusing a cached one if the body is already on it.
+    /// Like `get_footprint_path`, the steps are for the first point of the body and there's none without a body.
+    pub fn path(&mut self, map: &Map, body: &[Point], to: Point, mtype: LocomotionType) -> Option<NavigationPath> {
+        let first = *body.first()?;
+        let from = map.point_idx(first);
+        let goal = map.point_idx(to);
+        let shape: Vec<Point> = body.iter().map(|p| Point::new(p.x - first.x, p.y - first.y)).collect();
+        let key = (goal, shape, mtype);
+
+        if let Some(paths) = self.paths.get(&key) {
+            for cached in paths.iter() {
+                if let Some(i) = cached.steps.iter().position(|step| *step == from) {
+                    // something may have walked onto the path since, the goal itself is allowed to be occupied.
+                    // bigger bodies block their own path so theirs are trusted until the next refresh
+                    let next = cached.steps.get(i + 1);
+                    if body.len() == 1 && next.map_or(false, |next| *next != goal && map.blocked[*next]) {
+                        continue;
+                    }
+
+                    self.stats.path_hits += 1;
+                    let mut path = NavigationPath::new();
+                    path.destination = goal;
+                    path.success = true;
+                    path.steps = cached.steps[i..].to_vec();
+                    return Some(path);
+                }
+            }
+        }
+
+        self.stats.path_misses += 1;
+        let path = get_footprint_path(map, body, to, mtype)?;
+        if path.success && path.steps.len() > 1 {
+            let cached = CachedPath {
+                steps: path.steps.clone(),
+                chunks: path_chunks(map, &path.steps),
+                turn: self.turn,
+            };
+
+            let paths = self.paths.entry(key).or_insert_with(Vec::new);
+            if paths.len() >= PATHS_PER_GOAL {
+                paths.remove(0);
+            }
+            paths.push(cached);
+        }
+
+        Some(path)
+    }
+
+    /// A Dijkstra map out from `starts`, shared by everyone asking for the same starts.
+    pub fn dijkstra(&mut self, map: &Map, starts: &[usize], max_depth: f32) -> Arc<DijkstraMap> {
+        let mut key = starts.to_vec();
+        key.sort_unstable();
+        key.dedup();
+
+        if let Some(cached) = self.dijkstras.get(&key) {
+            if cached.max_depth == max_depth {
+                self.stats.dijkstra_hits += 1;
+                return cached.map.clone();
+            }
+        }
+
+        self.stats.dijkstra_misses += 1;
+        let dijkstra = Arc::new(DijkstraMap::new(map.width, map.height, &key, map, max_depth));
+
+        // the map only depends on the tiles it reached
+        let chunks = (0..dijkstra.map.len())
+            .filter(|idx| dijkstra.map[*idx] < f32::MAX)
+            .map(|idx| map.tiles.chunk_of(idx))
+            .collect();
+
+        self.dijkstras.insert(
+            key,
+            CachedDijkstra {
+                map: dijkstra.clone(),
+                max_depth,
+                chunks,
+                turn: self.turn,
+            },
+        );
+
+        dijkstra
+    }
+}
+
+// chunks under the box around a path, a change anywhere in there might open up a shorter way
+fn path_chunks(map: &Map, steps: &[usize]) -> HashSet<usize> {
+    let points: Vec<Point> = steps.iter().map(|idx| map.idx_point(*idx)).collect();
+    let x1 = points.iter().map(|p| p.x).min().unwrap_or(0);
+    let x2 = points.iter().map(|p| p.x).max().unwrap_or(0);
+    let y1 = points.iter().map(|p| p.y).min().unwrap_or(0);
+    let y2 = points.iter().map(|p| p.y).max().unwrap_or(0);
+
+    let mut chunks = HashSet::new();
+    for y in y1..=y2 {
+        for x in x1..=x2 {
+            chunks.insert(map.tiles.chunk_of(map.xy_idx(x, y)));
+        }
+    }
+
+    chunks
+}
@@ -1,8 +1,8 @@
 use std::collections::HashMap;
 
-use shipyard::{EntityId, Get, UniqueView, UniqueViewMut, ViewMut, World};
+use shipyard::{AddComponent, EntityId, Get, Remove, UniqueView, UniqueViewMut, ViewMut, World};
 
-use crate::components::{SpatialKnowledge, Vision};
+use crate::components::{Energy, Sneaking, SpatialKnowledge, Vision};
 use crate::map::{Map, TileType};
 use crate::uniques::{GameLog, PPoint, PlayerID};
 
@@ -26,11 +26,7 @@ pub fn get_player_viewshed(world: &World) -> Vision {
     if let Ok(vs) = vvs.get(player_id) {
         vs.clone()
     } else {
-        Vision {
-            visible_tiles: Vec::new(),
-            range: 0,
-            dirty: true,
-        }
+        Vision::new(0)
     }
 }
 
@@ -61,3 +57,48 @@ pub fn try_next_level(world: &World) -> bool {
         false
     }
 }
+
+// sneaking keeps the player's footsteps quiet
+pub fn toggle_stealth(world: &World) {
+    let player_id = world.borrow::<UniqueView<PlayerID>>().unwrap().0;
+    let mut log = world.borrow::<UniqueViewMut<GameLog>>().unwrap();
+    let mut vsneaking = world.borrow::<ViewMut<Sneaking>>().unwrap();
+
+    if vsneaking.get(player_id).is_ok() {
+        vsneaking.remove(player_id);
+        log.messages.push("You stop sneaking".to_string());
+    } else {
+        vsneaking.add_component_unchecked(player_id, Sneaking {});
+        log.messages.push("You start sneaking".to_string());
+    }
+}
+
+// pays off a turn the player owes from slow ground or being slowed, the rest of the world gets it instead
+pub fn player_owes_turn(world: &World) -> bool {
+    let player_id = world.borrow::<UniqueView<PlayerID>>().unwrap().0;
+    let mut venergy = world.borrow::<ViewMut<Energy>>().unwrap();
+
+    if let Ok(energy) = (&mut venergy).get(player_id) {
+        if energy.debt >= 1. {
+            energy.debt -= 1.;
+            return true;
+        }
+    }
+
+    false
+}
+
+// spends a turn the player banked from haste or quick ground, they act again before the world does
+pub fn player_spends_banked_turn(world: &World) -> bool {
+    let player_id = world.borrow::<UniqueView<PlayerID>>().unwrap().0;
+    let mut venergy = world.borrow::<ViewMut<Energy>>().unwrap();
+
+    if let Ok(energy) = (&mut venergy).get(player_id) {
+        if energy.debt <= -1. {
+            energy.debt += 1.;
+            return true;
+        }
+    }
+
+    false
+}
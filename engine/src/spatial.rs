@@ -0,0 +1,128 @@
+use rltk::Point;
+use shipyard::{Component, EntityId, Get, Unique, View};
+
+// width and height of a bucket in tiles
+const BUCKET_SIZE: i32 = 8;
+
+/// Entities bucketed by the tiles they stand on, rebuilt by map indexing every turn.
+/// Like `Map::tile_content` only entities in awake chunks are indexed.
+#[derive(Default, Unique)]
+pub struct SpatialIndex {
+    width: i32,
+    height: i32,
+    buckets_wide: i32,
+    buckets: Vec<Vec<(EntityId, Point)>>,
+}
+
+impl SpatialIndex {
+    /// Empties the index, resizing it if the map has changed size
+    pub fn clear(&mut self, width: i32, height: i32) {
+        if width != self.width || height != self.height {
+            self.width = width;
+            self.height = height;
+            self.buckets_wide = (width + BUCKET_SIZE - 1) / BUCKET_SIZE;
+            let buckets_high = (height + BUCKET_SIZE - 1) / BUCKET_SIZE;
+            self.buckets = vec![vec![]; (self.buckets_wide * buckets_high) as usize];
+        }
+
+        for bucket in self.buckets.iter_mut() {
+            bucket.clear();
+        }
+    }
+
+    pub fn insert(&mut self, id: EntityId, p: Point) {
+        if p.x < 0 || p.x >= self.width || p.y < 0 || p.y >= self.height {
+            return;
+        }
+
+        let bucket = (p.y / BUCKET_SIZE) * self.buckets_wide + p.x / BUCKET_SIZE;
+        self.buckets[bucket as usize].push((id, p));
+    }
+
+    /// Every entity tile within `radius` of `center`. Entities bigger than a tile show up once for each of their
+    /// tiles in range, so callers checking what can be seen don't miss the parts of them that are in view
+    pub fn within(&self, center: Point, radius: f32) -> Vec<(EntityId, Point)> {
+        if self.buckets.is_empty() {
+            return vec![];
+        }
+
+        let reach = radius.ceil() as i32;
+        let bx1 = (center.x - reach).max(0) / BUCKET_SIZE;
+        let bx2 = (center.x + reach).min(self.width - 1) / BUCKET_SIZE;
+        let by1 = (center.y - reach).max(0) / BUCKET_SIZE;
+        let by2 = (center.y + reach).min(self.height - 1) / BUCKET_SIZE;
+
+        let mut found = vec![];
+        for by in by1..=by2 {
+            for bx in bx1..=bx2 {
+                for (id, p) in self.buckets[(by * self.buckets_wide + bx) as usize].iter() {
+                    if rltk::DistanceAlg::Pythagoras.distance2d(center, *p) <= radius {
+                        found.push((*id, *p));
+                    }
+                }
+            }
+        }
+
+        found
+    }
+
+    /// Like `within`, only for entities that have a `T`
+    pub fn within_with<T: Component>(&self, view: &View<T>, center: Point, radius: f32) -> Vec<(EntityId, Point)> {
+        self.within(center, radius)
+            .into_iter()
+            .filter(|(id, _)| view.get(*id).is_ok())
+            .collect()
+    }
+}
+
+/// A set of tiles stored as a bitset over their bounding box, for constant time membership checks
+#[derive(Default, Clone, Debug, PartialEq)]
+pub struct TileSet {
+    x1: i32,
+    y1: i32,
+    width: i32,
+    height: i32,
+    bits: Vec<u64>,
+}
+
+impl TileSet {
+    pub fn new(points: &[Point]) -> TileSet {
+        if points.is_empty() {
+            return TileSet::default();
+        }
+
+        let x1 = points.iter().map(|p| p.x).min().unwrap();
+        let x2 = points.iter().map(|p| p.x).max().unwrap();
+        let y1 = points.iter().map(|p| p.y).min().unwrap();
+        let y2 = points.iter().map(|p| p.y).max().unwrap();
+
+        let mut set = TileSet {
+            x1,
+            y1,
+            width: x2 - x1 + 1,
+            height: y2 - y1 + 1,
+            bits: vec![],
+        };
+        set.bits = vec![0; ((set.width * set.height) as usize + 63) / 64];
+
+        for p in points.iter() {
+            let bit = set.bit(*p);
+            set.bits[bit / 64] |= 1u64 << (bit % 64);
+        }
+
+        set
+    }
+
+    fn bit(&self, p: Point) -> usize {
+        ((p.y - self.y1) * self.width + (p.x - self.x1)) as usize
+    }
+
+    pub fn contains(&self, p: Point) -> bool {
+        if p.x < self.x1 || p.x >= self.x1 + self.width || p.y < self.y1 || p.y >= self.y1 + self.height {
+            return false;
+        }
+
+        let bit = self.bit(p);
+        self.bits[bit / 64] & (1u64 << (bit % 64)) != 0
+    }
+}
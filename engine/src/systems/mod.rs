@@ -2,9 +2,15 @@ pub mod system_ai;
 pub mod system_ai_fish;
 pub mod system_cleanup;
 pub mod system_dissasemble;
+pub mod system_doors;
 pub mod system_fire;
+pub mod system_fluids;
+pub mod system_hunger;
+pub mod system_lighting;
 pub mod system_map_indexing;
 pub mod system_melee_combat;
 pub mod system_particle;
 pub mod system_pathfinding;
+pub mod system_squads;
+pub mod system_status;
 pub mod system_visibility;
@@ -1,21 +1,40 @@
 use crate::ai::decisions::{Intent, Target, Task};
-use crate::ai::labors;
-use crate::components::{Actor, ActorType, DijkstraMapToMe, Faction, Position, Spawner, SpawnerType};
+use crate::ai::{ecology, labors};
+use crate::components::{
+    Actor, ActorType, DijkstraMapToMe, Energy, Faction, LocomotionType, Locomotive, Position, Spawner, SpawnerType,
+    StatusKind, Statuses, WantsToDropItem, WantsToUnequipItem, WantsToUseItem,
+};
 use crate::effects::{add_effect, EffectType};
 use crate::entity_factory;
-use crate::map::{Map, TileType};
+use crate::map::Map;
+use crate::path_cache::PathCache;
 use crate::uniques::Turn;
-use crate::utils::{get_neighbors, get_path};
+use crate::utils::{closest_point, get_neighbors};
 use rltk;
-use rltk::{BaseMap, Point};
-use shipyard::{AddComponent, AllStoragesViewMut, EntityId, Get, IntoIter, IntoWithId, UniqueView, View, ViewMut};
+use rltk::{BaseMap, DistanceAlg, Point};
+use shipyard::{
+    AddComponent, AllStoragesViewMut, EntityId, Get, IntoIter, IntoWithId, UniqueView, UniqueViewMut, View, ViewMut,
+};
 
-pub fn run_ai_system(mut store: AllStoragesViewMut) {
-    let mut to_move_from_to: Vec<(EntityId, Point, Point)> = vec![];
+pub fn run_ai_system(store: AllStoragesViewMut) {
+    run_ai(store, false);
+}
+
+/// A second go for anyone with a whole turn banked from haste or quick ground, spending it
+pub fn run_banked_ai_system(store: AllStoragesViewMut) {
+    run_ai(store, true);
+}
+
+fn run_ai(mut store: AllStoragesViewMut, banked_pass: bool) {
+    let mut to_move_from_to: Vec<(EntityId, Vec<Point>, Point)> = vec![];
     let mut to_fish: Vec<(EntityId, Point)> = vec![];
     let mut to_attack: Vec<(EntityId, Point)> = vec![];
-    let mut to_spawn_fish: Vec<Point> = vec![];
-    let mut to_spawn_orc: Vec<(Point, Faction)> = vec![];
+    let mut to_use: Vec<(EntityId, EntityId, Option<Point>)> = vec![];
+    let mut to_drop: Vec<(EntityId, EntityId)> = vec![];
+    let mut to_unequip: Vec<(EntityId, EntityId)> = vec![];
+    let mut to_spawn_fish: Vec<(EntityId, Point)> = vec![];
+    let mut to_spawn_orc: Vec<(EntityId, Point, Faction)> = vec![];
+    let mut to_spawn_animal: Vec<(EntityId, Point, SpawnerType)> = vec![];
 
     store.run(
         |map: UniqueView<Map>,
@@ -24,15 +43,41 @@ pub fn run_ai_system(mut store: AllStoragesViewMut) {
          vpos: View<Position>,
          vdijkstra: View<DijkstraMapToMe>,
          mut vintent: ViewMut<Intent>,
-         vspawner: ViewMut<Spawner>| {
+         mut venergy: ViewMut<Energy>,
+         vstatuses: View<Statuses>,
+         mut vspawner: ViewMut<Spawner>| {
             for (id, (actor, pos)) in (&vactor, &vpos).iter().with_id() {
+                if !map.is_awake(map.point_idx(pos.any_point())) {
+                    continue;
+                }
+
+                // still catching up after slow ground, the player pays off theirs between their own turns
+                if actor.atype != ActorType::Player {
+                    let debt = (&venergy).get(id).map_or(0., |e| e.debt);
+                    if banked_pass {
+                        if debt > -1. {
+                            continue;
+                        }
+                        (&mut venergy).get(id).unwrap().debt += 1.;
+                    } else if debt >= 1. {
+                        (&mut venergy).get(id).unwrap().debt -= 1.;
+                        continue;
+                    }
+                }
+
+                if vstatuses.get(id).map_or(false, |s| s.has(StatusKind::Stunned)) {
+                    continue;
+                }
+
                 // if actor.atype != ActorType::Villager && actor.atype != ActorType::Orc {
                 //     continue;
                 // }
 
                 let new_intent = match actor.atype {
                     ActorType::Player => continue,
-                    ActorType::Orc | ActorType::Villager | ActorType::Wolf => labors::get_action(&store, id).intent,
+                    ActorType::Orc | ActorType::Villager | ActorType::Wolf | ActorType::Deer | ActorType::Rabbit => {
+                        labors::get_action(&store, id).intent
+                    }
                     ActorType::Fish => continue,
                     ActorType::Spawner => {
                         if let Ok(spawner) = vspawner.get(id) {
@@ -96,44 +141,78 @@ pub fn run_ai_system(mut store: AllStoragesViewMut) {
                                         }
                                     }
 
-                                    to_move_from_to.push((id, pos.ps[0], map.idx_point(tidx as usize)));
+                                    to_move_from_to.push((id, pos.ps.clone(), map.idx_point(tidx as usize)));
                                 } else {
-                                    to_move_from_to.push((id, pos.ps[0], target_pos.ps[0]));
+                                    to_move_from_to.push((id, pos.ps.clone(), target_pos.ps[0]));
                                 }
                             }
                         } else if let Target::LOCATION(loc) = new_intent.target[0] {
-                            to_move_from_to.push((id, pos.ps[0], loc));
+                            to_move_from_to.push((id, pos.ps.clone(), loc));
                         }
                     }
                     Task::Destroy => {}
                     Task::PickUpItem => {}
-                    Task::DropItem => todo!(),
-                    Task::UseItem => todo!(),
-                    Task::EquipItem => todo!(),
-                    Task::UnequipItem => todo!(),
+                    Task::DropItem => {
+                        if let Target::ENTITY(item) = new_intent.target[0] {
+                            to_drop.push((id, item));
+                        }
+                    }
+                    Task::UseItem | Task::EquipItem => {
+                        // equipping goes through item use, the second target is where to aim
+                        if let Target::ENTITY(item) = new_intent.target[0] {
+                            let target = new_intent.target.get(1).map(|t| t.get_point(&vpos));
+                            to_use.push((id, item, target));
+                        }
+                    }
+                    Task::UnequipItem => {
+                        if let Target::ENTITY(item) = new_intent.target[0] {
+                            to_unequip.push((id, item));
+                        }
+                    }
                     Task::UseWorkshop => todo!(),
                     Task::DepositItemToInventory => {}
                     Task::Attack => {
                         if let Target::ENTITY(target) = new_intent.target[0] {
                             if let Ok(target_pos) = vpos.get(target) {
-                                dbg!(1);
-                                to_attack.push((id, target_pos.ps[0]));
+                                // aim for whichever tile of the target is nearest to any part of the attacker
+                                let aim = target_pos.ps.iter().min_by(|a, b| {
+                                    let da = DistanceAlg::Pythagoras.distance2d(**a, closest_point(&pos.ps, **a));
+                                    let db = DistanceAlg::Pythagoras.distance2d(**b, closest_point(&pos.ps, **b));
+                                    da.partial_cmp(&db).unwrap()
+                                });
+                                if let Some(aim) = aim {
+                                    to_attack.push((id, *aim));
+                                }
                             }
                         } else if let Target::LOCATION(loc) = new_intent.target[0] {
                             to_attack.push((id, loc));
                         }
                     }
                     Task::Idle => {}
+                    Task::Graze => add_effect(
+                        Some(id),
+                        EffectType::Eat {
+                            amount: ecology::GRAZE_NUTRITION,
+                        },
+                    ),
                     Task::Spawn => {
-                        if let Ok(spawner) = vspawner.get(id) {
+                        if let Ok(spawner) = (&mut vspawner).get(id) {
+                            spawner.spawned.retain(|e| vpos.get(*e).is_ok());
+                            if spawner.spawned.len() >= spawner.cap {
+                                continue;
+                            }
+
                             match spawner.typ {
                                 SpawnerType::Orc => {
-                                    to_spawn_orc.push((pos.ps[0], actor.faction));
+                                    to_spawn_orc.push((id, pos.ps[0], actor.faction));
                                 }
                                 SpawnerType::Fish => {
-                                    to_spawn_fish.push(pos.ps[0]);
+                                    to_spawn_fish.push((id, pos.ps[0]));
                                     // entity_factory::fish(&mut store, pos.ps[0].x, pos.ps[0].y);
                                 }
+                                SpawnerType::Wolf | SpawnerType::Deer | SpawnerType::Rabbit => {
+                                    to_spawn_animal.push((id, pos.ps[0], spawner.typ));
+                                }
                             }
                         }
                     }
@@ -142,9 +221,15 @@ pub fn run_ai_system(mut store: AllStoragesViewMut) {
         },
     );
 
-    for (e, from, to) in to_move_from_to {
+    for (e, body, to) in to_move_from_to {
         let map = store.borrow::<UniqueView<Map>>().unwrap();
-        let path = get_path(&map, from, to);
+        let mut cache = store.borrow::<UniqueViewMut<PathCache>>().unwrap();
+        let vloco = store.borrow::<View<Locomotive>>().unwrap();
+        let mtype = vloco.get(e).map_or(LocomotionType::Ground, |loco| loco.mtype);
+        let path = match cache.path(&map, &body, to, mtype) {
+            Some(path) => path,
+            None => continue,
+        };
 
         if path.success && path.steps.len() > 1 {
             // movement::try_move_entity(e, point_diff(from, p), gs);
@@ -165,7 +250,7 @@ pub fn run_ai_system(mut store: AllStoragesViewMut) {
             .iter()
             .filter(|p| {
                 let idx = map.point_idx(**p);
-                map.tiles[idx] == TileType::Water
+                map.is_swimmable(idx)
             })
             .collect();
 
@@ -195,18 +280,54 @@ pub fn run_ai_system(mut store: AllStoragesViewMut) {
         );
     }
 
-    for pos in to_spawn_fish.iter() {
-        entity_factory::fish(&mut store, pos.x, pos.y);
+    for (e, item, target) in to_use {
+        store.add_component(e, WantsToUseItem { item, target });
+    }
+
+    for (e, item) in to_drop {
+        store.add_component(e, WantsToDropItem { item });
+    }
+
+    for (e, item) in to_unequip {
+        store.add_component(e, WantsToUnequipItem { item });
+    }
+
+    for (spawner, pos) in to_spawn_fish {
+        let e = entity_factory::fish(&mut store, pos.x, pos.y);
+        record_spawn(&store, spawner, e);
     }
 
-    for (pos, faction) in to_spawn_orc.iter() {
+    for (spawner, pos, typ) in to_spawn_animal {
+        let e = match typ {
+            SpawnerType::Wolf => {
+                // wolves are born into the den's pack
+                entity_factory::pack_wolf(&mut store, pos.x, pos.y, spawner)
+            }
+            SpawnerType::Deer => entity_factory::deer(&mut store, pos.x, pos.y),
+            SpawnerType::Rabbit => entity_factory::rabbit(&mut store, pos.x, pos.y),
+            _ => continue,
+        };
+        record_spawn(&store, spawner, e);
+    }
+
+    for (spawner, pos, faction) in to_spawn_orc {
         let e = entity_factory::orc(&mut store, pos.x, pos.y);
         store.run(|mut vactor: ViewMut<Actor>| {
             if let Ok(mut spawned_actor) = (&mut vactor).get(e) {
-                spawned_actor.faction = *faction;
+                spawned_actor.faction = faction;
             } else {
                 dbg!("Error: Orc isn't an actor, this shouldn't happen");
             }
         });
+        record_spawn(&store, spawner, e);
     }
 }
+
+// spawners count only what they've spawned themselves towards their cap
+fn record_spawn(store: &AllStoragesViewMut, spawner: EntityId, spawned: EntityId) {
+    store.run(|mut vspawner: ViewMut<Spawner>| {
+        if let Ok(spawner) = (&mut vspawner).get(spawner) {
+            spawner.spawned.push(spawned);
+        }
+    });
+}
@@ -1,75 +1,158 @@
-use crate::components::{Actor, ActorType, Position};
+use crate::ai::labors::AIBehaviors;
+use crate::components::{Actor, ActorType, Position, Velocity};
 use crate::effects::{add_effect, EffectType};
-use crate::map::{Map, TileType};
-use rand::prelude::SliceRandom;
-use rand::thread_rng;
+use crate::map::Map;
+use crate::uniques::RNG;
+use crate::utils::dir_to_point;
 use rltk::Point;
-use shipyard::{EntityId, IntoIter, IntoWithId, UniqueView, View};
+use shipyard::{EntityId, Get, IntoIter, IntoWithId, UniqueView, UniqueViewMut, View, ViewMut};
+use std::collections::HashMap;
 
 // Leaving this in a separate system for now as I suspect AI is going to change significantly
 
-// currently fish only move east
-pub fn run_fish_ai(map: UniqueView<Map>, vpos: View<Position>, vactor: View<Actor>) {
-    let mut to_try_move: Vec<(EntityId, Point)> = vec![];
-    let mut to_remove: Vec<EntityId> = vec![];
+const SCHOOL_RADIUS: f32 = 4.0;
+const PERSONAL_SPACE: f32 = 1.5;
+const FEAR_RADIUS: f32 = 3.0;
+
+const CURRENT_WEIGHT: f32 = 1.0;
+const COHESION_WEIGHT: f32 = 0.5;
+const ALIGNMENT_WEIGHT: f32 = 0.5;
+const SEPARATION_WEIGHT: f32 = 0.8;
+const FEAR_WEIGHT: f32 = 2.0;
+
+// fish drift with the current and school boids style, keeping clear of anyone who fishes
+pub fn run_fish_ai(
+    map: UniqueView<Map>,
+    mut rng: UniqueViewMut<RNG>,
+    vpos: View<Position>,
+    vactor: View<Actor>,
+    mut vvel: ViewMut<Velocity>,
+) {
+    let mut fish: Vec<(EntityId, Point)> = vec![];
+    let mut bodies: HashMap<EntityId, Vec<Point>> = HashMap::new();
+    let mut fishers: Vec<Point> = vec![];
 
     for (id, (pos, actor)) in (&vpos, &vactor).iter().with_id() {
-        if actor.atype != ActorType::Fish {
+        if !map.is_awake(map.point_idx(pos.any_point())) {
             continue;
         }
 
-        if pos.ps.len() == 1 {
-            // if at edge of map, remove fish
+        if actor.atype == ActorType::Fish {
+            // big fish school and steer by their first tile, the rest of the body has to fit wherever that goes
+            fish.push((id, pos.ps[0]));
+            bodies.insert(id, pos.ps.clone());
+        } else if actor.behaviors.contains(&AIBehaviors::GatherFish) {
+            fishers.push(pos.ps[0]);
+        }
+    }
 
-            let pos = pos.ps[0];
+    let mut to_move: Vec<(EntityId, Point, Point)> = vec![];
+    let mut to_remove: Vec<EntityId> = vec![];
+
+    for (id, pos) in fish.iter() {
+        let idx = map.point_idx(*pos);
+        let body = &bodies[id];
 
-            if pos.x >= map.width - 2 {
-                to_remove.push(id);
-            } else {
-                to_try_move.push((id, pos));
+        // fish that reach the end of the river leave the map
+        if body
+            .iter()
+            .any(|p| map.is_river_sink(map.point_idx(*p), map.river_flow))
+        {
+            to_remove.push(*id);
+            continue;
+        }
+
+        let current = dir_to_point(Point { x: 0, y: 0 }, map.currents[idx], 1);
+        let mut dx = current.x as f32 * CURRENT_WEIGHT;
+        let mut dy = current.y as f32 * CURRENT_WEIGHT;
+
+        let mut center = (0.0, 0.0);
+        let mut heading = (0.0, 0.0);
+        let mut neighbors = 0;
+        for (other, opos) in fish.iter() {
+            if other == id {
+                continue;
+            }
+
+            let dist = rltk::DistanceAlg::Pythagoras.distance2d(*pos, *opos);
+            if dist > SCHOOL_RADIUS {
+                continue;
+            }
+
+            neighbors += 1;
+            center.0 += opos.x as f32;
+            center.1 += opos.y as f32;
+
+            if let Ok(vel) = vvel.get(*other) {
+                heading.0 += vel.x;
+                heading.1 += vel.y;
+            }
+
+            if dist < PERSONAL_SPACE {
+                dx -= (opos.x - pos.x) as f32 * SEPARATION_WEIGHT;
+                dy -= (opos.y - pos.y) as f32 * SEPARATION_WEIGHT;
             }
-        } else {
-            dbg!("ERROR: multi-tile fish not supported");
         }
-    }
 
-    for (e, pos) in to_try_move {
-        let mut potential_spaces = vec![
-            Point { x: pos.x + 1, y: pos.y },
-            Point {
-                x: pos.x + 1,
-                y: pos.y + 1,
-            },
-            Point {
-                x: pos.x + 1,
-                y: pos.y - 1,
-            },
-        ];
-
-        potential_spaces.shuffle(&mut thread_rng());
-
-        for ps in potential_spaces {
-            let canmove = {
-                let idx = map.point_idx(ps);
-                map.tiles[idx] == TileType::Water
-            };
-
-            if canmove {
-                add_effect(
-                    Some(e),
-                    EffectType::Move {
-                        tile_idx: map.point_idx(ps),
-                    },
-                );
-                // movement::try_move_entity(e, point_diff(pos, ps), gs);
-                break;
+        if neighbors > 0 {
+            let n = neighbors as f32;
+            dx += (center.0 / n - pos.x as f32) / SCHOOL_RADIUS * COHESION_WEIGHT;
+            dy += (center.1 / n - pos.y as f32) / SCHOOL_RADIUS * COHESION_WEIGHT;
+            dx += heading.0 / n * ALIGNMENT_WEIGHT;
+            dy += heading.1 / n * ALIGNMENT_WEIGHT;
+        }
+
+        for fpos in fishers.iter() {
+            let dist = rltk::DistanceAlg::Pythagoras.distance2d(*pos, *fpos);
+            if dist > 0.0 && dist <= FEAR_RADIUS {
+                dx -= (fpos.x - pos.x) as f32 / dist * FEAR_WEIGHT;
+                dy -= (fpos.y - pos.y) as f32 / dist * FEAR_WEIGHT;
+            }
+        }
+
+        // pick the water tile that best matches where the fish wants to go, with a little wobble
+        let mut best = *pos;
+        let mut best_score = f32::MIN;
+        for dir in 1..=9 {
+            let p = dir_to_point(*pos, dir, 1);
+            let fits = body.iter().all(|b| {
+                let bp = Point::new(b.x + p.x - pos.x, b.y + p.y - pos.y);
+                map.in_bounds(bp.x, bp.y) && map.is_swimmable(map.point_idx(bp))
+            });
+            if !fits {
+                continue;
+            }
+
+            let step = ((p.x - pos.x) as f32, (p.y - pos.y) as f32);
+            let len = f32::max((step.0 * step.0 + step.1 * step.1).sqrt(), 1.0);
+            let score = (step.0 * dx + step.1 * dy) / len + rng.0.range(0, 100) as f32 / 200.0;
+
+            if score > best_score {
+                best = p;
+                best_score = score;
             }
         }
+
+        to_move.push((*id, *pos, best));
+    }
+
+    for (e, from, to) in to_move {
+        if let Ok(vel) = (&mut vvel).get(e) {
+            vel.x = (to.x - from.x) as f32;
+            vel.y = (to.y - from.y) as f32;
+        }
+
+        if to != from {
+            add_effect(
+                Some(e),
+                EffectType::Move {
+                    tile_idx: map.point_idx(to),
+                },
+            );
+        }
     }
 
     for e in to_remove.iter() {
         add_effect(Some(*e), EffectType::Delete { entity: *e });
-        // gs.world.delete_entity(*e);
-        // dbg!("ERROR: Need to delete entity");
     }
 }
@@ -0,0 +1,35 @@
+use crate::components::{BlocksTile, BlocksVisibility, Door, Position, Renderable};
+use crate::map::Map;
+use shipyard::{AddComponent, Get, IntoIter, IntoWithId, UniqueView, View, ViewMut};
+
+// open doors swing shut once nothing is left in the doorway
+pub fn run_door_system(
+    map: UniqueView<Map>,
+    vpos: View<Position>,
+    mut vdoor: ViewMut<Door>,
+    mut vblocks: ViewMut<BlocksTile>,
+    mut vblocksvis: ViewMut<BlocksVisibility>,
+    mut vrend: ViewMut<Renderable>,
+) {
+    let mut to_close = vec![];
+    for (id, (pos, door)) in (&vpos, &vdoor).iter().with_id() {
+        let clear = pos
+            .ps
+            .iter()
+            .all(|p| map.tile_content[map.point_idx(*p)].iter().all(|e| *e == id));
+        if door.open && clear {
+            to_close.push(id);
+        }
+    }
+
+    for id in to_close {
+        if let Ok(door) = (&mut vdoor).get(id) {
+            door.open = false;
+        }
+        vblocks.add_component_unchecked(id, BlocksTile {});
+        vblocksvis.add_component_unchecked(id, BlocksVisibility {});
+        if let Ok(rend) = (&mut vrend).get(id) {
+            rend.glyph = rltk::to_cp437('+');
+        }
+    }
+}
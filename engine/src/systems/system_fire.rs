@@ -1,82 +1,155 @@
-use crate::components::{CombatStats, Fire, Position};
+use crate::components::{Actor, BlocksTile, Flammable, Inventory, Position, StatusKind, Statuses};
+use crate::effects::noise::FIRE_NOISE;
 use crate::effects::{add_effect, EffectType, Targets};
+use crate::fluids::Fluid;
 use crate::map::{Map, TileType};
-use rltk::RandomNumberGenerator;
-use shipyard::{EntityId, IntoIter, IntoWithId, Remove, UniqueViewMut, View, ViewMut};
+use crate::tiles::tile_info;
+use crate::uniques::{Wind, RNG};
+use crate::utils::dir_to_point;
+use rltk::Point;
+use shipyard::{EntityId, Get, IntoIter, IntoWithId, UniqueViewMut, View, ViewMut};
 
 pub const NEW_FIRE_TURNS: i32 = 10;
 
+// chance in a thousand a burning tile lights each neighbor in still air
+const SPREAD_CHANCE: i32 = 100;
+
+// at full strength, wind doubles the chance of spreading downwind and stops fire spreading upwind
+const WIND_SPREAD: f32 = 1.0;
+
+// one in this many turns the wind veers a step
+const WIND_VEER_CHANCE: i32 = 40;
+
+// the numpad directions in order around the compass, for veering
+const COMPASS: [usize; 8] = [8, 9, 6, 3, 2, 1, 4, 7];
+
+// smoke a burning tile gives off each turn, the fluid system carries it away
+const SMOKE_PER_FIRE: f32 = 1.0;
+
 pub fn run_fire_system(
     mut map: UniqueViewMut<Map>,
+    mut rng: UniqueViewMut<RNG>,
+    mut wind: UniqueViewMut<Wind>,
     vpos: View<Position>,
-    vstats: ViewMut<CombatStats>,
-    mut vfire: ViewMut<Fire>,
+    vactor: View<Actor>,
+    vblocks: View<BlocksTile>,
+    vinv: View<Inventory>,
+    mut vflammable: ViewMut<Flammable>,
+    mut vstatuses: ViewMut<Statuses>,
 ) {
-    let mut rng = RandomNumberGenerator::new();
-
-    // damage all entities on fire. If they are standing somewhere flammable, ignite it
-    for (id, (pos, _, _)) in (&vpos, &vstats, &vfire).iter().with_id() {
-        add_effect(
-            None,
-            EffectType::Damage {
-                amount: 1,
-                target: Targets::Single { target: id },
-            },
-        );
-
-        for pos in pos.ps.iter() {
-            let idx = map.xy_idx(pos.x, pos.y);
-            if map.is_flammable(idx) && map.fire_turns[idx] == 0 {
-                map.fire_turns[idx] = NEW_FIRE_TURNS;
-            }
-        }
+    if rng.0.range(0, WIND_VEER_CHANCE) == 0 {
+        let i = COMPASS.iter().position(|d| *d == wind.dir).unwrap_or(0);
+        let veer = if rng.0.range(0, 2) == 0 { 1 } else { 7 };
+        wind.dir = COMPASS[(i + veer) % COMPASS.len()];
     }
 
-    // reduce fire turns and remove expired fire components
+    // anything burning lights whatever it's standing on and uses up its fuel, water or a soaked tile puts it out.
+    // the damage comes from the status system
     let mut to_remove: Vec<EntityId> = vec![];
-    (&mut vfire).iter().with_id().for_each(|(id, fire)| {
-        fire.turns -= 1;
+    let mut burnt_out: Vec<EntityId> = vec![];
+    for (id, (pos, statuses)) in (&vpos, &vstatuses).iter().with_id() {
+        if !statuses.has(StatusKind::Burning) {
+            continue;
+        }
 
-        if fire.turns <= 0 {
+        if pos.ps.iter().any(|p| {
+            let idx = map.point_idx(*p);
+            map.is_swimmable(idx) || map.fluids[idx].douses()
+        }) {
             to_remove.push(id);
-            // vfire.remove(id);
+            continue;
+        }
+
+        for pos in pos.ps.iter() {
+            let idx = map.point_idx(*pos);
+            map.ignite(idx);
+        }
+
+        if let Ok(flammable) = (&mut vflammable).get(id) {
+            flammable.fuel -= 1;
+            if flammable.fuel <= 0 {
+                burnt_out.push(id);
+            }
         }
-    });
+    }
     for e in to_remove.iter() {
-        vfire.remove(*e);
+        if let Ok(statuses) = (&mut vstatuses).get(*e) {
+            statuses.remove(StatusKind::Burning);
+        }
     }
 
-    // reduce fire turns on tiles
-    for idx in 0..(map.width * map.height) as usize {
-        if map.fire_turns[idx] > 0 {
-            map.fire_turns[idx] -= 1;
+    // anything with nothing left to burn is gone, buildings collapse into rubble and spill what was inside
+    for e in burnt_out {
+        if let Ok(pos) = vpos.get(e) {
+            if vblocks.get(e).is_ok() && vactor.get(e).is_err() {
+                for p in pos.ps.iter() {
+                    let idx = map.point_idx(*p);
+                    map.replace_tile(idx, TileType::Rubble);
+                    map.fire_turns[idx] = 0;
+                }
+            }
+        }
 
-            if map.fire_turns[idx] == 0 && map.is_flammable(idx) {
-                map.tiles[idx] = TileType::Dirt;
+        if let Ok(inv) = vinv.get(e) {
+            for item in inv.items.iter() {
+                add_effect(Some(e), EffectType::Drop { entity: *item });
             }
+        }
+
+        add_effect(None, EffectType::Delete { entity: e });
+    }
 
-            // light entities on this tile on fire
-            for e in map.tile_content[idx].iter() {
-                add_effect(
-                    None,
-                    EffectType::Fire {
-                        turns: NEW_FIRE_TURNS,
-                        target: Targets::Single { target: *e },
-                    },
-                );
+    // burn down tiles, fires in sleeping chunks smoulder until someone comes back
+    let burning: Vec<usize> = map.awake_tiles().filter(|idx| map.fire_turns[*idx] > 0).collect();
+    let downwind = dir_to_point(Point::new(0, 0), wind.dir, 1);
+    for idx in burning {
+        map.fire_turns[idx] -= 1;
+        map.fluids[idx][Fluid::Smoke] += SMOKE_PER_FIRE;
+
+        if map.fire_turns[idx] == 0 {
+            if let Some(burnt) = tile_info(map.tiles[idx]).burns_into {
+                map.replace_tile(idx, burnt);
             }
+        }
+
+        // light entities on this tile on fire
+        for e in map.tile_content[idx].iter() {
+            add_effect(
+                None,
+                EffectType::Fire {
+                    turns: NEW_FIRE_TURNS,
+                    target: Targets::Single { target: *e },
+                },
+            );
+        }
+
+        // chance to spread to nearby tiles, more likely the way the wind blows
+        let (x, y) = map.idx_xy(idx);
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                let (nx, ny) = (x + dx, y + dy);
+                if (dx == 0 && dy == 0) || !map.in_bounds(nx, ny) {
+                    continue;
+                }
+
+                // cosine of the angle between this neighbor and the wind, still air counts as crosswind
+                let lengths = ((dx * dx + dy * dy) * (downwind.x * downwind.x + downwind.y * downwind.y)) as f32;
+                let along = if lengths > 0. {
+                    (dx * downwind.x + dy * downwind.y) as f32 / lengths.sqrt()
+                } else {
+                    0.
+                };
+                let chance = SPREAD_CHANCE as f32 * (1. + WIND_SPREAD * wind.strength * along);
 
-            // Chance to spread to nearby tiles
-            let (x, y) = map.idx_xy(idx);
-            for dx in -1..=1 {
-                for dy in -1..=1 {
-                    let (nx, ny) = (x + dx, y + dy);
-                    if map.in_bounds(nx, ny) {
-                        let idx = map.xy_idx(nx, ny);
-                        if map.fire_turns[idx] == 0 && map.is_flammable(idx) && rng.range(0, 10) == 0 {
-                            map.fire_turns[idx] = NEW_FIRE_TURNS;
-                        }
-                    }
+                let nidx = map.xy_idx(nx, ny);
+                if (rng.0.range(0, 1000) as f32) < chance && map.ignite(nidx) {
+                    add_effect(
+                        None,
+                        EffectType::Noise {
+                            tile_idx: nidx,
+                            volume: FIRE_NOISE,
+                        },
+                    );
                 }
             }
         }
@@ -0,0 +1,173 @@
+use crate::components::{CombatStats, StatusKind, Statuses};
+use crate::effects::{add_effect, EffectType, Targets};
+use crate::fluids::{fluid_info, Fluid, ALL_FLUIDS};
+use crate::map::Map;
+use crate::tiles::tile_info;
+use crate::uniques::Wind;
+use crate::utils::{dir_to_point, get_neighbors};
+use rltk::Point;
+use shipyard::{EntityId, Get, UniqueView, UniqueViewMut, View};
+
+// anything thinner than this dries up or disperses
+const FLUID_MIN: f32 = 0.05;
+
+// liquids shallower than this stay put as a puddle
+const PUDDLE_DEPTH: f32 = 0.2;
+
+// share of a gas the wind carries a tile downwind each turn, at full strength
+const WIND_DRIFT: f32 = 0.5;
+
+// gases get past anything but walls and closed doors, liquids also need open ground or water to run over
+fn can_flow(map: &Map, idx: usize, gas: bool) -> bool {
+    let tile = tile_info(map.tiles[idx]);
+    if tile.wall || map.view_blocked[idx] {
+        false
+    } else {
+        gas || tile.walkable || tile.swimmable
+    }
+}
+
+pub fn run_fluid_system(
+    mut map: UniqueViewMut<Map>,
+    wind: UniqueView<Wind>,
+    vstats: View<CombatStats>,
+    vstatuses: View<Statuses>,
+) {
+    let wet: Vec<usize> = map.awake_tiles().filter(|idx| !map.fluids[*idx].is_empty()).collect();
+
+    // water puts fires out, oil feeds them, and anything harmful hurts or afflicts whoever stands in it
+    for &idx in wet.iter() {
+        let fluids = map.fluids[idx];
+
+        if fluids.douses() {
+            let burning = |e: &EntityId| vstatuses.get(*e).map_or(false, |s| s.has(StatusKind::Burning));
+            if map.fire_turns[idx] > 0 || map.tile_content[idx].iter().any(burning) {
+                add_effect(
+                    None,
+                    EffectType::Extinguish {
+                        target: Targets::Tile { tile_idx: idx },
+                    },
+                );
+            }
+        } else if map.fire_turns[idx] > 0 && fluids.fuel() > 0 {
+            // burning oil sets light to any oil next to it and is used up a turn's worth at a time
+            for n in get_neighbors(map.idx_point(idx)) {
+                if map.in_bounds(n.x, n.y) {
+                    let nidx = map.point_idx(n);
+                    if map.fluids[nidx].fuel() > 0 {
+                        map.ignite(nidx);
+                    }
+                }
+            }
+            for fluid in ALL_FLUIDS.iter() {
+                let fuel = fluid_info(*fluid).fuel;
+                if fuel > 0 {
+                    map.fluids[idx][*fluid] -= 1. / fuel as f32;
+                }
+            }
+        }
+
+        for fluid in ALL_FLUIDS.iter() {
+            let info = fluid_info(*fluid);
+            if !info.harmful_at.map_or(false, |at| fluids[*fluid] >= at) {
+                continue;
+            }
+
+            for e in map.tile_content[idx].iter() {
+                if vstats.get(*e).is_err() {
+                    continue;
+                }
+                if info.damage > 0 {
+                    add_effect(
+                        None,
+                        EffectType::Damage {
+                            amount: info.damage,
+                            target: Targets::Single { target: *e },
+                        },
+                    );
+                }
+                if let Some((kind, turns)) = info.status {
+                    add_effect(
+                        None,
+                        EffectType::Status {
+                            kind,
+                            turns,
+                            target: Targets::Single { target: *e },
+                        },
+                    );
+                }
+            }
+        }
+    }
+
+    // then everything spreads out, worked out from this turn's amounts before any of it moves
+    let downwind = dir_to_point(Point::new(0, 0), wind.dir, 1);
+    let mut flows: Vec<(usize, Fluid, f32)> = vec![];
+    for idx in wet {
+        let here = map.idx_point(idx);
+        for fluid in ALL_FLUIDS.iter() {
+            let amount = map.fluids[idx][*fluid];
+            if amount <= 0. {
+                continue;
+            }
+
+            let info = fluid_info(*fluid);
+
+            // liquids that don't float are lost into open water
+            if !info.gas && !info.floats && map.is_swimmable(idx) {
+                flows.push((idx, *fluid, -amount));
+                continue;
+            }
+
+            let open: Vec<usize> = get_neighbors(here)
+                .into_iter()
+                .filter(|n| map.in_bounds(n.x, n.y))
+                .map(|n| map.point_idx(n))
+                .filter(|n| can_flow(&map, *n, info.gas))
+                .collect();
+
+            let kept = amount * info.decay;
+            let mut moved = 0.;
+            if info.gas {
+                // gases spread out evenly and drift downwind
+                let share = kept * info.spread / 8.;
+                for n in open.iter() {
+                    flows.push((*n, *fluid, share));
+                    moved += share;
+                }
+
+                let to = Point::new(here.x + downwind.x, here.y + downwind.y);
+                if to != here && map.in_bounds(to.x, to.y) && can_flow(&map, map.point_idx(to), true) {
+                    let drift = (kept - moved) * WIND_DRIFT * wind.strength;
+                    flows.push((map.point_idx(to), *fluid, drift));
+                    moved += drift;
+                }
+            } else if kept > PUDDLE_DEPTH {
+                // liquids run off towards wherever there's less of them
+                for n in open.iter() {
+                    let there = map.fluids[*n][*fluid];
+                    if there < kept {
+                        let share = (kept - there) * info.spread / 8.;
+                        flows.push((*n, *fluid, share));
+                        moved += share;
+                    }
+                }
+            }
+
+            flows.push((idx, *fluid, kept - moved - amount));
+        }
+    }
+    for (idx, fluid, amount) in flows {
+        map.fluids[idx][fluid] += amount;
+    }
+
+    // the last wisps and films vanish
+    map.update_awake(|map, idx| {
+        for fluid in ALL_FLUIDS.iter() {
+            let amount = map.fluids[idx][*fluid];
+            if amount != 0. && amount < FLUID_MIN {
+                map.fluids[idx][*fluid] = 0.;
+            }
+        }
+    });
+}
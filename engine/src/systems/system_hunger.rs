@@ -0,0 +1,26 @@
+use crate::components::Hunger;
+use crate::effects::{add_effect, EffectType, Targets};
+use shipyard::{EntityId, IntoIter, IntoWithId, ViewMut};
+
+// everything gets a little hungrier each turn, starving actors slowly lose health
+pub fn run_hunger_system(mut vhunger: ViewMut<Hunger>) {
+    let mut starving: Vec<EntityId> = vec![];
+
+    for (id, hunger) in (&mut vhunger).iter().with_id() {
+        hunger.hunger = i32::min(hunger.hunger + 1, hunger.max);
+
+        if hunger.hunger >= hunger.max {
+            starving.push(id);
+        }
+    }
+
+    for id in starving {
+        add_effect(
+            None,
+            EffectType::Damage {
+                amount: 1,
+                target: Targets::Single { target: id },
+            },
+        );
+    }
+}
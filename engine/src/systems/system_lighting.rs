@@ -0,0 +1,108 @@
+use crate::components::{Equipped, LightSource, Position, StatusKind, Statuses};
+use crate::map::Map;
+use crate::palette::Palette;
+use crate::uniques::Turn;
+use crate::{GameMode, GameSettings};
+use rltk::{Point, RGBA};
+use shipyard::{Get, IntoIter, UniqueView, UniqueViewMut, View};
+
+/// Tiles darker than this can't be made out, except right next to whoever is looking
+pub const MIN_VISIBLE_LIGHT: f32 = 0.25;
+
+/// Turns from one midnight to the next in VillageSim
+pub const DAY_LENGTH: i32 = 600;
+
+// the game starts a little after dawn
+const START_OF_DAY: f32 = 0.3;
+
+// light underground and at the darkest point of the night
+const CAVE_LIGHT: f32 = 0.4;
+const MOON_LIGHT: f32 = 0.15;
+
+const FIRE_LIGHT: LightSource = LightSource {
+    color: Palette::COLOR_FIRE,
+    range: 5,
+};
+
+/// Ambient light at a turn, white at noon, orange around dawn and dusk and a dim blue at night
+pub fn daylight(turn: i32) -> RGBA {
+    let phase = (turn as f32 / DAY_LENGTH as f32 + START_OF_DAY).fract();
+    let sun = 0.5 - 0.5 * (phase * std::f32::consts::TAU).cos(); // 0 at midnight, 1 at noon
+
+    let level = MOON_LIGHT + (1. - MOON_LIGHT) * sun;
+    let warmth = 1. - (sun - 0.5).abs() * 2.; // strongest halfway between night and noon
+    RGBA::from_f32(
+        level,
+        level * (1. - 0.2 * warmth),
+        level * (1. - 0.4 * warmth) + 0.1 * (1. - sun),
+        1.,
+    )
+}
+
+// adds a light's glow to every tile it can see, fading out towards the edge of its range
+fn shine(map: &mut Map, center: Point, light: &LightSource) {
+    for p in rltk::field_of_view(center, light.range, &*map) {
+        if !map.in_bounds(p.x, p.y) {
+            continue;
+        }
+
+        let idx = map.point_idx(p);
+        if !map.is_awake(idx) {
+            continue;
+        }
+
+        let dist = rltk::DistanceAlg::Pythagoras.distance2d(center, p);
+        let strength = (1. - dist / (light.range as f32 + 1.)).max(0.);
+
+        let lit = &mut map.light[idx];
+        lit.r += light.color.r * strength;
+        lit.g += light.color.g * strength;
+        lit.b += light.color.b * strength;
+    }
+}
+
+pub fn run_lighting_system(
+    mut map: UniqueViewMut<Map>,
+    turn: UniqueView<Turn>,
+    settings: UniqueView<GameSettings>,
+    vpos: View<Position>,
+    vlight: View<LightSource>,
+    vequipped: View<Equipped>,
+    vstatuses: View<Statuses>,
+) {
+    let ambient = match settings.mode {
+        GameMode::VillageSim => daylight(turn.0),
+        _ => RGBA::from_f32(CAVE_LIGHT, CAVE_LIGHT, CAVE_LIGHT, 1.),
+    };
+
+    map.update_awake(|map, idx| map.light[idx] = ambient);
+
+    // placed lights shine from where they stand, carried ones from whoever has them in hand
+    let mut lights: Vec<(Point, LightSource)> = vec![];
+    for (light, equipped) in (&vlight, &vequipped).iter() {
+        if let Ok(pos) = vpos.get(equipped.owner) {
+            lights.push((pos.ps[0], *light));
+        }
+    }
+    for (light, pos) in (&vlight, &vpos).iter() {
+        lights.push((pos.ps[0], *light));
+    }
+
+    // anything burning lights up its surroundings
+    for (statuses, pos) in (&vstatuses, &vpos).iter() {
+        if statuses.has(StatusKind::Burning) {
+            lights.push((pos.ps[0], FIRE_LIGHT));
+        }
+    }
+    for idx in map.awake_tiles() {
+        if map.fire_turns[idx] > 0 {
+            lights.push((map.idx_point(idx), FIRE_LIGHT));
+        }
+    }
+
+    for (center, light) in lights.iter() {
+        if map.in_bounds(center.x, center.y) && map.is_awake(map.point_idx(*center)) {
+            shine(&mut map, *center, light);
+        }
+    }
+}
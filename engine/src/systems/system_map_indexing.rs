@@ -1,23 +1,63 @@
-use crate::components::{BlocksTile, Position};
+use crate::components::{Actor, ActorType, BlocksTile, BlocksVisibility, Door, Locomotive, Position};
 use crate::map::Map;
-use shipyard::{Get, IntoIter, IntoWithId, UniqueViewMut, View, ViewMut};
+use crate::spatial::SpatialIndex;
+use crate::uniques::PPoint;
+use shipyard::{Get, IntoIter, IntoWithId, UniqueView, UniqueViewMut, View, ViewMut};
+use std::collections::HashSet;
 
-pub fn run_map_indexing_system(mut map: UniqueViewMut<Map>, vpos: View<Position>, vblocks: ViewMut<BlocksTile>) {
+pub fn run_map_indexing_system(
+    mut map: UniqueViewMut<Map>,
+    mut index: UniqueViewMut<SpatialIndex>,
+    ppos: UniqueView<PPoint>,
+    vpos: View<Position>,
+    vactor: View<Actor>,
+    vloco: View<Locomotive>,
+    vblocks: ViewMut<BlocksTile>,
+    vblocksvis: View<BlocksVisibility>,
+    vdoor: View<Door>,
+) {
     map.set_blocked();
     map.clear_tile_content();
+    index.clear(map.width, map.height);
+
+    // the world keeps going around villagers and spawners too, not only wherever the player is looking
+    let mut wakers = vec![ppos.0];
+    let mut fixed_blockers = HashSet::new();
 
     for (id, pos) in vpos.iter().with_id() {
+        if let Ok(actor) = vactor.get(id) {
+            if actor.atype == ActorType::Villager || actor.atype == ActorType::Spawner {
+                wakers.push(pos.any_point());
+            }
+        }
+
+        // paths can go through a closed door anyone can open, the mover opens it when they get there
+        let passable_door = vdoor.get(id).map_or(false, |door| door.lock.is_none());
+
         for pos in pos.ps.iter() {
             let idx = map.xy_idx(pos.x, pos.y);
-            if idx > map.tiles.len() {
+            if idx >= map.tiles.len() || !map.is_awake(idx) {
                 continue;
             }
 
-            if let Ok(_bt) = vblocks.get(id) {
+            if vblocks.get(id).is_ok() && !passable_door {
                 map.blocked[idx] = true;
+                if vloco.get(id).is_err() {
+                    fixed_blockers.insert(idx);
+                }
+            }
+
+            if vblocksvis.get(id).is_ok() {
+                map.view_blocked[idx] = true;
             }
 
             map.tile_content[idx].push(id);
+            index.insert(id, *pos);
         }
     }
+
+    map.set_fixed_blockers(fixed_blockers);
+
+    // put distant chunks to sleep only after indexing, so they keep blocking what was on them
+    map.wake_around(&wakers);
 }
@@ -1,4 +1,6 @@
+use crate::effects::noise::MELEE_NOISE;
 use crate::effects::{EffectType, Targets};
+use crate::map::Map;
 use crate::palette::Palette;
 use crate::uniques::GameLog;
 use crate::{
@@ -6,10 +8,13 @@ use crate::{
     effects::add_effect,
     systems::system_particle::ParticleBuilder,
 };
-use shipyard::{AllStoragesView, EntityId, Get, IntoIter, IntoWithId, Remove, UniqueViewMut, View, ViewMut};
+use shipyard::{
+    AllStoragesView, EntityId, Get, IntoIter, IntoWithId, Remove, UniqueView, UniqueViewMut, View, ViewMut,
+};
 
 pub fn run_melee_combat_system(store: AllStoragesView) {
     let mut log = store.borrow::<UniqueViewMut<GameLog>>().unwrap();
+    let map = store.borrow::<UniqueView<Map>>().unwrap();
     let mut particle_builder = store.borrow::<UniqueViewMut<ParticleBuilder>>().unwrap();
 
     let mut vwants = store.borrow::<ViewMut<WantsToAttack>>().unwrap();
@@ -68,6 +73,14 @@ pub fn run_melee_combat_system(store: AllStoragesView) {
 
                     let pos = vpos.get(wants_attack.target);
                     if let Ok(pos) = pos {
+                        add_effect(
+                            Some(id),
+                            EffectType::Noise {
+                                tile_idx: map.point_idx(pos.ps[0]),
+                                volume: MELEE_NOISE,
+                            },
+                        );
+
                         for pos in pos.ps.iter() {
                             particle_builder.request(
                                 pos.x,
@@ -1,14 +1,24 @@
 use crate::components::{DijkstraMapToMe, Position};
 use crate::map::Map;
-use shipyard::{IntoIter, IntoWithId, UniqueView, View, ViewMut};
+use crate::path_cache::PathCache;
+use crate::uniques::Turn;
+use shipyard::{IntoIter, IntoWithId, UniqueView, UniqueViewMut, View, ViewMut};
+
+pub fn run_pathfinding_system(
+    mut map: UniqueViewMut<Map>,
+    turn: UniqueView<Turn>,
+    mut cache: UniqueViewMut<PathCache>,
+    vpos: View<Position>,
+    mut vmaps: ViewMut<DijkstraMapToMe>,
+) {
+    cache.refresh(&mut map, turn.0);
 
-pub fn run_pathfinding_system(map: UniqueView<Map>, vpos: View<Position>, mut vmaps: ViewMut<DijkstraMapToMe>) {
     for (_, (pos, dijkstra)) in (&vpos, &mut vmaps).iter().with_id() {
         let mut starts: Vec<usize> = vec![];
         for pos in pos.ps.iter() {
             starts.push(map.point_idx(*pos));
         }
 
-        dijkstra.map = rltk::DijkstraMap::new(map.width, map.height, &starts, &*map, 100.0);
+        dijkstra.map = cache.dijkstra(&map, &starts, 100.0);
     }
 }
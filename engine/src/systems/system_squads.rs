@@ -0,0 +1,129 @@
+use crate::ai::squads::RALLY_RADIUS;
+use crate::components::{Actor, CombatStats, Position, Squad, SquadMember, SquadState, Vision};
+use crate::effects::{add_effect, EffectType};
+use crate::spatial::SpatialIndex;
+use shipyard::{EntityId, Get, IntoIter, IntoWithId, UniqueView, View, ViewMut};
+
+// squads fall back once the health they have left drops below this fraction of their full strength
+const RETREAT_HP_RATIO: f32 = 0.4;
+
+// Keeps squad membership up to date and makes the decisions shared by the whole squad, members act on them in labors
+pub fn run_squad_system(
+    index: UniqueView<SpatialIndex>,
+    vpos: View<Position>,
+    vactor: View<Actor>,
+    vvs: View<Vision>,
+    vstats: View<CombatStats>,
+    vmember: View<SquadMember>,
+    mut vsquad: ViewMut<Squad>,
+) {
+    let mut to_remove: Vec<EntityId> = vec![];
+
+    for (id, squad) in (&mut vsquad).iter().with_id() {
+        // drop dead members
+        squad.members.retain(|m| {
+            vpos.get(*m).is_ok()
+                && match vmember.get(*m) {
+                    Ok(member) => member.squad == id,
+                    Err(_) => false,
+                }
+        });
+
+        if squad.members.is_empty() {
+            to_remove.push(id);
+            continue;
+        }
+
+        if !squad.members.contains(&squad.leader) {
+            squad.leader = squad.members[0];
+        }
+
+        let leader_pos = if let Ok(pos) = vpos.get(squad.leader) {
+            pos.ps[0]
+        } else {
+            continue;
+        };
+
+        let faction = if let Ok(actor) = vactor.get(squad.leader) {
+            actor.faction
+        } else {
+            continue;
+        };
+
+        // share everything the squad can see
+        let mut enemies: Vec<EntityId> = vec![];
+        for m in squad.members.iter() {
+            if let (Ok(vs), Ok(mpos)) = (vvs.get(*m), vpos.get(*m)) {
+                for (e, p) in index.within_with(&vstats, mpos.ps[0], vs.range as f32) {
+                    if !vs.is_visible(p) || enemies.contains(&e) {
+                        continue;
+                    }
+
+                    if let Ok(actor) = vactor.get(e) {
+                        if actor.faction != faction {
+                            enemies.push(e);
+                        }
+                    }
+                }
+            }
+        }
+
+        if let Some(target) = squad.target {
+            if !enemies.contains(&target) {
+                squad.target = None;
+            }
+        }
+
+        if squad.target.is_none() {
+            let mut best = f32::MAX;
+            for e in enemies.iter() {
+                if let Ok(epos) = vpos.get(*e) {
+                    let dist = rltk::DistanceAlg::Pythagoras.distance2d(leader_pos, epos.ps[0]);
+                    if dist < best {
+                        best = dist;
+                        squad.target = Some(*e);
+                    }
+                }
+            }
+        }
+
+        let mut hp = 0;
+        let mut max_hp = 0;
+        let mut rallied = 0;
+        for m in squad.members.iter() {
+            if let Ok(stats) = vstats.get(*m) {
+                hp += stats.hp;
+                max_hp += stats.max_hp;
+            }
+
+            if let Ok(pos) = vpos.get(*m) {
+                if rltk::DistanceAlg::Pythagoras.distance2d(leader_pos, pos.ps[0]) <= RALLY_RADIUS {
+                    rallied += 1;
+                }
+            }
+        }
+        // the dead count as no health at all, so a squad that's lost most of its members falls back too
+        squad.strength = i32::max(squad.strength, max_hp);
+        let health = if squad.strength > 0 {
+            hp as f32 / squad.strength as f32
+        } else {
+            1.0
+        };
+
+        squad.state = if squad.target.is_none() {
+            SquadState::Gathering
+        } else if health < RETREAT_HP_RATIO {
+            SquadState::Retreating
+        } else if squad.state == SquadState::Engaging
+            || rallied >= usize::min(squad.min_engage_size, squad.members.len())
+        {
+            SquadState::Engaging
+        } else {
+            SquadState::Gathering
+        };
+    }
+
+    for id in to_remove {
+        add_effect(None, EffectType::Delete { entity: id });
+    }
+}
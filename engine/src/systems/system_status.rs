@@ -0,0 +1,13 @@
+use crate::components::{Position, Statuses};
+use crate::effects::{add_effect, EffectType};
+use crate::map::Map;
+use shipyard::{IntoIter, IntoWithId, UniqueView, View};
+
+// every status ticks once a turn, statuses on anyone in a sleeping chunk wait until it wakes up
+pub fn run_status_system(map: UniqueView<Map>, vpos: View<Position>, vstatuses: View<Statuses>) {
+    for (id, (pos, _)) in (&vpos, &vstatuses).iter().with_id() {
+        if map.is_awake(map.point_idx(pos.any_point())) {
+            add_effect(Some(id), EffectType::TickStatuses { entity: id });
+        }
+    }
+}
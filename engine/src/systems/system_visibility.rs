@@ -1,8 +1,11 @@
 use crate::components::{Position, SpatialKnowledge, Vision};
 use crate::map::Map;
+use crate::systems::system_lighting::MIN_VISIBLE_LIGHT;
+use crate::utils::get_neighbors;
 use rltk;
 use rltk::Point;
 use shipyard::{Get, IntoIter, IntoWithId, UniqueView, View, ViewMut};
+use std::collections::HashSet;
 
 pub fn run_visibility_system(
     map: UniqueView<Map>,
@@ -12,12 +15,25 @@ pub fn run_visibility_system(
 ) {
     for (id, (pos, vs)) in (&vpos, &mut vvs).iter().with_id() {
         // if vs.dirty {
-        let pos = pos.ps.first().unwrap();
-
         vs.dirty = false;
-        vs.visible_tiles = rltk::field_of_view(Point::new(pos.x, pos.y), vs.range, &*map);
-        vs.visible_tiles
-            .retain(|p| p.x >= 0 && p.x < map.width && p.y >= 0 && p.y < map.height);
+
+        // a big body sees from all of its edge, the tiles inside it can't see anything the edge doesn't
+        let mut seen: HashSet<Point> = HashSet::new();
+        for p in pos.ps.iter() {
+            let edge = get_neighbors(*p).iter().any(|n| !pos.ps.contains(n));
+            if pos.ps.len() == 1 || edge {
+                seen.extend(rltk::field_of_view(*p, vs.range, &*map));
+            }
+        }
+
+        // dark tiles can only be made out by touch, right up against the body
+        let near = |p: &Point| pos.ps.iter().any(|b| (b.x - p.x).abs() <= 1 && (b.y - p.y).abs() <= 1);
+        vs.set_visible(
+            seen.into_iter()
+                .filter(|p| p.x >= 0 && p.x < map.width && p.y >= 0 && p.y < map.height)
+                .filter(|p| map.light_level(map.point_idx(*p)) >= MIN_VISIBLE_LIGHT || near(p))
+                .collect(),
+        );
 
         if let Ok(space) = (&mut vspace).get(id) {
             for vis in vs.visible_tiles.iter() {
@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+
+use rltk::{FontCharType, RGBA};
+use serde::Deserialize;
+
+use crate::components::LocomotionType;
+use crate::map::TileType;
+use crate::palette::Palette;
+
+/// How a kind of tile looks and behaves, every `TileType` has one in resources/tiles.json
+pub struct TileInfo {
+    pub name: String,
+    pub glyph: FontCharType,
+    pub fg: RGBA,
+    pub bg: RGBA,
+    pub opaque: bool,
+    pub walkable: bool,
+    pub wall: bool,
+    pub cost: f32,      // multiplies the cost of walking onto the tile
+    pub swim_cost: f32, // same for swimmers
+    pub flammable: bool,
+    pub fuel: i32, // turns the tile burns for once lit
+    pub burns_into: Option<TileType>,
+    pub swimmable: bool,
+    pub hardness: i32, // damage it takes to break the tile, 0 if it can't be broken
+    pub breaks_into: Option<TileType>,
+    pub drops: Option<String>, // what's left lying around once it breaks, by spawn name
+}
+
+fn yes() -> bool {
+    true
+}
+
+fn one() -> f32 {
+    1.
+}
+
+// a tile as written in the data file, anything left out is an ordinary open floor
+#[derive(Deserialize)]
+struct TileDef {
+    tile: TileType,
+    name: String,
+    glyph: char,
+    fg: String,
+    #[serde(default)]
+    bg: Option<String>,
+    #[serde(default)]
+    opaque: bool,
+    #[serde(default = "yes")]
+    walkable: bool,
+    #[serde(default)]
+    wall: bool,
+    #[serde(default = "one")]
+    cost: f32,
+    #[serde(default = "one")]
+    swim_cost: f32,
+    #[serde(default)]
+    flammable: bool,
+    #[serde(default)]
+    fuel: i32,
+    #[serde(default)]
+    burns_into: Option<TileType>,
+    #[serde(default)]
+    swimmable: bool,
+    #[serde(default)]
+    hardness: i32,
+    #[serde(default)]
+    breaks_into: Option<TileType>,
+    #[serde(default)]
+    drops: Option<String>,
+}
+
+// colors are named after the `Palette` constant they use
+fn color(name: &str) -> RGBA {
+    Palette::named(name).unwrap_or_else(|| panic!("No palette color named {} in tiles.json", name))
+}
+
+lazy_static! {
+    static ref TILES: HashMap<TileType, TileInfo> = {
+        let defs: Vec<TileDef> =
+            serde_json::from_str(include_str!("../../resources/tiles.json")).expect("Couldn't parse tiles.json");
+
+        defs.into_iter()
+            .map(|def| {
+                let info = TileInfo {
+                    name: def.name,
+                    glyph: rltk::to_cp437(def.glyph),
+                    fg: color(&def.fg),
+                    bg: def.bg.as_deref().map_or(Palette::MAIN_BG, color),
+                    opaque: def.opaque,
+                    walkable: def.walkable,
+                    wall: def.wall,
+                    cost: def.cost,
+                    swim_cost: def.swim_cost,
+                    flammable: def.flammable,
+                    fuel: def.fuel,
+                    burns_into: def.burns_into,
+                    swimmable: def.swimmable,
+                    hardness: def.hardness,
+                    breaks_into: def.breaks_into,
+                    drops: def.drops,
+                };
+                (def.tile, info)
+            })
+            .collect()
+    };
+}
+
+impl TileInfo {
+    pub fn move_cost(&self, mtype: LocomotionType) -> f32 {
+        match mtype {
+            LocomotionType::Ground => self.cost,
+            LocomotionType::Water => self.swim_cost,
+        }
+    }
+}
+
+lazy_static! {
+    // cheapest tiles to walk or swim onto, keeps the A* distance estimate from overshooting
+    static ref CHEAPEST_WALK: f32 = TILES
+        .values()
+        .filter(|info| info.walkable)
+        .map(|info| info.cost)
+        .fold(1., f32::min);
+    static ref CHEAPEST_SWIM: f32 = TILES
+        .values()
+        .filter(|info| info.swimmable)
+        .map(|info| info.swim_cost)
+        .fold(1., f32::min);
+}
+
+pub fn cheapest_cost(mtype: LocomotionType) -> f32 {
+    match mtype {
+        LocomotionType::Ground => *CHEAPEST_WALK,
+        LocomotionType::Water => *CHEAPEST_SWIM,
+    }
+}
+
+pub fn tile_info(tile: TileType) -> &'static TileInfo {
+    TILES
+        .get(&tile)
+        .unwrap_or_else(|| panic!("No entry for {:?} in tiles.json", tile))
+}
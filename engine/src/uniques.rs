@@ -20,3 +20,10 @@ pub struct PPoint(pub Point);
 
 #[derive(Clone, Debug, Unique, Copy)]
 pub struct FrameTime(pub f32);
+
+/// Which numpad direction the wind blows towards and how hard, from 0 to 1
+#[derive(Clone, Debug, Unique, Copy)]
+pub struct Wind {
+    pub dir: usize,
+    pub strength: f32,
+}
@@ -1,6 +1,8 @@
-use rltk::{BaseMap, DijkstraMap, NavigationPath, Point, RGBA};
+use rltk::{BaseMap, DijkstraMap, NavigationPath, Point, SmallVec, RGBA};
 
+use crate::components::LocomotionType;
 use crate::map::Map;
+use crate::tiles::cheapest_cost;
 
 /// returns the point adjacent to origin that will lead to target
 pub fn dijkstra_backtrace(dijkstra: DijkstraMap, map: &mut Map, origin: usize, mut target: usize) -> usize {
@@ -157,6 +159,114 @@ pub fn get_path(map: &Map, from: Point, tp: Point) -> NavigationPath {
     return path;
 }
 
+/// A* for a body of any size moving by `mtype`, every tile of it has to fit at each step of the path.
+/// The path is for the first point of the body, the rest follow it at the same offsets. Bodies bigger than a tile
+/// stop as soon as any part of them is next to `tp`, they rarely fit on top of what they're heading for.
+/// None if there's no body to move.
+pub fn get_footprint_path(map: &Map, body: &[Point], tp: Point, mtype: LocomotionType) -> Option<NavigationPath> {
+    let first = *body.first()?;
+    let footprint = Footprint {
+        map,
+        mtype,
+        offsets: body.iter().map(|p| Point::new(p.x - first.x, p.y - first.y)).collect(),
+        body: body.iter().map(|p| map.point_idx(*p)).collect(),
+        goal: map.point_idx(tp),
+    };
+
+    let mut path = rltk::a_star_search(map.point_idx(first) as i32, footprint.goal as i32, &footprint);
+
+    // the last step onto the goal is the free one from beside it, the body doesn't actually go there
+    let len = path.steps.len();
+    if path.success && len > 1 && footprint.touches_goal(path.steps[len - 2]) {
+        path.steps.pop();
+    }
+
+    Some(path)
+}
+
+// the map as seen by a body and the way it moves, exits are where its whole footprint fits
+struct Footprint<'a> {
+    map: &'a Map,
+    mtype: LocomotionType,
+    offsets: Vec<Point>,
+    body: Vec<usize>, // the body's own tiles are blocked by itself, it can always move into them
+    goal: usize,
+}
+
+impl Footprint<'_> {
+    // cost of the footprint's worst tile with its first point at x,y, or None if it doesn't fit there
+    fn fit(&self, x: i32, y: i32) -> Option<f32> {
+        let mut cost: f32 = 0.;
+        for offset in self.offsets.iter() {
+            let (tx, ty) = (x + offset.x, y + offset.y);
+            if !self.map.in_bounds(tx, ty) {
+                return None;
+            }
+
+            let idx = self.map.xy_idx(tx, ty);
+            if !self.map.is_passable(idx, self.mtype) || (self.map.blocked[idx] && !self.body.contains(&idx)) {
+                return None;
+            }
+
+            cost = cost.max(self.map.move_cost(idx, self.mtype));
+        }
+
+        Some(cost)
+    }
+
+    // whether a body bigger than a tile with its first point at idx has a tile next to the goal
+    fn touches_goal(&self, idx: usize) -> bool {
+        if self.offsets.len() <= 1 {
+            return false;
+        }
+
+        let p = self.map.idx_point(idx);
+        let goal = self.map.idx_point(self.goal);
+        self.offsets
+            .iter()
+            .any(|offset| (p.x + offset.x - goal.x).abs() <= 1 && (p.y + offset.y - goal.y).abs() <= 1)
+    }
+}
+
+impl BaseMap for Footprint<'_> {
+    // scaled down so quick ground can't make the A* estimate overshoot
+    fn get_pathing_distance(&self, idx1: usize, idx2: usize) -> f32 {
+        self.map.get_pathing_distance(idx1, idx2) * cheapest_cost(self.mtype)
+    }
+
+    fn get_available_exits(&self, idx: usize) -> SmallVec<[(usize, f32); 10]> {
+        let mut exits = SmallVec::new();
+        let p = self.map.idx_point(idx);
+
+        for dir in [1, 2, 3, 4, 6, 7, 8, 9].iter() {
+            let np = dir_to_point(p, *dir, 1);
+            let distance = if np.x != p.x && np.y != p.y { 1.45 } else { 1.0 };
+            if let Some(cost) = self.fit(np.x, np.y) {
+                exits.push((self.map.point_idx(np), distance * cost));
+            }
+        }
+
+        // a free step onto the goal from anywhere next to it, stripped off the end of the path afterwards
+        if self.touches_goal(idx) {
+            exits.push((self.goal, 0.));
+        }
+
+        exits
+    }
+}
+
+/// The point in a body closest to a target point
+pub fn closest_point(body: &[Point], tp: Point) -> Point {
+    *body
+        .iter()
+        .min_by(|a, b| {
+            let da = rltk::DistanceAlg::Pythagoras.distance2d(**a, tp);
+            let db = rltk::DistanceAlg::Pythagoras.distance2d(**b, tp);
+            da.partial_cmp(&db).unwrap()
+        })
+        .unwrap_or(&tp)
+}
+
 pub fn normalize(num: i32) -> i32 {
     if num == 0 {
         0
@@ -26,6 +26,7 @@ pub enum InputCommand {
     RevealMap,
     Fireball,
     UseStairs,
+    ToggleStealth,
 }
 
 impl InputCommand {
@@ -105,6 +106,11 @@ impl InputCommand {
                     RunState::AwaitingInput
                 }
             }
+            InputCommand::ToggleStealth => {
+                player::toggle_stealth(&world);
+
+                RunState::AwaitingInput
+            }
         };
     }
 }
@@ -130,6 +136,7 @@ pub fn map_keys(ctx: &Rltk, mode: GameMode) -> InputCommand {
                 VirtualKeyCode::W => InputCommand::Wait,
                 VirtualKeyCode::Escape => InputCommand::Escape,
                 VirtualKeyCode::Period => InputCommand::UseStairs,
+                VirtualKeyCode::S => InputCommand::ToggleStealth,
                 _ => InputCommand::None,
             },
         },
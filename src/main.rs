@@ -1,9 +1,10 @@
 use engine::components::{Ranged, WantsToDropItem, WantsToUnequipItem, WantsToUseItem};
+use engine::map_builders::{self, MapGenData, VIEWER_BUILDERS};
 use engine::systems::{system_cleanup, system_particle, system_visibility};
 use engine::uniques::{FrameTime, PlayerID, Turn};
-use engine::{effects, uniques, Engine, GameMode, GameSettings};
-use engine::{map_builders::MapGenData, SCALE, TILE_SIZE};
-use render::{camera, gui_menus};
+use engine::{effects, player, uniques, Engine, GameMode, GameSettings};
+use engine::{SCALE, SHOW_MAPGEN_ANIMATION, TILE_SIZE};
+use render::{camera, gui_menus, mapgen};
 use rltk::{GameState, Rltk, RltkBuilder, RGBA};
 use shipyard::{EntityId, Get, UniqueView, UniqueViewMut, ViewMut, World};
 
@@ -37,6 +38,12 @@ pub enum RunState {
     NextLevel,
     GameOver,
     MapGenAnimation,
+    MapGenMenu {
+        selection: usize,
+    },
+    MapGenViewer {
+        selection: usize,
+    },
 }
 
 pub struct State {
@@ -99,7 +106,13 @@ impl GameState for State {
                     new_runstate == RunState::PlayerTurn,
                     new_runstate == RunState::AiTurn,
                 );
-                new_runstate = RunState::AiTurn;
+
+                // a player with a whole turn banked from haste or quick ground goes again before the world does
+                new_runstate = if player::player_spends_banked_turn(&self.engine.world) {
+                    RunState::AwaitingInput
+                } else {
+                    RunState::AiTurn
+                };
             }
             RunState::AiTurn => {
                 {
@@ -111,7 +124,13 @@ impl GameState for State {
                     new_runstate == RunState::PlayerTurn,
                     new_runstate == RunState::AiTurn,
                 );
-                new_runstate = RunState::AwaitingInput;
+
+                // a player owing turns for rough ground or being slowed sits them out while everyone else carries on
+                new_runstate = if player::player_owes_turn(&self.engine.world) {
+                    RunState::AiTurn
+                } else {
+                    RunState::AwaitingInput
+                };
             }
             RunState::ShowInventory => {
                 let result = gui_menus::show_inventory(&self.engine.world, ctx);
@@ -190,17 +209,23 @@ impl GameState for State {
                     }
                     gui_menus::MainMenuResult::Selection { selected } => match selected {
                         gui_menus::MainMenuSelection::Roguelike => {
-                            self.engine.reset_engine(game_modes::get_settings(GameMode::RL));
+                            let history = self.engine.reset_engine(game_modes::get_settings(GameMode::RL));
+                            self.mapgen_data = MapGenData::new(history);
                             new_runstate = RunState::MapGenAnimation
                         }
                         gui_menus::MainMenuSelection::Simulator => {
-                            self.engine.reset_engine(game_modes::get_settings(GameMode::VillageSim));
+                            let history = self.engine.reset_engine(game_modes::get_settings(GameMode::VillageSim));
+                            self.mapgen_data = MapGenData::new(history);
                             new_runstate = RunState::MapGenAnimation
                         }
                         gui_menus::MainMenuSelection::OrcHalls => {
-                            self.engine.reset_engine(game_modes::get_settings(GameMode::OrcHalls));
+                            let history = self.engine.reset_engine(game_modes::get_settings(GameMode::OrcHalls));
+                            self.mapgen_data = MapGenData::new(history);
                             new_runstate = RunState::MapGenAnimation
                         }
+                        gui_menus::MainMenuSelection::MapGenViewer => {
+                            new_runstate = RunState::MapGenMenu { selection: 0 }
+                        }
                         gui_menus::MainMenuSelection::Exit => ::std::process::exit(0),
                     },
                 }
@@ -212,8 +237,8 @@ impl GameState for State {
                 };
             }
             RunState::NextLevel => {
-                Engine::next_level(&mut self.engine.world);
-                new_runstate = RunState::PreRun;
+                self.mapgen_data = MapGenData::new(Engine::next_level(&mut self.engine.world));
+                new_runstate = RunState::MapGenAnimation;
             }
             RunState::GameOver => {
                 let result = gui_menus::game_over(ctx);
@@ -228,25 +253,37 @@ impl GameState for State {
                 }
             }
             RunState::MapGenAnimation => {
-                new_runstate = RunState::PreRun;
+                if !SHOW_MAPGEN_ANIMATION {
+                    new_runstate = RunState::PreRun;
+                } else if let mapgen::PlaybackResult::Done = mapgen::mapgen_playback(ctx, &mut self.mapgen_data, true) {
+                    new_runstate = RunState::PreRun;
+                }
+            }
+            RunState::MapGenMenu { selection } => match mapgen::mapgen_menu(ctx, selection) {
+                mapgen::MapGenMenuResult::NoSelection { selected } => {
+                    new_runstate = RunState::MapGenMenu { selection: selected }
+                }
+                mapgen::MapGenMenuResult::Selected { selected } => {
+                    let mut builder = map_builders::viewer_builder(VIEWER_BUILDERS[selected], 1);
+                    builder.build_map();
 
-                // if !SHOW_MAPGEN_ANIMATION {
-                //     new_runstate = RunState::PreRun;
-                // } else {
-                //     ctx.cls();
-                //     // todo bring back mapgen rendering
-                //     // map::draw_map(&gs.mapgen_data.history[gs.mapgen_data.index], ctx);
-                //     gs.engine_controller.render(gs, ctx);
+                    let mut history = builder.get_map_history();
+                    history.push(builder.get_map());
+                    self.mapgen_data = MapGenData::new(history);
+                    self.mapgen_data.connectivity = builder.get_connectivity();
 
-                //     gs.mapgen_data.timer += ctx.frame_time_ms;
-                //     if gs.mapgen_data.timer > MAPGEN_FRAME_TIME {
-                //         gs.mapgen_data.timer = 0.0;
-                //         gs.mapgen_data.index += 1;
-                //         if gs.mapgen_data.index >= gs.mapgen_data.history.len() {
-                //             new_runstate = RunState::PreRun;
-                //         }
-                //     }
-                // }
+                    new_runstate = RunState::MapGenViewer { selection: selected };
+                }
+                mapgen::MapGenMenuResult::Cancel => {
+                    new_runstate = RunState::MainMenu {
+                        menu_selection: gui_menus::MainMenuSelection::MapGenViewer,
+                    }
+                }
+            },
+            RunState::MapGenViewer { selection } => {
+                if let mapgen::PlaybackResult::Done = mapgen::mapgen_playback(ctx, &mut self.mapgen_data, false) {
+                    new_runstate = RunState::MapGenMenu { selection };
+                }
             }
         }
 
@@ -257,7 +294,12 @@ impl GameState for State {
 
         //now render
         match self.state {
-            RunState::MainMenu { .. } | RunState::EscPressed | RunState::GameOver => {}
+            RunState::MainMenu { .. }
+            | RunState::EscPressed
+            | RunState::GameOver
+            | RunState::MapGenAnimation
+            | RunState::MapGenMenu { .. }
+            | RunState::MapGenViewer { .. } => {}
             _ => {
                 camera::render_game(&self.engine.world, ctx);
                 render::draw_gui(&self.engine.world, ctx);
@@ -271,6 +313,8 @@ fn main() -> rltk::BError {
     println!("==== Start game =========");
     println!("=========================");
 
+    engine::map_builders::load_prefabs();
+
     let xscaled = (WINDOWWIDTH as f32 / SCALE) as i32;
     let yscaled = (WINDOWHEIGHT as f32 / SCALE) as i32;
 
@@ -288,11 +332,7 @@ fn main() -> rltk::BError {
             world: World::new(),
             first_run: true,
         },
-        mapgen_data: MapGenData {
-            history: Vec::new(),
-            timer: 0.0,
-            index: 0,
-        },
+        mapgen_data: MapGenData::new(Vec::new()),
         state: RunState::MainMenu {
             menu_selection: gui_menus::MainMenuSelection::Roguelike,
         },
@@ -1,8 +1,10 @@
 use engine::{
     components::{Player, Renderable},
+    fluids::{fluid_info, ALL_FLUIDS},
     map::TileType,
     palette::Palette,
     player::{get_player_map_knowledge, get_player_viewshed},
+    tiles::tile_info,
     uniques::PPoint,
     utils::Scale,
     GameSettings, SCALE,
@@ -15,6 +17,9 @@ use shipyard::{Get, IntoIter, IntoWithId, UniqueView, View, World};
 const SHOW_BOUNDARIES: bool = true;
 const RENDER_DJIKSTRA: bool = false;
 
+// how much of a color still shows in total darkness, so the map never goes fully black
+const DARK_FLOOR: f32 = 0.25;
+
 pub fn render_game(world: &World, ctx: &mut Rltk) {
     let world = &world;
     // let res = &gs.resources;
@@ -50,6 +55,9 @@ pub fn render_game(world: &World, ctx: &mut Rltk) {
                     if settings.use_player_los && !player_vs.is_visible(p) {
                         fg = fg.scaled(0.5);
                         bg = bg.scaled(0.5);
+                    } else {
+                        fg = lit(fg, map.light[idx]);
+                        bg = lit(bg, map.light[idx]);
                     }
 
                     ctx.set(x, y, fg, bg, glyph);
@@ -83,6 +91,7 @@ pub fn render_game(world: &World, ctx: &mut Rltk) {
                 let idx = map.xy_idx(pos.x, pos.y);
                 if pos.y > min_y - 1 && pos.x > min_x - 1 && (!settings.use_player_los || player_vs.is_visible(*pos)) {
                     let (_, _, bgcolor) = get_tile_glyph(idx, &*map);
+                    let (fgcolor, bgcolor) = (lit(render.fg, map.light[idx]), lit(bgcolor, map.light[idx]));
 
                     let entity_screen_x = xoff as i32 + pos.x - min_x;
                     let entity_screen_y = yoff as i32 + pos.y - min_y;
@@ -91,7 +100,7 @@ pub fn render_game(world: &World, ctx: &mut Rltk) {
                         && entity_screen_y > 0
                         && entity_screen_y < size.1 as i32
                     {
-                        ctx.set(entity_screen_x, entity_screen_y, render.fg, bgcolor, render.glyph);
+                        ctx.set(entity_screen_x, entity_screen_y, fgcolor, bgcolor, render.glyph);
                     }
                 }
             }
@@ -101,70 +110,66 @@ pub fn render_game(world: &World, ctx: &mut Rltk) {
     ctx.set_active_console(0);
 }
 
-fn get_tile_glyph(idx: usize, map: &Map) -> (rltk::FontCharType, RGBA, RGBA) {
-    let mut glyph = rltk::to_cp437(' ');
-    let fg;
-    let mut bg = Palette::MAIN_BG;
+/// Draws a bare map with no entities or line of sight, for watching map generation
+pub fn render_map(map: &Map, ctx: &mut Rltk) {
+    ctx.set_active_console(1);
 
-    match map.tiles[idx] {
-        TileType::Floor => {
-            fg = Palette::COLOR_GREEN_DARK;
-            glyph = rltk::to_cp437('·');
-        }
-        TileType::Wall => {
-            fg = Palette::MAIN_FG;
-            glyph = rltk::to_cp437('#');
-        }
-        TileType::StairsDown => {
-            fg = Palette::MAIN_FG;
-            glyph = rltk::to_cp437('>');
-        }
-        TileType::StairsUp => {
-            fg = Palette::MAIN_FG;
-            glyph = rltk::to_cp437('<');
-        }
-        TileType::Grass => {
-            fg = Palette::COLOR_GREEN;
-            bg = Palette::COLOR_GREEN_DARK;
-            // glyph = rltk::to_cp437('"');
-        }
-        TileType::Wheat => {
-            fg = Palette::COLOR_AMBER;
-            // let gs = vec!['|', '{', '}'];
-            // let c = gs.choose(&mut rand::thread_rng()).unwrap();
-            let c = '{';
-            glyph = rltk::to_cp437(c);
-        }
-        TileType::Dirt => {
-            fg = Palette::COLOR_DIRT;
-            glyph = rltk::to_cp437('.');
-        }
-        TileType::Water => {
-            fg = Palette::COLOR_WATER;
-            bg = Palette::COLOR_WATER;
-            bg = bg.scaled(0.7);
-            glyph = rltk::to_cp437('~');
-        }
-        TileType::WoodWall => {
-            fg = Palette::COLOR_WOOD;
-            glyph = rltk::to_cp437('#');
-        }
-        TileType::WoodDoor => {
-            fg = Palette::COLOR_WOOD;
-            glyph = rltk::to_cp437('+');
-        }
-        TileType::WoodFloor => {
-            fg = Palette::COLOR_WOOD;
-            glyph = rltk::to_cp437('.');
+    let xoff = (OFFSET_X as f32 / SCALE).ceil() as usize;
+    let yoff = (OFFSET_Y as f32 / SCALE).ceil() as usize;
+    let center = Point {
+        x: map.width / 2,
+        y: map.height / 2,
+    };
+    let (min_x, max_x, min_y, max_y) = super::get_map_coords_for_screen(center, ctx, (map.width, map.height));
+
+    let mut y = yoff;
+    for ty in min_y..=max_y {
+        let mut x = xoff;
+        for tx in min_x..=max_x {
+            if tx >= 0 && tx < map.width && ty >= 0 && ty < map.height {
+                let (glyph, fg, bg) = get_tile_glyph(map.xy_idx(tx, ty), map);
+                ctx.set(x, y, fg, bg, glyph);
+            }
+            x += 1;
         }
+        y += 1;
     }
 
+    ctx.set_active_console(0);
+}
+
+// tints a color by the light falling on it
+fn lit(color: RGBA, light: RGBA) -> RGBA {
+    let channel = |c: f32, l: f32| c * (DARK_FLOOR + (1. - DARK_FLOOR) * l.min(1.));
+    RGBA::from_f32(
+        channel(color.r, light.r),
+        channel(color.g, light.g),
+        channel(color.b, light.b),
+        color.a,
+    )
+}
+
+fn get_tile_glyph(idx: usize, map: &Map) -> (rltk::FontCharType, RGBA, RGBA) {
+    let info = tile_info(map.tiles[idx]);
+    let mut glyph = info.glyph;
+    let fg = info.fg;
+    let mut bg = info.bg;
+
     if map.fire_turns[idx] > 0 {
         // TODO check if player knows about fire
         bg = Palette::COLOR_FIRE;
         glyph = rltk::to_cp437('^');
     }
 
+    // gases and puddles tint the tile, fully once they're thick
+    for fluid in ALL_FLUIDS.iter() {
+        let amount = map.fluids[idx][*fluid];
+        if amount > 0. {
+            let info = fluid_info(*fluid);
+            bg = bg.lerp(info.color, (amount / info.thick).min(1.));
+        }
+    }
+
     match map.tiles[idx] {
         TileType::Floor | TileType::Grass => {
             if RENDER_DJIKSTRA && map.dijkstra_map[idx] >= 0.0 {
@@ -23,6 +23,7 @@ pub enum MainMenuSelection {
     Roguelike,
     Simulator,
     OrcHalls,
+    MapGenViewer,
     Exit,
 }
 
@@ -69,7 +70,13 @@ pub fn main_menu(ctx: &mut Rltk, runstate: RunState) -> MainMenuResult {
             Palette::MAIN_BG,
             "Orc Halls",
         );
-        ctx.print_color_centered(40, get_fg(selection, MainMenuSelection::Exit), Palette::MAIN_BG, "Exit");
+        ctx.print_color_centered(
+            40,
+            get_fg(selection, MainMenuSelection::MapGenViewer),
+            Palette::MAIN_BG,
+            "Map Gen Viewer",
+        );
+        ctx.print_color_centered(45, get_fg(selection, MainMenuSelection::Exit), Palette::MAIN_BG, "Exit");
 
         match ctx.key {
             None => return MainMenuResult::NoSelection { selected: selection },
@@ -83,13 +90,13 @@ pub fn main_menu(ctx: &mut Rltk, runstate: RunState) -> MainMenuResult {
                     VirtualKeyCode::Up => {
                         let sel: i8 = selection.into();
                         // TODO: use len of menu selections instead of hard coded 3
-                        let new_sel = MainMenuSelection::try_from((sel - 1i8).rem_euclid(4)).unwrap();
+                        let new_sel = MainMenuSelection::try_from((sel - 1i8).rem_euclid(5)).unwrap();
                         return MainMenuResult::NoSelection { selected: new_sel };
                     }
                     VirtualKeyCode::Down => {
                         let sel: i8 = selection.into();
                         // TODO: use len of menu selections instead of hard coded 3
-                        let new_sel = MainMenuSelection::try_from((sel + 1i8).rem_euclid(4)).unwrap();
+                        let new_sel = MainMenuSelection::try_from((sel + 1i8).rem_euclid(5)).unwrap();
                         return MainMenuResult::NoSelection { selected: new_sel };
                     }
                     VirtualKeyCode::Return => return MainMenuResult::Selection { selected: selection },
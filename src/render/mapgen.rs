@@ -0,0 +1,127 @@
+use engine::map_builders::{MapGenData, VIEWER_BUILDERS};
+use engine::palette::Palette;
+use engine::MAPGEN_FRAME_TIME;
+use rltk::{Rltk, VirtualKeyCode};
+
+use super::camera::render_map;
+
+pub enum MapGenMenuResult {
+    NoSelection { selected: usize },
+    Selected { selected: usize },
+    Cancel,
+}
+
+pub enum PlaybackResult {
+    Playing,
+    Done,
+}
+
+pub fn mapgen_menu(ctx: &mut Rltk, selection: usize) -> MapGenMenuResult {
+    ctx.print_color_centered(10, Palette::COLOR_GREEN_DARK, Palette::MAIN_BG, "Select a map builder");
+
+    for (i, name) in VIEWER_BUILDERS.iter().enumerate() {
+        let fg = if i == selection {
+            Palette::COLOR_RED
+        } else {
+            Palette::MAIN_FG
+        };
+        ctx.print_color_centered(14 + 2 * i as i32, fg, Palette::MAIN_BG, name);
+    }
+
+    let count = VIEWER_BUILDERS.len();
+    match ctx.key {
+        None => MapGenMenuResult::NoSelection { selected: selection },
+        Some(key) => match key {
+            VirtualKeyCode::Escape => MapGenMenuResult::Cancel,
+            VirtualKeyCode::Up => MapGenMenuResult::NoSelection {
+                selected: (selection + count - 1) % count,
+            },
+            VirtualKeyCode::Down => MapGenMenuResult::NoSelection {
+                selected: (selection + 1) % count,
+            },
+            VirtualKeyCode::Return => MapGenMenuResult::Selected { selected: selection },
+            _ => MapGenMenuResult::NoSelection { selected: selection },
+        },
+    }
+}
+
+/// Plays back a map's build history, with `stop_at_end` it finishes by itself after the last step
+pub fn mapgen_playback(ctx: &mut Rltk, data: &mut MapGenData, stop_at_end: bool) -> PlaybackResult {
+    if data.history.is_empty() {
+        return PlaybackResult::Done;
+    }
+    let last = data.history.len() - 1;
+
+    match ctx.key {
+        Some(VirtualKeyCode::Space) => data.paused = !data.paused,
+        Some(VirtualKeyCode::Left) => {
+            data.paused = true;
+            data.index = data.index.saturating_sub(1);
+        }
+        Some(VirtualKeyCode::Right) => {
+            data.paused = true;
+            data.index = usize::min(data.index + 1, last);
+        }
+        Some(VirtualKeyCode::R) | Some(VirtualKeyCode::Home) => {
+            data.index = 0;
+            data.timer = 0.0;
+        }
+        Some(VirtualKeyCode::End) => data.index = last,
+        Some(VirtualKeyCode::Return) | Some(VirtualKeyCode::Escape) => return PlaybackResult::Done,
+        _ => {}
+    }
+
+    if !data.paused {
+        data.timer += ctx.frame_time_ms;
+        if data.timer > MAPGEN_FRAME_TIME {
+            data.timer = 0.0;
+            if data.index < last {
+                data.index += 1;
+            } else if stop_at_end {
+                return PlaybackResult::Done;
+            }
+        }
+    }
+
+    render_map(&data.history[data.index], ctx);
+
+    let status = if data.paused { "paused" } else { "playing" };
+    ctx.print_color(
+        1,
+        1,
+        Palette::MAIN_FG,
+        Palette::MAIN_BG,
+        format!(
+            "Map generation step {} / {} ({})",
+            data.index + 1,
+            data.history.len(),
+            status
+        ),
+    );
+    if let Some(report) = &data.connectivity {
+        ctx.print_color(
+            1,
+            2,
+            Palette::MAIN_FG,
+            Palette::MAIN_BG,
+            format!(
+                "{:.0}% of {} tiles reachable, {} of {} regions joined, {} tiles dug, {} culled",
+                report.reachable_ratio() * 100.,
+                report.open_tiles,
+                report.regions_connected,
+                report.regions.saturating_sub(1),
+                report.tiles_dug,
+                report.tiles_culled
+            ),
+        );
+    }
+    ctx.print_color(
+        1,
+        3,
+        Palette::MAIN_FG,
+        Palette::MAIN_BG,
+        "[space] pause  [left/right] step  [r] rewind  [end] last step  [enter] done",
+    );
+
+    PlaybackResult::Playing
+}
@@ -1,13 +1,14 @@
 use crate::uniques::GameLog;
 use crate::{WINDOWHEIGHT, WINDOWWIDTH};
 use engine::ai::decisions::Intent;
-use engine::components::{CombatStats, Fire, Inventory, Name, Position, Vision};
+use engine::components::{CombatStats, Inventory, Name, Position, Status, StatusKind, Statuses, Vision};
 use engine::map::Map;
 use engine::palette::Palette;
+use engine::path_cache::PathCache;
 use engine::player::get_player_map_knowledge;
 use engine::uniques::{FrameTime, PPoint, PlayerID, Turn};
 use engine::{GameSettings, OFFSET_X, OFFSET_Y, SCALE};
-use rltk::{Point, Rltk, VirtualKeyCode, RGB};
+use rltk::{Point, Rltk, VirtualKeyCode, RGB, RGBA};
 use shipyard::{Get, UniqueView, View, World};
 
 pub mod camera;
@@ -15,6 +16,8 @@ pub use camera::*;
 
 pub mod gui_menus;
 
+pub mod mapgen;
+
 /*
 Render strategy:
 Background color shows material
@@ -67,13 +70,23 @@ pub fn draw_gui(world: &World, ctx: &mut Rltk) {
         format!("Depth: {}", map.depth),
     );
 
-    // On fire display
-    let vfire = world.borrow::<View<Fire>>().unwrap();
-    match vfire.get(player_id) {
-        Ok(_) => {
-            ctx.print_color(1, 3, Palette::MAIN_FG, Palette::COLOR_FIRE, "FIRE");
+    let player_pos = world.borrow::<UniqueView<PPoint>>().unwrap().0;
+    if let Some(region) = map.region_name(map.point_idx(player_pos)) {
+        ctx.print_color(1, 10, Palette::MAIN_FG, Palette::MAIN_BG, region);
+    }
+
+    // whatever the player is suffering from, in the rows between the turn counter and the depth
+    let vstatuses = world.borrow::<View<Statuses>>().unwrap();
+    if let Ok(statuses) = vstatuses.get(player_id) {
+        for (i, status) in statuses.active.iter().take(6).enumerate() {
+            ctx.print_color(
+                1,
+                3 + i as i32,
+                status_color(status.kind),
+                Palette::MAIN_BG,
+                status_text(status),
+            );
         }
-        Err(_) => {}
     }
 
     for y in 0..WINDOWHEIGHT {
@@ -130,6 +143,7 @@ pub fn draw_tooltips(world: &World, ctx: &mut Rltk) {
     let vstats = world.borrow::<View<CombatStats>>().unwrap();
     let vinv = world.borrow::<View<Inventory>>().unwrap();
     let vintent = world.borrow::<View<Intent>>().unwrap();
+    let vstatuses = world.borrow::<View<Statuses>>().unwrap();
 
     let mut ypos = OFFSET_Y;
 
@@ -155,6 +169,24 @@ pub fn draw_tooltips(world: &World, ctx: &mut Rltk) {
         format!("Frametime: {:?}", frametime),
     );
 
+    // how well the path cache is keeping up, hits out of all queries
+    let paths = world.borrow::<UniqueView<PathCache>>().unwrap().stats;
+    ypos += 1;
+    ctx.print_color(
+        1,
+        ypos,
+        Palette::MAIN_FG,
+        Palette::MAIN_BG,
+        format!(
+            "Paths: {}/{} Dijkstra: {}/{} Dropped: {}",
+            paths.path_hits,
+            paths.path_hits + paths.path_misses,
+            paths.dijkstra_hits,
+            paths.dijkstra_hits + paths.dijkstra_misses,
+            paths.invalidated
+        ),
+    );
+
     /* Normal stuff */
     ypos += 2;
     ctx.print_color(1, ypos, Palette::MAIN_FG, Palette::MAIN_BG, "Tile:");
@@ -199,6 +231,19 @@ pub fn draw_tooltips(world: &World, ctx: &mut Rltk) {
             );
         }
 
+        if let Ok(statuses) = vstatuses.get(*e) {
+            for status in statuses.active.iter() {
+                ypos += 1;
+                ctx.print_color(
+                    2,
+                    ypos,
+                    status_color(status.kind),
+                    Palette::MAIN_BG,
+                    status_text(status),
+                );
+            }
+        }
+
         if let Ok(intent) = vintent.get(*e) {
             ypos += 1;
             ctx.print_color(
@@ -288,6 +333,26 @@ pub fn draw_tooltips(world: &World, ctx: &mut Rltk) {
     // }
 }
 
+fn status_text(status: &Status) -> String {
+    if status.stacks > 1 {
+        format!("{} x{} ({})", status.kind.name(), status.stacks, status.turns)
+    } else {
+        format!("{} ({})", status.kind.name(), status.turns)
+    }
+}
+
+fn status_color(kind: StatusKind) -> RGBA {
+    match kind {
+        StatusKind::Poisoned => Palette::COLOR_GREEN,
+        StatusKind::Burning => Palette::COLOR_FIRE,
+        StatusKind::Confused => Palette::COLOR_PURPLE,
+        StatusKind::Slowed => Palette::COLOR_WATER,
+        StatusKind::Hasted => Palette::COLOR_AMBER,
+        StatusKind::Regenerating => Palette::COLOR_RED,
+        StatusKind::Stunned => Palette::MAIN_FG,
+    }
+}
+
 pub fn get_map_coords_for_screen(focus: Point, ctx: &mut Rltk, mapsize: (i32, i32)) -> (i32, i32, i32, i32) {
     let (mut x_chars, mut y_chars) = ctx.get_char_size();
     x_chars -= (OFFSET_X as f32 / SCALE).ceil() as u32;